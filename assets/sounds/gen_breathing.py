@@ -0,0 +1,53 @@
+"""Synthesise the heavy-breathing loop (assets/sounds/heavy_breathing.wav).
+
+Filtered noise shaped into a gasp in and a longer puff out, silent at both
+ends so it loops cleanly. Run from the repo root: python3 assets/sounds/gen_breathing.py
+"""
+import math
+import random
+import struct
+import wave
+from pathlib import Path
+
+RATE = 22050
+OUT = Path(__file__).with_name("heavy_breathing.wav")
+
+
+def envelope(t, start, length, attack):
+    if t < start or t > start + length:
+        return 0.0
+    x = (t - start) / length
+    rise = min(1.0, x / attack)
+    fall = min(1.0, (1.0 - x) / (1.0 - attack))
+    return math.sin(rise * math.pi / 2) * math.sin(fall * math.pi / 2)
+
+
+def main():
+    rng = random.Random(43)
+    total = 1.6
+    # (start, length, attack, loudness, brightness): a sharp gasp in, a long breath out
+    phases = [(0.05, 0.45, 0.35, 0.55, 0.35), (0.6, 0.85, 0.15, 0.8, 0.18)]
+    low = band = 0.0
+    samples = []
+    for i in range(int(total * RATE)):
+        t = i / RATE
+        noise = rng.uniform(-1.0, 1.0)
+        value = 0.0
+        for start, length, attack, loud, bright in phases:
+            env = envelope(t, start, length, attack)
+            if env > 0.0:
+                # two one-pole filters give a breathy band around a few hundred Hz
+                low += bright * (noise - low)
+                band += 0.08 * (low - band)
+                value += (low - band) * env * loud
+        samples.append(max(-1.0, min(1.0, value * 2.2)))
+
+    with wave.open(str(OUT), "wb") as out:
+        out.setnchannels(1)
+        out.setsampwidth(2)
+        out.setframerate(RATE)
+        out.writeframes(b"".join(struct.pack("<h", int(s * 32000)) for s in samples))
+
+
+if __name__ == "__main__":
+    main()
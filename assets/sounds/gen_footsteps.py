@@ -0,0 +1,46 @@
+"""Synthesise the phantom footsteps (assets/sounds/footsteps_behind.wav).
+
+Five heel-strikes on a hard floor, each a low thump with a short scuff of
+noise, getting louder as whoever it is closes in. Run from the repo root:
+python3 assets/sounds/gen_footsteps.py
+"""
+import math
+import random
+import struct
+import wave
+from pathlib import Path
+
+RATE = 22050
+OUT = Path(__file__).with_name("footsteps_behind.wav")
+
+
+def main():
+    rng = random.Random(45)
+    steps = 5
+    gap = 0.55
+    total = steps * gap + 0.4
+    samples = [0.0] * int(total * RATE)
+    for n in range(steps):
+        start = int((0.1 + n * gap + rng.uniform(-0.03, 0.03)) * RATE)
+        loud = 0.35 + 0.65 * n / (steps - 1)
+        pitch = rng.uniform(70.0, 90.0)
+        low = 0.0
+        for i in range(int(0.25 * RATE)):
+            t = i / RATE
+            # the heel: a fast-decaying low sine
+            thump = math.sin(2 * math.pi * pitch * t) * math.exp(-t * 28.0)
+            # the sole: muffled noise, a little later and shorter
+            low += 0.25 * (rng.uniform(-1.0, 1.0) - low)
+            scuff = low * math.exp(-max(0.0, t - 0.02) * 45.0) * (t > 0.015)
+            if start + i < len(samples):
+                samples[start + i] += (thump + 0.5 * scuff) * loud
+    peak = max(abs(s) for s in samples) or 1.0
+    with wave.open(str(OUT), "wb") as out:
+        out.setnchannels(1)
+        out.setsampwidth(2)
+        out.setframerate(RATE)
+        out.writeframes(b"".join(struct.pack("<h", int(s / peak * 0.8 * 32000)) for s in samples))
+
+
+if __name__ == "__main__":
+    main()
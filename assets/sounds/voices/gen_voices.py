@@ -0,0 +1,84 @@
+"""Synthesise the dialogue babble lines (assets/sounds/voices/*.wav).
+
+Each voice is a glottal buzz at its own pitch pushed through two vowel
+resonators, chopped into syllables. No words, just the cadence of someone
+talking. Run from the repo root: python3 assets/sounds/voices/gen_voices.py
+"""
+import math
+import random
+import struct
+import wave
+from pathlib import Path
+
+RATE = 22050
+OUT = Path(__file__).parent
+LINES_PER_VOICE = 3
+
+# id: (pitch Hz, formant scale, syllables per second, roughness)
+VOICES = {
+    "student_high": (240.0, 1.15, 6.5, 0.02),
+    "student_low": (150.0, 1.0, 6.0, 0.03),
+    "trader_gruff": (105.0, 0.9, 4.5, 0.12),
+    "trader_warm": (205.0, 1.05, 5.0, 0.03),
+    "adult_low": (115.0, 0.95, 4.8, 0.05),
+    "adult_high": (195.0, 1.1, 5.2, 0.03),
+}
+
+# rough first and second formants of a few vowels
+VOWELS = [(730, 1090), (530, 1840), (270, 2290), (570, 840), (300, 870), (660, 1720)]
+
+
+class Resonator:
+    """Two-pole band-pass around one formant"""
+
+    def __init__(self, freq, width):
+        r = math.exp(-math.pi * width / RATE)
+        self.a1 = 2 * r * math.cos(2 * math.pi * freq / RATE)
+        self.a2 = -r * r
+        self.gain = 1 - r
+        self.y1 = self.y2 = 0.0
+
+    def __call__(self, x):
+        y = self.gain * x + self.a1 * self.y1 + self.a2 * self.y2
+        self.y2, self.y1 = self.y1, y
+        return y
+
+
+def line(rng, pitch, scale, rate, rough):
+    syllables = rng.randint(4, 8)
+    samples = []
+    phase = 0.0
+    for n in range(syllables):
+        length = rng.uniform(0.7, 1.3) / rate
+        f1, f2 = rng.choice(VOWELS)
+        r1, r2 = Resonator(f1 * scale, 90), Resonator(f2 * scale, 120)
+        # the pitch falls towards the end of the line, and wobbles a little per syllable
+        f0 = pitch * (1.1 - 0.25 * n / syllables) * rng.uniform(0.92, 1.08)
+        count = int(length * RATE)
+        for i in range(count):
+            x = i / count
+            env = math.sin(math.pi * min(1.0, x / 0.85)) ** 0.6 if x < 0.85 else 0.0
+            phase = (phase + f0 / RATE) % 1.0
+            source = (1.0 - 2.0 * phase) + rng.uniform(-rough, rough) * 8
+            samples.append((r1(source) + 0.6 * r2(source)) * env)
+        # a short gap between some syllables
+        if rng.random() < 0.3:
+            samples.extend([0.0] * int(0.06 * RATE))
+    peak = max(abs(s) for s in samples) or 1.0
+    return [s / peak * 0.7 for s in samples]
+
+
+def main():
+    for voice, (pitch, scale, rate, rough) in VOICES.items():
+        rng = random.Random(voice)
+        for k in range(1, LINES_PER_VOICE + 1):
+            samples = line(rng, pitch, scale, rate, rough)
+            with wave.open(str(OUT / f"{voice}_{k}.wav"), "wb") as out:
+                out.setnchannels(1)
+                out.setsampwidth(2)
+                out.setframerate(RATE)
+                out.writeframes(b"".join(struct.pack("<h", int(s * 32000)) for s in samples))
+
+
+if __name__ == "__main__":
+    main()
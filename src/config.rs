@@ -1,9 +1,12 @@
 // global config constants
 pub const SAVE_DIR: &str = "Save game";
 pub const AUTOSAVE_FILE: &str = "autosave.saved_escape";
+pub const CHECKPOINT_FILE: &str = "checkpoint.saved_escape";
 pub const DEFAULT_SAVE_EXT: &str = ".saved_escape";
 
 // performance tuning
-pub const SIMULATION_RADIUS: f32 = 220.0; // only simulate entities within this radius of player
+pub const SIMULATION_RADIUS: f32 = 220.0; // beyond this entities are dormant (schedule bookkeeping only)
+pub const FULL_SIM_RADIUS: f32 = 70.0; // full AI inside this, coarse movement out to SIMULATION_RADIUS
+pub const LOD_HYSTERESIS: f32 = 10.0; // how far past a tier boundary an entity must go to switch
 pub const CAR_SPAWN_RADIUS: f32 = 120.0;
 pub const CAR_DESPAWN_RADIUS: f32 = 300.0;
@@ -0,0 +1,357 @@
+use bevy::gltf::Gltf;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::core::lod::{SimLod, SimTier};
+use crate::core::navigation::NavAgent;
+use crate::core::npc_ai::{Npc, NpcState};
+use crate::narrative::ActiveTimeline;
+
+/// Rigged character GLBs under `assets/`
+pub const CHARACTER_MODELS: [&str; 7] = [
+    "Man.glb",
+    "Man in Suit.glb",
+    "Animated Woman.glb",
+    "Farmer.glb",
+    "Worker.glb",
+    "Worker Female.glb",
+    "Punk.glb",
+];
+
+/// Cross-fade between clips
+const BLEND_SECS: f32 = 0.25;
+/// Below this (m/s) a character is standing still
+const MOVING_SPEED: f32 = 0.15;
+/// At or above this (m/s) a character runs rather than walks
+const RUN_SPEED: f32 = 2.5;
+/// Ground speeds the walk and run clips look right at; playback is scaled from these
+const WALK_CLIP_SPEED: f32 = 1.4;
+const RUN_CLIP_SPEED: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimState {
+    Idle,
+    Walk,
+    Run,
+    Interact,
+    Scared,
+}
+
+/// Clip name without the armature prefix, lower case: "CharacterArmature|Run" -> "run"
+pub fn short_clip_name(name: &str) -> String {
+    name.rsplit('|').next().unwrap_or(name).to_lowercase()
+}
+
+/// Which state a clip suits and how well (0 is the best fit). The packs name
+/// clips differently ("Man_Walk", "Walking", "Walk"), so match loosely.
+pub fn classify_clip(name: &str) -> Option<(AnimState, u8)> {
+    let short = short_clip_name(name);
+    let short = short.strip_prefix("man_").unwrap_or(&short);
+    let fit = match short {
+        "idle" => (AnimState::Idle, 0),
+        "idle_neutral" | "standing" => (AnimState::Idle, 1),
+        "walk" | "walking" => (AnimState::Walk, 0),
+        "run" | "running" => (AnimState::Run, 0),
+        "interact" => (AnimState::Interact, 0),
+        "pickup" => (AnimState::Interact, 1),
+        "wave" | "clapping" => (AnimState::Interact, 2),
+        "hitrecieve" | "hitreceive" => (AnimState::Scared, 0),
+        "hitrecieve_2" | "hitreceive_2" => (AnimState::Scared, 1),
+        _ => return None,
+    };
+    Some(fit)
+}
+
+/// Best clip name for each state out of a model's clips
+pub fn pick_clips<'a>(names: impl IntoIterator<Item = &'a str>) -> HashMap<AnimState, &'a str> {
+    let mut best: HashMap<AnimState, (u8, &'a str)> = HashMap::new();
+    for name in names {
+        if let Some((state, fit)) = classify_clip(name) {
+            if best.get(&state).is_none_or(|(b, _)| fit < *b) {
+                best.insert(state, (fit, name));
+            }
+        }
+    }
+    best.into_iter().map(|(state, (_, name))| (state, name)).collect()
+}
+
+/// The clips one character model offers, as nodes of one animation graph
+#[derive(Debug, Clone, Default)]
+pub struct ClipSet {
+    pub graph: Handle<AnimationGraph>,
+    pub states: HashMap<AnimState, AnimationNodeIndex>,
+    /// Every clip by `short_clip_name`, for scripted playback
+    pub named: HashMap<String, AnimationNodeIndex>,
+}
+
+impl ClipSet {
+    fn from_gltf(gltf: &Gltf, graphs: &mut Assets<AnimationGraph>) -> Self {
+        let mut graph = AnimationGraph::new();
+        let nodes: HashMap<&str, AnimationNodeIndex> = gltf
+            .named_animations
+            .iter()
+            .map(|(k, h)| (k.as_ref(), graph.add_clip(h.clone(), 1.0, graph.root)))
+            .collect();
+        let picked = pick_clips(gltf.named_animations.keys().map(|k| k.as_ref()));
+        let states = picked.into_iter().filter_map(|(state, name)| nodes.get(name).map(|n| (state, *n))).collect();
+        let named = nodes.iter().map(|(k, n)| (short_clip_name(k), *n)).collect();
+        Self { graph: graphs.add(graph), states, named }
+    }
+
+    /// Clip for a state, falling back to the nearest thing the model has
+    pub fn for_state(&self, state: AnimState) -> Option<&AnimationNodeIndex> {
+        self.states.get(&state).or_else(|| match state {
+            AnimState::Run => self.states.get(&AnimState::Walk),
+            AnimState::Interact | AnimState::Scared => self.states.get(&AnimState::Idle),
+            AnimState::Idle | AnimState::Walk => None,
+        })
+    }
+}
+
+/// Clip sets per character model, filled in as the GLBs finish loading
+#[derive(Resource, Default)]
+pub struct CharacterClips {
+    loading: Vec<(&'static str, Handle<Gltf>)>,
+    pub sets: HashMap<&'static str, ClipSet>,
+}
+
+/// A character whose scene has an animation rig
+#[derive(Component, Debug, Clone)]
+pub struct Animated {
+    /// One of `CHARACTER_MODELS`
+    pub model: &'static str,
+    /// Name timelines use for this character ("ethan", "warden")
+    pub actor: Option<&'static str>,
+    /// State whose clip is playing; `None` while a scripted clip has the rig
+    pub state: Option<AnimState>,
+    /// Seconds left on a scripted clip
+    scripted: f32,
+    last_pos: Option<Vec3>,
+}
+
+impl Animated {
+    pub fn new(model: &'static str) -> Self {
+        Self { model, actor: None, state: None, scripted: 0.0, last_pos: None }
+    }
+
+    pub fn actor(mut self, name: &'static str) -> Self {
+        self.actor = Some(name);
+        self
+    }
+}
+
+/// The `AnimationPlayer` somewhere inside an `Animated` character's scene
+#[derive(Component)]
+pub struct AnimTarget(pub Entity);
+
+#[derive(Debug, Clone)]
+pub enum ClipTarget {
+    Entity(Entity),
+    /// Every character with this `Animated::actor` name
+    Actor(String),
+}
+
+/// Play a named clip in place of the state machine for a while
+#[derive(Event, Debug, Clone)]
+pub struct PlayClip {
+    pub target: ClipTarget,
+    /// Clip name with or without the armature prefix, any case ("Interact", "Man_Clapping")
+    pub clip: String,
+    pub secs: f32,
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CharacterClips>()
+           .add_event::<PlayClip>()
+           .add_systems(Startup, load_character_clips)
+           .add_systems(Update, (
+               discover_clips_system,
+               link_players_system,
+               timeline_clips_system,
+               play_clip_system,
+               animation_state_system,
+           ).chain());
+    }
+}
+
+fn load_character_clips(asset_server: Res<AssetServer>, mut clips: ResMut<CharacterClips>) {
+    clips.loading = CHARACTER_MODELS.iter().map(|path| (*path, asset_server.load(*path))).collect();
+}
+
+fn discover_clips_system(gltfs: Res<Assets<Gltf>>, mut graphs: ResMut<Assets<AnimationGraph>>, mut clips: ResMut<CharacterClips>) {
+    if clips.loading.is_empty() {
+        return;
+    }
+    let loading = std::mem::take(&mut clips.loading);
+    for (path, handle) in loading {
+        let Some(gltf) = gltfs.get(&handle) else {
+            clips.loading.push((path, handle));
+            continue;
+        };
+        let set = ClipSet::from_gltf(gltf, &mut graphs);
+        for state in [AnimState::Idle, AnimState::Walk, AnimState::Run, AnimState::Interact, AnimState::Scared] {
+            if !set.states.contains_key(&state) {
+                debug!("{} has no {:?} clip; a fallback will play", path, state);
+            }
+        }
+        info!("{}: {} animation clips", path, set.named.len());
+        clips.sets.insert(path, set);
+    }
+}
+
+/// Scenes spawn their rig a few frames after the character; once the
+/// `AnimationPlayer` shows up and the model's clips are in, give the player
+/// the model's graph and point the character root at it
+fn link_players_system(
+    mut commands: Commands,
+    clips: Res<CharacterClips>,
+    players: Query<Entity, (With<AnimationPlayer>, Without<Handle<AnimationGraph>>)>,
+    parents: Query<&Parent>,
+    animated: Query<&Animated>,
+) {
+    for player in &players {
+        let mut current = player;
+        while let Ok(parent) = parents.get(current) {
+            current = parent.get();
+            if let Ok(anim) = animated.get(current) {
+                if let Some(set) = clips.sets.get(anim.model) {
+                    commands.entity(player).insert((set.graph.clone(), AnimationTransitions::new()));
+                    commands.entity(current).insert(AnimTarget(player));
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Timeline frames can list `animate: [{ actor, clip }]`; play them as each frame starts
+fn timeline_clips_system(
+    active: Option<Res<ActiveTimeline>>,
+    mut last: Local<Option<(String, usize)>>,
+    mut play: EventWriter<PlayClip>,
+) {
+    let Some(active) = active else {
+        *last = None;
+        return;
+    };
+    let Some(frame) = active.current_frame() else { return };
+    let key = (active.timeline.title.clone(), active.current);
+    if last.as_ref() == Some(&key) {
+        return;
+    }
+    let secs = active.timeline.frame_duration_secs(active.current);
+    for cue in &frame.animate {
+        play.send(PlayClip { target: ClipTarget::Actor(cue.actor.clone()), clip: cue.clip.clone(), secs });
+    }
+    *last = Some(key);
+}
+
+fn play_clip_system(
+    mut events: EventReader<PlayClip>,
+    clips: Res<CharacterClips>,
+    mut characters: Query<(Entity, &mut Animated, Option<&AnimTarget>)>,
+    mut players: Query<(&mut AnimationPlayer, &mut AnimationTransitions)>,
+) {
+    for ev in events.read() {
+        let wanted = short_clip_name(&ev.clip);
+        for (entity, mut anim, target) in &mut characters {
+            let addressed = match &ev.target {
+                ClipTarget::Entity(e) => *e == entity,
+                ClipTarget::Actor(name) => anim.actor.is_some_and(|a| a.eq_ignore_ascii_case(name)),
+            };
+            if !addressed {
+                continue;
+            }
+            let Some(clip) = clips.sets.get(anim.model).and_then(|s| s.named.get(&wanted)) else {
+                warn!("{} has no clip '{}'", anim.model, ev.clip);
+                continue;
+            };
+            let Some((mut player, mut transitions)) = target.and_then(|t| players.get_mut(t.0).ok()) else { continue };
+            transitions.play(&mut player, *clip, Duration::from_secs_f32(BLEND_SECS)).repeat().set_speed(1.0);
+            anim.scripted = ev.secs;
+            anim.state = None;
+        }
+    }
+}
+
+/// Pick idle / walk / run / interact / scared from movement and AI state and
+/// cross-fade to its clip. Walk and run playback follows ground speed.
+fn animation_state_system(
+    time: Res<Time>,
+    clips: Res<CharacterClips>,
+    mut characters: Query<(&mut Animated, &Transform, &AnimTarget, Option<&NavAgent>, Option<&Npc>, Option<&SimLod>)>,
+    mut players: Query<(&mut AnimationPlayer, &mut AnimationTransitions)>,
+) {
+    let dt = time.delta_seconds();
+    if dt <= 0.0 {
+        return;
+    }
+    for (mut anim, tf, target, agent, npc, lod) in &mut characters {
+        let moved = anim.last_pos.map_or(0.0, |p| Vec3::new(tf.translation.x - p.x, 0.0, tf.translation.z - p.z).length() / dt);
+        anim.last_pos = Some(tf.translation);
+        // nobody is near enough to see the rig of an NPC out of full simulation
+        if lod.is_some_and(|l| l.tier != SimTier::Full) {
+            continue;
+        }
+        if anim.scripted > 0.0 {
+            anim.scripted -= dt;
+            continue;
+        }
+
+        let speed = agent.filter(|a| !a.is_idle()).map_or(moved, |a| a.velocity.length().max(moved));
+        let state = match npc.map(|n| n.state) {
+            Some(NpcState::Fleeing) if speed < MOVING_SPEED => AnimState::Scared,
+            Some(NpcState::Interacting) if speed < MOVING_SPEED => AnimState::Interact,
+            _ if speed < MOVING_SPEED => AnimState::Idle,
+            _ if speed < RUN_SPEED => AnimState::Walk,
+            _ => AnimState::Run,
+        };
+
+        let Ok((mut player, mut transitions)) = players.get_mut(target.0) else { continue };
+        let Some(&clip) = clips.sets.get(anim.model).and_then(|s| s.for_state(state)) else { continue };
+        if anim.state != Some(state) {
+            transitions.play(&mut player, clip, Duration::from_secs_f32(BLEND_SECS)).repeat();
+            anim.state = Some(state);
+        }
+        let playback = match state {
+            AnimState::Walk => (speed / WALK_CLIP_SPEED).clamp(0.5, 2.0),
+            AnimState::Run => (speed / RUN_CLIP_SPEED).clamp(0.6, 1.8),
+            _ => 1.0,
+        };
+        if let Some(active) = player.animation_mut(clip) {
+            active.set_speed(playback);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clips_map_across_model_packs() {
+        let man = ["HumanArmature|Man_Clapping", "HumanArmature|Man_Idle", "HumanArmature|Man_Run", "HumanArmature|Man_Standing", "HumanArmature|Man_Walk"];
+        let picked = pick_clips(man);
+        assert_eq!(picked[&AnimState::Idle], "HumanArmature|Man_Idle");
+        assert_eq!(picked[&AnimState::Walk], "HumanArmature|Man_Walk");
+        assert_eq!(picked[&AnimState::Interact], "HumanArmature|Man_Clapping");
+        assert!(!picked.contains_key(&AnimState::Scared));
+
+        let punk = ["CharacterArmature|Idle_Gun", "CharacterArmature|Idle", "CharacterArmature|Run_Back", "CharacterArmature|Run", "CharacterArmature|Wave", "CharacterArmature|Interact", "CharacterArmature|HitRecieve"];
+        let picked = pick_clips(punk);
+        assert_eq!(picked[&AnimState::Idle], "CharacterArmature|Idle");
+        assert_eq!(picked[&AnimState::Run], "CharacterArmature|Run");
+        assert_eq!(picked[&AnimState::Interact], "CharacterArmature|Interact");
+        assert_eq!(picked[&AnimState::Scared], "CharacterArmature|HitRecieve");
+
+        let woman = ["Armature|Walking", "Armature|Running", "Armature|PickUp", "Armature|SitIdle"];
+        let picked = pick_clips(woman);
+        assert_eq!(picked[&AnimState::Run], "Armature|Running");
+        assert_eq!(picked[&AnimState::Interact], "Armature|PickUp");
+        assert!(!picked.contains_key(&AnimState::Idle));
+    }
+}
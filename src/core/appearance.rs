@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// How an NPC was dressed by the archetype table
+#[derive(Component, Debug, Clone)]
+pub struct Appearance {
+    /// `ArchetypeDef::id`
+    pub archetype: String,
+    /// Multiplied into every material of the model
+    pub tint: Color,
+    /// Key of `Voices` the NPC speaks dialogue in; empty for silent figures
+    pub voice: String,
+}
+
+/// A character's name, shown in conversations
+#[derive(Component, Debug, Clone)]
+pub struct NpcName(pub String);
+
+pub struct AppearancePlugin;
+
+impl Plugin for AppearancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, tint_materials_system);
+    }
+}
+
+/// Scene meshes arrive a few frames after the NPC. Give each one a tinted copy
+/// of its material, shared by every mesh with the same material and tint.
+fn tint_materials_system(
+    mut meshes: Query<(Entity, &mut Handle<StandardMaterial>), Added<Handle<StandardMaterial>>>,
+    parents: Query<&Parent>,
+    appearances: Query<&Appearance>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut tinted: Local<HashMap<(AssetId<StandardMaterial>, [u8; 3]), Handle<StandardMaterial>>>,
+) {
+    for (entity, mut material) in &mut meshes {
+        let mut current = entity;
+        let tint = loop {
+            let Ok(parent) = parents.get(current) else { break None };
+            current = parent.get();
+            if let Ok(appearance) = appearances.get(current) {
+                break Some(appearance.tint);
+            }
+        };
+        let Some(tint) = tint.filter(|t| *t != Color::WHITE) else { continue };
+
+        let Srgba { red: r, green: g, blue: b, .. } = tint.to_srgba();
+        let key = (material.id(), [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8]);
+        if let Some(handle) = tinted.get(&key) {
+            *material = handle.clone();
+            continue;
+        }
+        let Some(mut copy) = materials.get(&*material).cloned() else { continue };
+        let base = copy.base_color.to_srgba();
+        copy.base_color = Color::srgba(base.red * r, base.green * g, base.blue * b, base.alpha);
+        let handle = materials.add(copy);
+        tinted.insert(key, handle.clone());
+        *material = handle;
+    }
+}
@@ -0,0 +1,354 @@
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_rapier3d::prelude::*;
+
+use crate::core::hiding::Hidden;
+use crate::core::player::{Ethan, Locomotion};
+use crate::core::school::{floor_y, SchoolLayout, FLOOR_HEIGHT};
+use crate::narrative::ActiveTimeline;
+use crate::states::GameState;
+use crate::systems::controls::Controls;
+use crate::systems::inventory_ui::InventoryScreen;
+
+/// Orbit distance behind the shoulder when nothing is in the way
+pub const THIRD_PERSON_DISTANCE: f32 = 4.5;
+/// Orbit pivot above Ethan's feet (standing / crouched)
+const PIVOT_HEIGHT: f32 = 1.6;
+const CROUCH_PIVOT_HEIGHT: f32 = 1.0;
+const SHOULDER_OFFSET: f32 = 0.5;
+/// Eye height in first person (standing / crouched)
+const EYE_HEIGHT: f32 = 1.65;
+const CROUCH_EYE_HEIGHT: f32 = 1.0;
+/// Kept between the camera and whatever wall it was pulled in front of
+const CAMERA_RADIUS: f32 = 0.25;
+const MIN_DISTANCE: f32 = 0.4;
+/// Metres per second the boom extends back out once a wall is cleared
+const BOOM_RETURN_SPEED: f32 = 3.0;
+/// Radians per pixel of mouse motion
+const MOUSE_SENSITIVITY: f32 = 0.003;
+/// Radians per second at full right-stick deflection
+const GAMEPAD_LOOK_SPEED: f32 = 2.5;
+const STICK_DEADZONE: f32 = 0.15;
+const PITCH_MIN: f32 = -1.2;
+const PITCH_MAX: f32 = 1.0;
+/// Seconds to swing between third and first person
+const MODE_BLEND_SECS: f32 = 0.35;
+/// Default blend when a script takes or returns the camera
+pub const HANDOFF_BLEND_SECS: f32 = 0.6;
+/// Past this far into first person Ethan's own model is hidden
+const HIDE_BODY_BLEND: f32 = 0.8;
+/// Render order of the gameplay camera, after the menu's 2D camera at 0
+const GAMEPLAY_CAMERA_ORDER: isize = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    #[default]
+    ThirdPerson,
+    FirstPerson,
+}
+
+/// The gameplay camera. Follows Ethan in the mode the player picked, except
+/// inside classrooms and hiding spots where it is forced into first person.
+#[derive(Component, Debug, Clone)]
+pub struct CameraRig {
+    /// Mode picked with `Controls::camera_toggle`
+    pub preferred: CameraMode,
+    /// Mode actually in use this frame
+    pub mode: CameraMode,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Current boom length after wall avoidance
+    pub distance: f32,
+    /// 0 = third person, 1 = first person
+    pub blend: f32,
+    /// Where the orbit was centred last frame; scripted shots are placed from it
+    pub pivot: Vec3,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            preferred: CameraMode::ThirdPerson,
+            mode: CameraMode::ThirdPerson,
+            yaw: 0.0,
+            pitch: -0.25,
+            distance: THIRD_PERSON_DISTANCE,
+            blend: 0.0,
+            pivot: Vec3::ZERO,
+        }
+    }
+}
+
+/// Scripts and timelines take the camera away from the player with
+/// `Take` and give it back with `Release`; both blend over `blend_secs`.
+#[derive(Event, Debug, Clone)]
+pub enum CameraHandOff {
+    Take { shot: Transform, blend_secs: f32 },
+    Release { blend_secs: f32 },
+}
+
+/// Scripted shot currently holding the camera
+#[derive(Resource, Debug, Default)]
+pub struct CameraDirector {
+    pub shot: Option<Transform>,
+    /// 0 = player rig, 1 = scripted shot
+    pub weight: f32,
+    releasing: bool,
+    blend_secs: f32,
+}
+
+impl CameraDirector {
+    /// True while a script owns the camera, including the blend back
+    pub fn in_control(&self) -> bool {
+        self.shot.is_some()
+    }
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraDirector>()
+           .add_event::<CameraHandOff>()
+           .add_systems(Update, (spawn_camera_system, timeline_camera_system, handoff_system, look_input_system).chain())
+           .add_systems(OnEnter(GameState::Title), release_camera)
+           .add_systems(
+               PostUpdate,
+               camera_rig_system
+                   .after(PhysicsSet::Writeback)
+                   .before(TransformSystem::TransformPropagate),
+           );
+    }
+}
+
+/// The 3D camera comes with the player. It takes over from the menu's 2D
+/// camera, which would otherwise clear over it and draw the UI twice.
+fn spawn_camera_system(
+    mut commands: Commands,
+    rigs: Query<(), With<CameraRig>>,
+    player_q: Query<&Transform, Added<Ethan>>,
+    mut menu_cameras: Query<&mut Camera, With<Camera2d>>,
+) {
+    let Ok(tf) = player_q.get_single() else { return };
+    if !rigs.is_empty() {
+        return;
+    }
+    for mut camera in &mut menu_cameras {
+        camera.is_active = false;
+    }
+    let pivot = tf.translation + Vec3::Y * PIVOT_HEIGHT;
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera { order: GAMEPLAY_CAMERA_ORDER, ..default() },
+            transform: Transform::from_translation(pivot + Vec3::Z * THIRD_PERSON_DISTANCE).looking_at(pivot, Vec3::Y),
+            ..default()
+        },
+        CameraRig { pivot, ..default() },
+    ));
+    info!("Camera rig spawned.");
+}
+
+/// Back on the title screen the rig goes and the menu's 2D camera draws again.
+/// The next mission's Ethan brings a fresh rig.
+fn release_camera(
+    mut commands: Commands,
+    rigs: Query<Entity, With<CameraRig>>,
+    mut menu_cameras: Query<&mut Camera, With<Camera2d>>,
+    mut director: ResMut<CameraDirector>,
+) {
+    for rig in &rigs {
+        commands.entity(rig).despawn_recursive();
+    }
+    for mut camera in &mut menu_cameras {
+        camera.is_active = true;
+    }
+    *director = CameraDirector::default();
+    info!("Camera rig released.");
+}
+
+/// Timeline frames with a `shot` take the camera; it goes back to the player
+/// on the first frame without one or when the timeline ends
+fn timeline_camera_system(
+    active: Option<Res<ActiveTimeline>>,
+    rigs: Query<&CameraRig>,
+    mut last: Local<Option<(String, usize)>>,
+    mut holding: Local<bool>,
+    mut handoff: EventWriter<CameraHandOff>,
+) {
+    let frame = active.as_ref().and_then(|a| a.current_frame().map(|f| (a, f)));
+    let Some((active, frame)) = frame else {
+        *last = None;
+        if std::mem::take(&mut *holding) {
+            handoff.send(CameraHandOff::Release { blend_secs: HANDOFF_BLEND_SECS });
+        }
+        return;
+    };
+    let key = (active.timeline.title.clone(), active.current);
+    if last.as_ref() == Some(&key) {
+        return;
+    }
+    *last = Some(key);
+
+    match (&frame.shot, rigs.get_single()) {
+        (Some(shot), Ok(rig)) => {
+            let facing = Quat::from_rotation_y(rig.yaw);
+            let eye = rig.pivot + facing * Vec3::from(shot.eye);
+            let target = rig.pivot + facing * Vec3::from(shot.look_at);
+            let blend_secs = if shot.cut { 0.0 } else { HANDOFF_BLEND_SECS };
+            handoff.send(CameraHandOff::Take { shot: Transform::from_translation(eye).looking_at(target, Vec3::Y), blend_secs });
+            *holding = true;
+        }
+        _ if *holding => {
+            handoff.send(CameraHandOff::Release { blend_secs: HANDOFF_BLEND_SECS });
+            *holding = false;
+        }
+        _ => {}
+    }
+}
+
+fn handoff_system(time: Res<Time>, mut events: EventReader<CameraHandOff>, mut director: ResMut<CameraDirector>) {
+    for ev in events.read() {
+        match *ev {
+            CameraHandOff::Take { shot, blend_secs } => {
+                director.shot = Some(shot);
+                director.releasing = false;
+                director.blend_secs = blend_secs;
+            }
+            CameraHandOff::Release { blend_secs } => {
+                director.releasing = true;
+                director.blend_secs = blend_secs;
+            }
+        }
+    }
+    if director.shot.is_none() {
+        return;
+    }
+
+    let step = if director.blend_secs <= 0.0 { 1.0 } else { time.delta_seconds() / director.blend_secs };
+    if director.releasing {
+        director.weight = (director.weight - step).max(0.0);
+        if director.weight <= 0.0 {
+            director.shot = None;
+            director.releasing = false;
+        }
+    } else {
+        director.weight = (director.weight + step).min(1.0);
+    }
+}
+
+/// Mouse and right stick orbit the camera; the toggle key or a right-stick
+/// click swaps between third and first person
+fn look_input_system(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    director: Res<CameraDirector>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    mut mouse: EventReader<MouseMotion>,
+    screen: Option<Res<InventoryScreen>>,
+    mut rigs: Query<&mut CameraRig>,
+) {
+    let mouse_delta: Vec2 = mouse.read().map(|m| m.delta).sum();
+    let Ok(mut rig) = rigs.get_single_mut() else { return };
+    // the mouse is busy dragging items around
+    if director.in_control() || screen.is_some_and(|s| s.open) {
+        return;
+    }
+
+    let mut toggle = keyboard.just_pressed(controls.camera_toggle);
+    let mut stick = Vec2::ZERO;
+    for gamepad in gamepads.iter() {
+        let x = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX)).unwrap_or(0.0);
+        let y = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY)).unwrap_or(0.0);
+        let v = Vec2::new(x, y);
+        if v.length() > STICK_DEADZONE {
+            stick += v;
+        }
+        toggle |= buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::RightThumb));
+    }
+
+    if toggle {
+        rig.preferred = match rig.preferred {
+            CameraMode::ThirdPerson => CameraMode::FirstPerson,
+            CameraMode::FirstPerson => CameraMode::ThirdPerson,
+        };
+    }
+
+    let turn = stick * GAMEPAD_LOOK_SPEED * time.delta_seconds();
+    rig.yaw -= mouse_delta.x * MOUSE_SENSITIVITY + turn.x;
+    rig.pitch = (rig.pitch - mouse_delta.y * MOUSE_SENSITIVITY + turn.y).clamp(PITCH_MIN, PITCH_MAX);
+}
+
+/// Places the camera after physics has moved Ethan this frame
+fn camera_rig_system(
+    time: Res<Time>,
+    rapier: Res<RapierContext>,
+    school: Option<Res<SchoolLayout>>,
+    director: Res<CameraDirector>,
+    mut player_q: Query<(Entity, &Transform, &mut Visibility, Option<&Locomotion>, Option<&Hidden>), With<Ethan>>,
+    mut rigs: Query<(&mut CameraRig, &mut Transform), Without<Ethan>>,
+) {
+    let Ok((mut rig, mut cam_tf)) = rigs.get_single_mut() else { return };
+    let dt = time.delta_seconds();
+
+    if let Ok((player, tf, mut visibility, motion, hidden)) = player_q.get_single_mut() {
+        let crouching = motion.is_some_and(|m| m.crouching);
+        let indoors = school.as_ref().is_some_and(|s| {
+            s.rooms.iter().any(|r| {
+                let y = floor_y(r.floor);
+                r.contains(tf.translation) && tf.translation.y >= y - 0.5 && tf.translation.y < y + FLOOR_HEIGHT
+            })
+        });
+        rig.mode = if hidden.is_some() || indoors { CameraMode::FirstPerson } else { rig.preferred };
+        let target_blend = if rig.mode == CameraMode::FirstPerson { 1.0 } else { 0.0 };
+        let step = dt / MODE_BLEND_SECS;
+        rig.blend += (target_blend - rig.blend).clamp(-step, step);
+
+        let rotation = Quat::from_euler(EulerRot::YXZ, rig.yaw, rig.pitch, 0.0);
+        rig.pivot = tf.translation + Vec3::Y * if crouching { CROUCH_PIVOT_HEIGHT } else { PIVOT_HEIGHT };
+
+        // pull the boom in front of walls at once, let it ease back out
+        let boom = rotation * Vec3::new(SHOULDER_OFFSET, 0.0, THIRD_PERSON_DISTANCE);
+        let filter = QueryFilter::default().exclude_collider(player).exclude_sensors();
+        let clear = rapier
+            .cast_ray(rig.pivot, boom.normalize(), boom.length(), true, filter)
+            .map_or(boom.length(), |(_, toi)| (toi - CAMERA_RADIUS).max(MIN_DISTANCE));
+        rig.distance = if clear < rig.distance { clear } else { (rig.distance + BOOM_RETURN_SPEED * dt).min(clear) };
+        let third = Transform::from_translation(rig.pivot + boom.normalize() * rig.distance).with_rotation(rotation);
+
+        // hiding spots supply their own (peeking) view
+        let first = match hidden {
+            Some(hidden) => hidden.view,
+            None => {
+                let eye = tf.translation + Vec3::Y * if crouching { CROUCH_EYE_HEIGHT } else { EYE_HEIGHT };
+                Transform::from_translation(eye).with_rotation(rotation)
+            }
+        };
+
+        *cam_tf = blend(&third, &first, ease(rig.blend));
+        *visibility = if rig.blend > HIDE_BODY_BLEND && director.weight < 0.5 {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+
+    if let Some(shot) = director.shot {
+        *cam_tf = blend(&cam_tf, &shot, ease(director.weight));
+    }
+}
+
+fn blend(a: &Transform, b: &Transform, t: f32) -> Transform {
+    Transform {
+        translation: a.translation.lerp(b.translation, t),
+        rotation: a.rotation.slerp(b.rotation, t),
+        scale: Vec3::ONE,
+    }
+}
+
+fn ease(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+use crate::core::world::StreetLight;
+use crate::states::GameState;
+use crate::systems::settings::Settings;
+
+/// Real seconds in one in-game day
+pub const DEFAULT_DAY_LENGTH_SECS: f32 = 1440.0;
+/// Hour the game starts at
+const START_HOUR: f32 = 7.0;
+const SUNRISE_HOUR: f32 = 6.0;
+const SUNSET_HOUR: f32 = 18.0;
+/// Sun illuminance at noon, in lux
+const NOON_ILLUMINANCE: f32 = 10_000.0;
+const DAY_AMBIENT: f32 = 0.5;
+const NIGHT_AMBIENT: f32 = 0.04;
+/// Street lights come on when the sun is lower than this (sine of elevation)
+const STREET_LIGHT_ELEVATION: f32 = 0.1;
+const STREET_LIGHT_INTENSITY: f32 = 1600.0;
+
+/// Time of day in the world. `hour` runs 0..24 and wraps into the next day.
+#[derive(Resource, Debug, Clone)]
+pub struct WorldClock {
+    pub day: u32,
+    pub hour: f32,
+    pub day_length_secs: f32,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self { day: 1, hour: START_HOUR, day_length_secs: DEFAULT_DAY_LENGTH_SECS }
+    }
+}
+
+impl WorldClock {
+    /// Move the clock on by `secs` real seconds
+    pub fn advance(&mut self, secs: f32) {
+        self.hour += secs * 24.0 / self.day_length_secs.max(1.0);
+        while self.hour >= 24.0 {
+            self.hour -= 24.0;
+            self.day += 1;
+        }
+    }
+
+    /// Whole hour of the day, 0..=23
+    pub fn whole_hour(&self) -> u32 {
+        self.hour as u32
+    }
+
+    /// Sine of the sun's elevation: 0 at sunrise/sunset, 1 at noon, negative at night
+    pub fn sun_height(&self) -> f32 {
+        sun_angle(self.hour).sin()
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.sun_height() < STREET_LIGHT_ELEVATION
+    }
+
+    /// "HH:MM" for the HUD and logs
+    pub fn label(&self) -> String {
+        let minutes = (self.hour * 60.0) as u32;
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+/// Sent whenever the clock passes a whole hour. `hour` 0 is also the start of a new day.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HourChanged {
+    pub day: u32,
+    pub hour: u32,
+}
+
+/// The directional light playing the sun
+#[derive(Component)]
+pub struct Sun;
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        let day_length_secs = app
+            .world()
+            .get_resource::<Settings>()
+            .map_or(DEFAULT_DAY_LENGTH_SECS, |s| s.day_length_secs);
+        app.insert_resource(WorldClock { day_length_secs, ..default() })
+           .insert_resource(AmbientLight { color: Color::WHITE, brightness: DAY_AMBIENT })
+           .add_event::<HourChanged>()
+           .add_systems(Startup, spawn_sun)
+           .add_systems(Update, tick_clock_system.run_if(in_state(GameState::OpenWorld).or_else(in_state(GameState::Mission1))))
+           // lighting also follows loads and debug clock changes outside play states
+           .add_systems(Update, (sun_and_ambient_system, street_lights_system).run_if(resource_changed::<WorldClock>));
+    }
+}
+
+/// Sun angle over the horizon in radians: 0 at sunrise, PI/2 at noon, PI at sunset
+fn sun_angle(hour: f32) -> f32 {
+    (hour - SUNRISE_HOUR) / (SUNSET_HOUR - SUNRISE_HOUR) * PI
+}
+
+fn spawn_sun(mut commands: Commands, settings: Option<Res<Settings>>) {
+    let shadows = settings.is_none_or(|s| s.shadow_quality > 0 && !s.low_spec_mode);
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight { shadows_enabled: shadows, ..default() },
+            ..default()
+        },
+        Sun,
+    ));
+}
+
+fn tick_clock_system(time: Res<Time>, mut clock: ResMut<WorldClock>, mut hours: EventWriter<HourChanged>) {
+    let before = (clock.day, clock.whole_hour());
+    clock.advance(time.delta_seconds());
+    if (clock.day, clock.whole_hour()) != before {
+        hours.send(HourChanged { day: clock.day, hour: clock.whole_hour() });
+        info!("Day {} {}", clock.day, clock.label());
+    }
+}
+
+fn sun_and_ambient_system(
+    clock: Res<WorldClock>,
+    mut ambient: ResMut<AmbientLight>,
+    mut sun_q: Query<(&mut Transform, &mut DirectionalLight), With<Sun>>,
+) {
+    let height = clock.sun_height();
+    let daylight = height.max(0.0);
+    for (mut tf, mut light) in &mut sun_q {
+        // rises in the -Z sky, sets towards +Z; slight yaw so shadows aren't grid-aligned
+        *tf = Transform::from_rotation(Quat::from_rotation_y(0.4) * Quat::from_rotation_x(-sun_angle(clock.hour)));
+        light.illuminance = NOON_ILLUMINANCE * daylight.sqrt();
+        // warm and low at dawn and dusk, white at midday
+        light.color = Color::srgb(1.0, 0.75 + 0.25 * daylight, 0.55 + 0.45 * daylight);
+    }
+    ambient.brightness = NIGHT_AMBIENT + (DAY_AMBIENT - NIGHT_AMBIENT) * daylight.sqrt();
+    ambient.color = if height > 0.0 { Color::srgb(1.0, 0.96, 0.9) } else { Color::srgb(0.45, 0.5, 0.8) };
+}
+
+fn street_lights_system(clock: Res<WorldClock>, mut lamps: Query<&mut PointLight, With<StreetLight>>) {
+    let intensity = if clock.is_night() { STREET_LIGHT_INTENSITY } else { 0.0 };
+    for mut light in &mut lamps {
+        if light.intensity != intensity {
+            light.intensity = intensity;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_wraps_into_the_next_day() {
+        let mut clock = WorldClock { day: 3, hour: 23.0, day_length_secs: 240.0 };
+        // 240 s per day is 10 s per hour
+        clock.advance(15.0);
+        assert_eq!(clock.day, 4);
+        assert!((clock.hour - 0.5).abs() < 1e-4);
+        assert_eq!(clock.label(), "00:30");
+    }
+
+    #[test]
+    fn night_and_day() {
+        let at = |hour| WorldClock { hour, ..default() };
+        assert!(at(0.0).is_night());
+        assert!(at(22.0).is_night());
+        assert!(!at(12.0).is_night());
+        assert!((at(12.0).sun_height() - 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,247 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+use crate::core::player::{Ethan, PlayerStats};
+use crate::core::reactions::StinkCloud;
+use crate::states::GameState;
+
+/// Entries kept in the session's damage log
+pub const DAMAGE_LOG_LEN: usize = 50;
+/// Hits from the same source this close together share a log entry
+const LOG_MERGE_SECS: f32 = 1.0;
+/// After a discrete hit (car, fall, Warden) the same source can't hurt again for this long
+const INVULNERABLE_SECS: f32 = 0.6;
+/// Standing in a stink cloud, per second
+const STINK_DAMAGE: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageSource {
+    Starvation,
+    Car,
+    Fall,
+    Warden,
+    /// Hazards in the world: stink clouds and the like
+    Environment,
+}
+
+impl DamageSource {
+    /// Ticks a little every frame rather than landing as one hit
+    pub fn continuous(self) -> bool {
+        matches!(self, DamageSource::Starvation | DamageSource::Environment)
+    }
+
+    /// The Warden captures Ethan; he never kills him
+    pub fn lethal(self) -> bool {
+        self != DamageSource::Warden
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DamageSource::Starvation => "Starvation",
+            DamageSource::Car => "Hit by a car",
+            DamageSource::Fall => "Fall",
+            DamageSource::Warden => "The Warden",
+            DamageSource::Environment => "Environment",
+        }
+    }
+}
+
+/// Hurt Ethan. `amount` is before `DamageModifiers`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DamageEvent {
+    pub source: DamageSource,
+    pub amount: f32,
+}
+
+/// Health hit zero
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerDied {
+    pub source: DamageSource,
+}
+
+/// Scales incoming damage. `resist` is the fraction taken off per source
+/// (0.25 = a quarter less); `global` multiplies everything after that.
+#[derive(Resource, Debug, Clone)]
+pub struct DamageModifiers {
+    pub resist: HashMap<DamageSource, f32>,
+    pub global: f32,
+}
+
+impl Default for DamageModifiers {
+    fn default() -> Self {
+        Self { resist: HashMap::new(), global: 1.0 }
+    }
+}
+
+impl DamageModifiers {
+    pub fn apply(&self, source: DamageSource, amount: f32) -> f32 {
+        let resist = self.resist.get(&source).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+        (amount * (1.0 - resist) * self.global).max(0.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DamageRecord {
+    /// Seconds since startup when the (first) hit landed
+    pub at: f32,
+    pub source: DamageSource,
+    pub amount: f32,
+    pub health_after: f32,
+}
+
+/// Everything that hurt Ethan this session, newest last
+#[derive(Resource, Debug, Default)]
+pub struct DamageLog {
+    pub entries: VecDeque<DamageRecord>,
+    /// Source of the very latest hit, which may have been folded into an older entry
+    last_hit: Option<DamageSource>,
+}
+
+impl DamageLog {
+    /// Add a hit, folding it into the most recent entry of the same source
+    /// that began within `LOG_MERGE_SECS` (starvation would otherwise log
+    /// every frame, and a car hit in between shouldn't split the ticks up)
+    pub fn record(&mut self, at: f32, source: DamageSource, amount: f32, health_after: f32) {
+        self.last_hit = Some(source);
+        let mut recent = self.entries.iter_mut().rev().take_while(|e| at - e.at <= LOG_MERGE_SECS);
+        if let Some(entry) = recent.find(|e| e.source == source) {
+            entry.amount += amount;
+            entry.health_after = health_after;
+            return;
+        }
+        self.entries.push_back(DamageRecord { at, source, amount, health_after });
+        while self.entries.len() > DAMAGE_LOG_LEN {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn last_source(&self) -> Option<DamageSource> {
+        self.last_hit
+    }
+}
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DamageModifiers>()
+           .init_resource::<DamageLog>()
+           .add_event::<DamageEvent>()
+           .add_event::<PlayerDied>()
+           .add_systems(Update, environment_damage_system.run_if(in_state(GameState::Mission1)))
+           // not state-gated: the Warden's blow lands on the frame he sends Ethan to Caught
+           .add_systems(Update, apply_damage_system)
+           .add_systems(Update, death_system.after(apply_damage_system).run_if(in_state(GameState::Mission1)));
+    }
+}
+
+fn environment_damage_system(
+    time: Res<Time>,
+    player_q: Query<&Transform, With<Ethan>>,
+    clouds: Query<(&Transform, &StinkCloud)>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    let Ok(tf) = player_q.get_single() else { return };
+    if clouds.iter().any(|(c, cloud)| c.translation.distance(tf.translation) < cloud.radius) {
+        damage.send(DamageEvent { source: DamageSource::Environment, amount: STINK_DAMAGE * time.delta_seconds() });
+    }
+}
+
+fn apply_damage_system(
+    time: Res<Time>,
+    mut events: EventReader<DamageEvent>,
+    modifiers: Res<DamageModifiers>,
+    stats: Option<ResMut<PlayerStats>>,
+    mut log: ResMut<DamageLog>,
+    mut invulnerable: Local<HashMap<DamageSource, f32>>,
+) {
+    let now = time.elapsed_seconds();
+    let Some(mut stats) = stats else {
+        events.clear();
+        return;
+    };
+    for ev in events.read() {
+        if !ev.source.continuous() {
+            if invulnerable.get(&ev.source).is_some_and(|until| now < *until) {
+                continue;
+            }
+            invulnerable.insert(ev.source, now + INVULNERABLE_SECS);
+        }
+        let mut amount = modifiers.apply(ev.source, ev.amount);
+        if !ev.source.lethal() {
+            amount = amount.min((stats.health - 1.0).max(0.0));
+        }
+        if amount <= 0.0 {
+            continue;
+        }
+        stats.health = (stats.health - amount).max(0.0);
+        log.record(now, ev.source, amount, stats.health);
+        if !ev.source.continuous() {
+            info!("Ethan took {:.0} damage ({}), {:.0} health left", amount, ev.source.label(), stats.health);
+        }
+    }
+}
+
+fn death_system(
+    stats: Option<Res<PlayerStats>>,
+    log: Res<DamageLog>,
+    mut died: EventWriter<PlayerDied>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(stats) = stats else { return };
+    if stats.health > 0.0 {
+        return;
+    }
+    let source = log.last_source().unwrap_or(DamageSource::Environment);
+    died.send(PlayerDied { source });
+    next_state.set(GameState::GameOver);
+    info!("Ethan died: {}", source.label());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resistances_scale_damage() {
+        let mut modifiers = DamageModifiers::default();
+        assert_eq!(modifiers.apply(DamageSource::Car, 20.0), 20.0);
+        modifiers.resist.insert(DamageSource::Car, 0.25);
+        modifiers.global = 2.0;
+        assert_eq!(modifiers.apply(DamageSource::Car, 20.0), 30.0);
+        assert_eq!(modifiers.apply(DamageSource::Fall, 20.0), 40.0);
+    }
+
+    #[test]
+    fn log_merges_ticks_from_one_source() {
+        let mut log = DamageLog::default();
+        for i in 0..10 {
+            log.record(i as f32 * 0.05, DamageSource::Starvation, 0.1, 100.0 - i as f32 * 0.1);
+        }
+        log.record(0.6, DamageSource::Car, 12.0, 87.0);
+        log.record(5.0, DamageSource::Starvation, 0.1, 86.9);
+        assert_eq!(log.entries.len(), 3);
+        assert!((log.entries[0].amount - 1.0).abs() < 1e-4);
+        assert_eq!(log.last_source(), Some(DamageSource::Starvation));
+    }
+
+    #[test]
+    fn log_merges_ticks_around_another_hit() {
+        let mut log = DamageLog::default();
+        log.record(0.0, DamageSource::Starvation, 0.1, 99.9);
+        log.record(0.1, DamageSource::Car, 12.0, 87.9);
+        log.record(0.2, DamageSource::Starvation, 0.1, 87.8);
+        log.record(0.3, DamageSource::Starvation, 0.1, 87.7);
+        assert_eq!(log.entries.len(), 2);
+        assert_eq!(log.entries[0].source, DamageSource::Starvation);
+        assert!((log.entries[0].amount - 0.3).abs() < 1e-4);
+        assert_eq!(log.entries[0].health_after, 87.7);
+        assert_eq!(log.entries[1].source, DamageSource::Car);
+        // the starvation tick landed last, so it gets the blame
+        assert_eq!(log.last_source(), Some(DamageSource::Starvation));
+
+        // outside the window the ticks start a new entry
+        log.record(1.5, DamageSource::Starvation, 0.1, 87.6);
+        assert_eq!(log.entries.len(), 3);
+    }
+}
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+
+use crate::core::clock::HourChanged;
+use crate::core::hiding::Hidden;
+use crate::core::player::{Ethan, Locomotion, PlayerStats};
+use crate::core::seed::LootRng;
+use crate::core::world::{spawn_food_pickup, WorldSpawnInfo};
+use crate::data::assets_loader::Handles;
+use crate::data::items::{FoodEffect, ItemDb};
+use crate::states::GameState;
+use crate::systems::controls::Controls;
+use crate::systems::inventory::{Inventory, ItemPickup};
+
+/// Health per second while well fed
+const WELL_FED_REGEN: f32 = 0.5;
+/// Extra hunger lost per second while nauseous
+const NAUSEA_HUNGER_DRAIN: f32 = 0.8;
+/// A food spot counts as stocked with a pickup this close to it
+const FOOD_SPOT_RADIUS: f32 = 1.5;
+/// Chance per game hour that an empty food spot is restocked
+const RESTOCK_CHANCE: f64 = 0.5;
+/// What turns up at the market spots, with weights
+pub const MARKET_FOOD: &[(&str, u32)] = &[("food_apple", 4), ("food_snack", 3), ("food_burger", 2), ("food_leftovers", 1)];
+
+/// Ethan is eating `item`; moving, sprinting or hiding puts it away uneaten
+#[derive(Component, Debug, Clone)]
+pub struct Eating {
+    pub item: String,
+    pub remaining: f32,
+    pub total: f32,
+}
+
+/// Seconds left on each food effect
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct StatusEffects {
+    pub well_fed: f32,
+    pub nausea: f32,
+}
+
+impl StatusEffects {
+    pub fn nauseous(&self) -> bool {
+        self.nausea > 0.0
+    }
+
+    fn apply(&mut self, effect: FoodEffect) {
+        match effect {
+            FoodEffect::WellFed { secs } => self.well_fed = self.well_fed.max(secs),
+            FoodEffect::Nausea { secs } => {
+                self.nausea = self.nausea.max(secs);
+                // a bad stomach cancels out a good meal
+                self.well_fed = 0.0;
+            }
+        }
+    }
+}
+
+pub struct FoodPlugin;
+
+impl Plugin for FoodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StatusEffects>()
+           .add_systems(Update, (eat_input_system, eating_system, status_effect_system).chain().run_if(in_state(GameState::Mission1)))
+           .add_systems(Update, food_restock_system);
+    }
+}
+
+/// Start eating: the equipped food, else the first food Ethan has in item order
+fn eat_input_system(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    inv: Res<Inventory>,
+    items: Res<ItemDb>,
+    effects: Res<StatusEffects>,
+    stats: Option<Res<PlayerStats>>,
+    player_q: Query<Entity, (With<Ethan>, Without<Eating>, Without<Hidden>)>,
+) {
+    if !keyboard.just_pressed(controls.eat) {
+        return;
+    }
+    let Ok(player) = player_q.get_single() else { return };
+    if effects.nauseous() {
+        info!("Ethan can't face food right now.");
+        return;
+    }
+    if stats.is_some_and(|s| s.hunger >= s.max_hunger) {
+        info!("Ethan isn't hungry.");
+        return;
+    }
+    let edible = |id: &str| inv.count(id) > 0 && items.get(id).is_some_and(|d| d.food().is_some());
+    let choice = inv.equipped_id().filter(|id| edible(id)).map(str::to_string).or_else(|| items.defs.iter().map(|d| d.id.clone()).find(|id| edible(id)));
+    let Some((id, food)) = choice.and_then(|id| items.get(&id).and_then(|d| d.food()).map(|f| (id, f))) else {
+        info!("Nothing to eat.");
+        return;
+    };
+    commands.entity(player).insert(Eating { item: id, remaining: food.eat_secs, total: food.eat_secs });
+}
+
+fn eating_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut player_q: Query<(Entity, &mut Eating, &Locomotion, Option<&Hidden>), With<Ethan>>,
+    mut inv: ResMut<Inventory>,
+    items: Res<ItemDb>,
+    mut effects: ResMut<StatusEffects>,
+    stats: Option<ResMut<PlayerStats>>,
+) {
+    let Ok((player, mut eating, motion, hidden)) = player_q.get_single_mut() else { return };
+    if motion.speed > 0.0 || hidden.is_some() {
+        commands.entity(player).remove::<Eating>();
+        return;
+    }
+    eating.remaining -= time.delta_seconds();
+    if eating.remaining > 0.0 {
+        return;
+    }
+    commands.entity(player).remove::<Eating>();
+
+    let Some(def) = items.get(&eating.item) else { return };
+    let Some(food) = def.food() else { return };
+    if !inv.take(&def.id) {
+        return;
+    }
+    if let Some(mut stats) = stats {
+        stats.hunger = (stats.hunger + food.nutrition).min(stats.max_hunger);
+    }
+    if let Some(effect) = food.effect {
+        effects.apply(effect);
+    }
+    info!("Ethan ate the {}.", def.name);
+}
+
+fn status_effect_system(time: Res<Time>, mut effects: ResMut<StatusEffects>, stats: Option<ResMut<PlayerStats>>) {
+    let dt = time.delta_seconds();
+    if let Some(mut stats) = stats {
+        if effects.well_fed > 0.0 {
+            stats.health = (stats.health + WELL_FED_REGEN * dt).min(stats.max_health);
+        }
+        if effects.nausea > 0.0 {
+            stats.hunger = (stats.hunger - NAUSEA_HUNGER_DRAIN * dt).max(0.0);
+        }
+    }
+    effects.well_fed = (effects.well_fed - dt).max(0.0);
+    effects.nausea = (effects.nausea - dt).max(0.0);
+}
+
+/// Every game hour an empty spot by the market stalls may get fresh food
+fn food_restock_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<StandardMaterial>>,
+    mut hours: EventReader<HourChanged>,
+    handles: Res<Handles>,
+    spawn_info: Res<WorldSpawnInfo>,
+    pickups: Query<&Transform, With<ItemPickup>>,
+    mut rng: ResMut<LootRng>,
+) {
+    if hours.read().count() == 0 {
+        return;
+    }
+    let weights = WeightedIndex::new(MARKET_FOOD.iter().map(|(_, w)| *w)).expect("market food weights");
+    for spot in &spawn_info.food_spots {
+        let stocked = pickups.iter().any(|tf| (tf.translation - *spot).length() < FOOD_SPOT_RADIUS);
+        if stocked || !rng.0.gen_bool(RESTOCK_CHANCE) {
+            continue;
+        }
+        let item = MARKET_FOOD[weights.sample(&mut rng.0)].0;
+        spawn_food_pickup(&mut commands, &mut meshes, &mut mats, &handles, *spot, item);
+    }
+}
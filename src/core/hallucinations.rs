@@ -0,0 +1,522 @@
+use bevy::prelude::*;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+
+use crate::core::animation::{AnimTarget, Animated, CHARACTER_MODELS};
+use crate::core::appearance::Appearance;
+use crate::core::camera::CameraRig;
+use crate::core::hiding::{Hidden, HidingSpot};
+use crate::core::lod::FullSim;
+use crate::core::navigation::NavAgent;
+use crate::core::npc_ai::Npc;
+use crate::core::perception::Perception;
+use crate::core::player::{Ethan, Locomotion};
+use crate::core::sanity::{Sanity, SanityShock};
+use crate::core::school::{HidingKind, PropKind};
+use crate::core::seed::HallucinationRng;
+use crate::core::warden::{TheWarden, WARDEN_MODEL};
+use crate::core::world::MissionEntity;
+use crate::data::assets_loader::Handles;
+use crate::data::hallucinations::{load_hallucinations, HallucinationDef, HallucinationTable, HALLUCINATIONS_PATH};
+use crate::narrative::ActiveTimeline;
+use crate::states::GameState;
+
+/// Phantoms stand this far off, towards the edge of the view
+const PHANTOM_DISTANCE: (f32, f32) = (12.0, 18.0);
+const PHANTOM_VIEW_OFFSET_DEG: f32 = 35.0;
+/// The phantom Warden stands at the far end of whatever Ethan is looking down
+const PHANTOM_WARDEN_DISTANCE: (f32, f32) = (20.0, 28.0);
+/// Walk up to a phantom, or look straight at one that hides from sight, and it is gone
+const PHANTOM_VANISH_DISTANCE: f32 = 5.0;
+const PHANTOM_VANISH_ANGLE_DEG: f32 = 8.0;
+/// The reflection stands behind glass this far in front of Ethan...
+const MIRROR_DISTANCE: f32 = 1.5;
+/// ...and copies what he did this long ago
+const REFLECTION_DELAY_SECS: f32 = 0.4;
+/// Player positions kept for reflections to replay
+const TRAIL_SECS: f32 = 2.0;
+const LOCKER_SWING_DEG: f32 = 70.0;
+/// Door bangs per second
+const LOCKER_SWING_RATE: f32 = 1.5;
+/// Intensity multiplier range while a light flickers
+const FLICKER_RANGE: (f32, f32) = (0.05, 1.3);
+/// Each hallucination costs a little more sanity
+const HALLUCINATION_SHOCK: f32 = -0.02;
+/// Budget the director can bank with sanity at zero; it scales down with sanity
+const BUDGET_CAP: f32 = 10.0;
+/// Budget earned per second with sanity at zero, before pressure
+const BUDGET_REFILL: f32 = 0.2;
+/// Seconds between the director's rolls, and the least time between two scares
+const DIRECTOR_TICK_SECS: f32 = 1.0;
+const MIN_GAP_SECS: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum HallucinationKind {
+    /// A grey figure at the edge of sight that isn't there when you look
+    PhantomNpc,
+    /// The Warden at the end of the hall, gone a moment later
+    PhantomWarden,
+    /// Footsteps behind Ethan only he can hear
+    FakeFootsteps,
+    /// The HUD stutters and lies for a moment
+    UiGlitch,
+    /// A reflection in glass that moves a beat late
+    Reflection,
+    /// Nearby lockers bang open and shut on their own
+    LockerSwing,
+    /// Nearby lights stutter
+    LightFlicker,
+    /// NPCs around Ethan stop dead mid-motion
+    NpcFreeze,
+}
+
+impl HallucinationKind {
+    pub const ALL: [HallucinationKind; 8] = [
+        HallucinationKind::PhantomNpc,
+        HallucinationKind::PhantomWarden,
+        HallucinationKind::FakeFootsteps,
+        HallucinationKind::UiGlitch,
+        HallucinationKind::Reflection,
+        HallucinationKind::LockerSwing,
+        HallucinationKind::LightFlicker,
+        HallucinationKind::NpcFreeze,
+    ];
+}
+
+/// Stage the `HallucinationDef` with this id now, whatever the budget says
+#[derive(Event, Debug, Clone)]
+pub struct Hallucinate {
+    pub id: String,
+}
+
+/// Decides when the unscripted scares happen. Its budget fills faster the
+/// worse Ethan's state, and every scare spends from it.
+#[derive(Resource, Debug, Default)]
+pub struct HallucinationDirector {
+    pub budget: f32,
+    /// Seconds left per `HallucinationDef::id`
+    cooldowns: HashMap<String, f32>,
+    since_last: f32,
+    tick: f32,
+}
+
+impl HallucinationDirector {
+    fn ready(&self, def: &HallucinationDef) -> bool {
+        self.cooldowns.get(&def.id).is_none_or(|c| *c <= 0.0)
+    }
+}
+
+/// HUD glitch time left; `systems::ui` scrambles the bars while it runs
+#[derive(Resource, Debug, Default)]
+pub struct UiGlitch {
+    pub remaining: f32,
+}
+
+#[derive(Component)]
+pub struct Phantom {
+    pub remaining: f32,
+    /// Gone the moment Ethan looks straight at it
+    pub shy: bool,
+}
+
+/// Ethan's double behind imaginary glass
+#[derive(Component)]
+pub struct Reflection {
+    plane_point: Vec3,
+    normal: Vec3,
+    remaining: f32,
+}
+
+/// On a locker's mesh while its door bangs; `base` is put back afterwards
+#[derive(Component)]
+struct LockerSwing {
+    remaining: f32,
+    base: Transform,
+    hinge: Vec3,
+}
+
+#[derive(Component)]
+struct Flicker {
+    remaining: f32,
+    base_intensity: f32,
+}
+
+/// NPC stopped dead by a hallucination; movement and animation resume after
+#[derive(Component)]
+pub struct Frozen {
+    remaining: f32,
+    speed: f32,
+}
+
+/// Where Ethan has been over the last `TRAIL_SECS`
+#[derive(Resource, Default)]
+struct PlayerTrail(VecDeque<(f32, Transform)>);
+
+pub struct HallucinationPlugin;
+
+impl Plugin for HallucinationPlugin {
+    fn build(&self, app: &mut App) {
+        let table = load_hallucinations(HALLUCINATIONS_PATH).unwrap_or_else(|err| {
+            error!("Failed to load hallucinations, the director stays quiet: {:?}", err);
+            HallucinationTable::default()
+        });
+        app.insert_resource(table)
+           .init_resource::<HallucinationDirector>()
+           .init_resource::<UiGlitch>()
+           .init_resource::<PlayerTrail>()
+           .add_event::<Hallucinate>()
+           // cues come from whatever timeline is playing, in or out of the mission
+           .add_systems(Update, timeline_hallucinations_system
+               .run_if(resource_exists::<ActiveTimeline>)
+               .before(start_hallucination_system))
+           .add_systems(Update, (
+               record_trail_system,
+               director_system,
+               start_hallucination_system,
+               phantom_system,
+               reflection_system,
+               locker_swing_system,
+               flicker_system,
+               frozen_system,
+               ui_glitch_decay_system,
+           ).chain().run_if(in_state(GameState::Mission1)));
+    }
+}
+
+fn record_trail_system(time: Res<Time>, mut trail: ResMut<PlayerTrail>, player_q: Query<&Transform, With<Ethan>>) {
+    let Ok(tf) = player_q.get_single() else { return };
+    let now = time.elapsed_seconds();
+    trail.0.push_back((now, *tf));
+    while trail.0.front().is_some_and(|(t, _)| now - t > TRAIL_SECS) {
+        trail.0.pop_front();
+    }
+}
+
+/// Frames can list hallucination ids under `hallucinate`, staged as the frame comes up
+fn timeline_hallucinations_system(
+    active: Res<ActiveTimeline>,
+    mut last: Local<Option<(String, usize)>>,
+    mut events: EventWriter<Hallucinate>,
+) {
+    // a replay of the same timeline cues its frames afresh
+    if active.is_added() {
+        *last = None;
+    }
+    let Some(frame) = active.current_frame() else { return };
+    let key = (active.timeline.title.clone(), active.current);
+    if last.as_ref() == Some(&key) {
+        return;
+    }
+    *last = Some(key);
+    for id in &frame.hallucinate {
+        events.send(Hallucinate { id: id.clone() });
+    }
+}
+
+/// Fill the budget from Ethan's state and, now and then, spend it on a scare
+/// he is fragile enough for. Holds off while the Warden has eyes on him: a
+/// chase is frightening enough without the screen lying as well.
+fn director_system(
+    time: Res<Time>,
+    sanity: Res<Sanity>,
+    table: Res<HallucinationTable>,
+    mut director: ResMut<HallucinationDirector>,
+    mut rng: ResMut<HallucinationRng>,
+    player_q: Query<(Option<&Locomotion>, Option<&Hidden>), With<Ethan>>,
+    wardens: Query<&Perception, With<TheWarden>>,
+    mut events: EventWriter<Hallucinate>,
+) {
+    let dt = time.delta_seconds();
+    for cooldown in director.cooldowns.values_mut() {
+        *cooldown -= dt;
+    }
+    director.since_last += dt;
+    let Ok((motion, hidden)) = player_q.get_single() else { return };
+
+    let dread = 1.0 - sanity.value;
+    let suspicion = wardens.iter().map(|p| p.suspicion).fold(0.0, f32::max);
+    let mut pressure = 1.0 + suspicion;
+    if hidden.is_some() {
+        pressure += 0.5;
+    }
+    if motion.is_some_and(|m| m.exhausted) {
+        pressure += 0.3;
+    }
+    let cap = BUDGET_CAP * dread;
+    director.budget = (director.budget + BUDGET_REFILL * dread * pressure * dt).min(cap);
+
+    director.tick += dt;
+    if director.tick < DIRECTOR_TICK_SECS {
+        return;
+    }
+    director.tick = 0.0;
+    if director.since_last < MIN_GAP_SECS || cap <= 0.0 || wardens.iter().any(|p| p.sees_player) {
+        return;
+    }
+    // a fuller budget means something is more likely to happen
+    if !rng.0.gen_bool((director.budget / cap).clamp(0.0, 1.0) as f64 * 0.5) {
+        return;
+    }
+
+    let eligible = table
+        .defs
+        .iter()
+        .filter(|d| !d.scripted && sanity.value <= d.max_sanity && d.cost <= director.budget && director.ready(d))
+        .collect::<Vec<_>>();
+    let Ok(pick) = WeightedIndex::new(eligible.iter().map(|d| d.weight)) else { return };
+    let def = eligible[pick.sample(&mut rng.0)];
+    director.budget -= def.cost;
+    events.send(Hallucinate { id: def.id.clone() });
+}
+
+fn start_hallucination_system(
+    mut commands: Commands,
+    mut events: EventReader<Hallucinate>,
+    table: Res<HallucinationTable>,
+    mut director: ResMut<HallucinationDirector>,
+    mut rng: ResMut<HallucinationRng>,
+    handles: Res<Handles>,
+    asset_server: Res<AssetServer>,
+    player_q: Query<&Transform, With<Ethan>>,
+    cameras: Query<&GlobalTransform, With<CameraRig>>,
+    lockers: Query<(&GlobalTransform, &HidingSpot, &Children)>,
+    meshes: Query<&Transform, (With<Handle<Mesh>>, Without<LockerSwing>)>,
+    points: Query<(Entity, &GlobalTransform, &PointLight), Without<Flicker>>,
+    spots: Query<(Entity, &GlobalTransform, &SpotLight), Without<Flicker>>,
+    npcs: Query<(Entity, &Transform, &NavAgent), (With<Npc>, With<FullSim>, Without<Frozen>)>,
+    mut glitch: ResMut<UiGlitch>,
+    mut shocks: EventWriter<SanityShock>,
+) {
+    for ev in events.read() {
+        let Some(def) = table.get(&ev.id) else {
+            warn!("Unknown hallucination '{}'", ev.id);
+            continue;
+        };
+        let Ok(tf) = player_q.get_single() else { return };
+        let facing = cameras.get_single().map_or(tf.back(), |c| c.forward());
+        let facing = Vec3::new(facing.x, 0.0, facing.z).normalize_or_zero();
+        let near = |p: Vec3| p.distance(tf.translation) <= def.radius;
+
+        match def.kind {
+            HallucinationKind::PhantomNpc => {
+                let side = if rng.0.gen_bool(0.5) { 1.0 } else { -1.0 };
+                let dir = Quat::from_rotation_y((side * PHANTOM_VIEW_OFFSET_DEG).to_radians()).mul_vec3(facing);
+                let pos = tf.translation + dir * rng.0.gen_range(PHANTOM_DISTANCE.0..PHANTOM_DISTANCE.1);
+                // anyone but the Warden
+                let models = CHARACTER_MODELS.iter().copied().filter(|m| *m != WARDEN_MODEL).collect::<Vec<_>>();
+                let model = *models.choose(&mut rng.0).unwrap();
+                commands.spawn((
+                    SceneBundle {
+                        scene: handles.character(model),
+                        transform: Transform::from_translation(pos).looking_to(dir, Vec3::Y),
+                        ..default()
+                    },
+                    Animated::new(model),
+                    Appearance { archetype: "phantom".into(), tint: Color::srgb(0.5, 0.55, 0.65), voice: String::new() },
+                    Phantom { remaining: def.duration_secs, shy: true },
+                    MissionEntity,
+                ));
+            }
+            HallucinationKind::PhantomWarden => {
+                let pos = tf.translation + facing * rng.0.gen_range(PHANTOM_WARDEN_DISTANCE.0..PHANTOM_WARDEN_DISTANCE.1);
+                commands.spawn((
+                    SceneBundle {
+                        scene: handles.punk.clone(),
+                        // facing Ethan: characters look along their back()
+                        transform: Transform::from_translation(pos).looking_to(facing, Vec3::Y),
+                        ..default()
+                    },
+                    Animated::new(WARDEN_MODEL),
+                    Phantom { remaining: def.duration_secs, shy: false },
+                    MissionEntity,
+                ));
+            }
+            HallucinationKind::FakeFootsteps | HallucinationKind::UiGlitch => {}
+            HallucinationKind::Reflection => {
+                let plane_point = tf.translation + facing * MIRROR_DISTANCE;
+                commands.spawn((
+                    SceneBundle {
+                        scene: handles.man.clone(),
+                        transform: mirrored(tf, plane_point, facing),
+                        ..default()
+                    },
+                    Animated::new("Man.glb"),
+                    Reflection { plane_point, normal: facing, remaining: def.duration_secs },
+                    MissionEntity,
+                ));
+            }
+            HallucinationKind::LockerSwing => {
+                for (gt, spot, children) in &lockers {
+                    if spot.kind != HidingKind::Locker || spot.occupant.is_some() || !near(gt.translation()) {
+                        continue;
+                    }
+                    // the door is the visible box; swing it about its left edge
+                    for &child in children {
+                        if let Ok(mesh_tf) = meshes.get(child) {
+                            let size = PropKind::Locker.size();
+                            let hinge = Vec3::new(-size.x * 0.5, 0.0, size.z * 0.5);
+                            commands.entity(child).insert(LockerSwing { remaining: def.duration_secs, base: *mesh_tf, hinge });
+                        }
+                    }
+                }
+            }
+            HallucinationKind::LightFlicker => {
+                for (entity, gt, light) in &points {
+                    if near(gt.translation()) {
+                        commands.entity(entity).insert(Flicker { remaining: def.duration_secs, base_intensity: light.intensity });
+                    }
+                }
+                for (entity, gt, light) in &spots {
+                    if near(gt.translation()) {
+                        commands.entity(entity).insert(Flicker { remaining: def.duration_secs, base_intensity: light.intensity });
+                    }
+                }
+            }
+            HallucinationKind::NpcFreeze => {
+                for (entity, npc_tf, agent) in &npcs {
+                    if near(npc_tf.translation) {
+                        commands.entity(entity).insert(Frozen { remaining: def.duration_secs, speed: agent.speed });
+                    }
+                }
+            }
+        }
+        if def.kind == HallucinationKind::UiGlitch {
+            glitch.remaining = def.duration_secs;
+        }
+        // sounds are only in Ethan's head: no NoiseEvent for anyone else to hear
+        if let Some(sound) = &def.sound {
+            commands.spawn(AudioBundle { source: asset_server.load(sound.clone()), settings: PlaybackSettings::DESPAWN });
+        }
+
+        director.cooldowns.insert(def.id.clone(), def.cooldown_secs);
+        director.since_last = 0.0;
+        shocks.send(SanityShock { amount: HALLUCINATION_SHOCK });
+        info!("Hallucination: {}", def.id);
+    }
+}
+
+/// Phantoms fade when approached, when stared at if they're shy, or after a while
+fn phantom_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    player_q: Query<&Transform, With<Ethan>>,
+    cameras: Query<&GlobalTransform, With<CameraRig>>,
+    mut phantoms: Query<(Entity, &Transform, &mut Phantom), Without<Ethan>>,
+) {
+    let player = player_q.get_single().ok();
+    let view = cameras.get_single().ok();
+    for (entity, tf, mut phantom) in &mut phantoms {
+        phantom.remaining -= time.delta_seconds();
+        let near = player.is_some_and(|p| p.translation.distance(tf.translation) < PHANTOM_VANISH_DISTANCE);
+        let stared = phantom.shy
+            && view.is_some_and(|v| {
+                let to = tf.translation + Vec3::Y - v.translation();
+                v.forward().angle_between(to).to_degrees() < PHANTOM_VANISH_ANGLE_DEG
+            });
+        if phantom.remaining <= 0.0 || near || stared {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// The reflection replays Ethan's movement from a moment ago, mirrored in the glass
+fn reflection_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    trail: Res<PlayerTrail>,
+    mut reflections: Query<(Entity, &mut Transform, &mut Reflection), Without<Ethan>>,
+) {
+    let then = time.elapsed_seconds() - REFLECTION_DELAY_SECS;
+    let past = trail.0.iter().rev().find(|(t, _)| *t <= then).or(trail.0.front()).map(|(_, tf)| *tf);
+    for (entity, mut tf, mut reflection) in &mut reflections {
+        reflection.remaining -= time.delta_seconds();
+        if reflection.remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if let Some(past) = past {
+            *tf = mirrored(&past, reflection.plane_point, reflection.normal);
+        }
+    }
+}
+
+fn locker_swing_system(mut commands: Commands, time: Res<Time>, mut doors: Query<(Entity, &mut Transform, &mut LockerSwing)>) {
+    let t = time.elapsed_seconds();
+    for (entity, mut tf, mut swing) in &mut doors {
+        swing.remaining -= time.delta_seconds();
+        if swing.remaining <= 0.0 {
+            *tf = swing.base;
+            commands.entity(entity).remove::<LockerSwing>();
+            continue;
+        }
+        let open = (t * LOCKER_SWING_RATE * std::f32::consts::TAU).sin().max(0.0);
+        let rotation = Quat::from_rotation_y(-(open * LOCKER_SWING_DEG).to_radians());
+        let hinge = swing.base.translation + swing.hinge;
+        *tf = swing.base;
+        tf.rotate_around(hinge, rotation);
+    }
+}
+
+fn flicker_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rng: ResMut<HallucinationRng>,
+    mut lights: Query<(Entity, &mut Flicker, Option<&mut PointLight>, Option<&mut SpotLight>)>,
+) {
+    for (entity, mut flicker, point, spot) in &mut lights {
+        flicker.remaining -= time.delta_seconds();
+        let intensity = if flicker.remaining <= 0.0 {
+            commands.entity(entity).remove::<Flicker>();
+            flicker.base_intensity
+        } else {
+            flicker.base_intensity * rng.0.gen_range(FLICKER_RANGE.0..FLICKER_RANGE.1)
+        };
+        if let Some(mut light) = point {
+            light.intensity = intensity;
+        }
+        if let Some(mut light) = spot {
+            light.intensity = intensity;
+        }
+    }
+}
+
+/// Frozen NPCs hold still with their rig paused mid-clip
+fn frozen_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut npcs: Query<(Entity, &mut Frozen, &mut NavAgent, Option<&AnimTarget>)>,
+    mut players: Query<&mut AnimationPlayer>,
+) {
+    for (entity, mut frozen, mut agent, target) in &mut npcs {
+        let mut rig = target.and_then(|t| players.get_mut(t.0).ok());
+        frozen.remaining -= time.delta_seconds();
+        if frozen.remaining <= 0.0 {
+            agent.speed = frozen.speed;
+            if let Some(rig) = rig.as_mut() {
+                rig.resume_all();
+            }
+            commands.entity(entity).remove::<Frozen>();
+            continue;
+        }
+        agent.speed = 0.0;
+        agent.velocity = Vec3::ZERO;
+        if let Some(rig) = rig.as_mut() {
+            rig.pause_all();
+        }
+    }
+}
+
+fn ui_glitch_decay_system(time: Res<Time>, mut glitch: ResMut<UiGlitch>) {
+    glitch.remaining = (glitch.remaining - time.delta_seconds()).max(0.0);
+}
+
+/// `tf` reflected in the plane through `point` with unit `normal`
+fn mirrored(tf: &Transform, point: Vec3, normal: Vec3) -> Transform {
+    let reflect = |v: Vec3| v - 2.0 * v.dot(normal) * normal;
+    let pos = point + reflect(tf.translation - point);
+    // characters face their back() (+Z); mirror that and face it the same way
+    let front = reflect(*tf.back());
+    Transform::from_translation(pos).looking_to(-front, Vec3::Y).with_scale(tf.scale)
+}
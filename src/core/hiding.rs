@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+
+use crate::core::perception::{NoiseEvent, NoiseSource, Perception};
+use crate::core::player::Ethan;
+use crate::core::school::HidingKind;
+use crate::systems::controls::Controls;
+use crate::systems::interact::{Interact, InteractSet, InteractTarget};
+
+/// How close the player must be to use a hiding spot
+pub const USE_RANGE: f32 = 1.3;
+/// Furthest the view can swing left or right while peeking
+const MAX_PEEK_DEG: f32 = 35.0;
+const PEEK_SPEED_DEG: f32 = 90.0;
+/// Breath spent per second while holding it (full lungs last ~6.5 s)
+const BREATH_DRAIN: f32 = 0.15;
+const BREATH_REGEN: f32 = 0.25;
+const BREATHING_LOUDNESS: f32 = 2.5;
+const GASP_LOUDNESS: f32 = 7.0;
+const BREATHING_INTERVAL: f32 = 1.5;
+
+/// Somewhere the player can hide. `facing` points out of the spot; the player
+/// steps out that way and peeks along it.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HidingSpot {
+    pub kind: HidingKind,
+    pub facing: Vec3,
+    pub occupant: Option<Entity>,
+}
+
+impl HidingSpot {
+    pub fn new(kind: HidingKind, facing: Vec3) -> Self {
+        Self { kind, facing, occupant: None }
+    }
+
+    fn eye_height(&self) -> f32 {
+        match self.kind {
+            HidingKind::Locker => 1.55,
+            HidingKind::UnderDesk => 0.45,
+            HidingKind::Bush => 0.9,
+        }
+    }
+}
+
+/// On the player while inside a hiding spot
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Hidden {
+    pub spot: Entity,
+    /// A watcher saw the player climb in, so hiding doesn't fool them
+    pub observed: bool,
+    pub peek_deg: f32,
+    /// First-person view from inside the spot, for the camera rig
+    pub view: Transform,
+}
+
+/// Lung capacity for holding breath while hidden, 0..1
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Breath {
+    pub value: f32,
+    pub holding: bool,
+}
+
+impl Default for Breath {
+    fn default() -> Self {
+        Self { value: 1.0, holding: false }
+    }
+}
+
+pub struct HidingPlugin;
+
+impl Plugin for HidingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            hiding_interact_system,
+            peek_system,
+            breath_system,
+        ).chain().after(InteractSet).run_if(in_state(crate::states::GameState::Mission1)))
+           .add_systems(OnExit(crate::states::GameState::Mission1), vacate_spots);
+    }
+}
+
+/// The player is despawned when caught, so nobody is left inside
+fn vacate_spots(mut spots: Query<&mut HidingSpot>) {
+    for mut spot in &mut spots {
+        spot.occupant = None;
+    }
+}
+
+fn hiding_interact_system(
+    mut commands: Commands,
+    mut interacts: EventReader<Interact>,
+    watchers: Query<&Perception>,
+    mut player_q: Query<(Entity, &mut Transform, Option<&Hidden>), With<Ethan>>,
+    mut spots: Query<(&GlobalTransform, &mut HidingSpot), Without<Ethan>>,
+) {
+    let Some(target) = interacts.read().map(|ev| ev.target).last() else { return };
+    let Ok((player, mut tf, hidden)) = player_q.get_single_mut() else { return };
+
+    match (target, hidden) {
+        (InteractTarget::LeaveSpot, Some(hidden)) => {
+            // climb out in front of the spot
+            if let Ok((gt, mut spot)) = spots.get_mut(hidden.spot) {
+                spot.occupant = None;
+                tf.translation = gt.translation() + spot.facing * 0.9;
+                tf.look_to(-spot.facing, Vec3::Y);
+            }
+            commands.entity(player).remove::<Hidden>();
+            info!("Left hiding spot.");
+        }
+        (InteractTarget::HidingSpot(entity), None) => {
+            let Ok((gt, mut spot)) = spots.get_mut(entity) else { return };
+            if spot.occupant.is_some() {
+                return;
+            }
+            let observed = watchers.iter().any(|p| p.sees_player);
+            spot.occupant = Some(player);
+            tf.translation = gt.translation();
+            let view = spot_view(gt.translation(), &spot, 0.0);
+            commands.entity(player).insert(Hidden { spot: entity, observed, peek_deg: 0.0, view });
+            info!("Hiding in {:?}{}", spot.kind, if observed { " (seen!)" } else { "" });
+        }
+        _ => {}
+    }
+}
+
+fn spot_view(origin: Vec3, spot: &HidingSpot, peek_deg: f32) -> Transform {
+    let eye = origin + Vec3::Y * spot.eye_height() + spot.facing * 0.2;
+    let dir = Quat::from_rotation_y(peek_deg.to_radians()).mul_vec3(spot.facing);
+    Transform::from_translation(eye).looking_to(dir, Vec3::Y)
+}
+
+/// Strafe keys swing the view left/right from inside the spot; the camera
+/// rig looks out through the gap along `Hidden::view`
+fn peek_system(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    spots: Query<(&GlobalTransform, &HidingSpot)>,
+    mut player_q: Query<&mut Hidden, With<Ethan>>,
+) {
+    let Ok(mut hidden) = player_q.get_single_mut() else { return };
+    let Ok((gt, spot)) = spots.get(hidden.spot) else { return };
+
+    let mut input = 0.0;
+    if keyboard.pressed(controls.move_left) { input += 1.0; }
+    if keyboard.pressed(controls.move_right) { input -= 1.0; }
+    let target = if input == 0.0 { 0.0 } else { input * MAX_PEEK_DEG };
+    let step = PEEK_SPEED_DEG * time.delta_seconds();
+    hidden.peek_deg += (target - hidden.peek_deg).clamp(-step, step);
+    hidden.view = spot_view(gt.translation(), spot, hidden.peek_deg);
+}
+
+/// Hold breath while hidden to stay silent; run out and you gasp
+fn breath_system(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    mut since_breath: Local<f32>,
+    // after a gasp the key must be released before holding again
+    mut winded: Local<bool>,
+    mut player_q: Query<(&Transform, &mut Breath, Option<&Hidden>), With<Ethan>>,
+    mut noise: EventWriter<NoiseEvent>,
+) {
+    let Ok((tf, mut breath, hidden)) = player_q.get_single_mut() else { return };
+    let dt = time.delta_seconds();
+
+    if !keyboard.pressed(controls.hold_breath) {
+        *winded = false;
+    }
+    let wants_hold = hidden.is_some() && keyboard.pressed(controls.hold_breath) && !*winded;
+    if wants_hold && breath.value > 0.0 {
+        breath.holding = true;
+        breath.value = (breath.value - BREATH_DRAIN * dt).max(0.0);
+        if breath.value <= 0.0 {
+            breath.holding = false;
+            *winded = true;
+            noise.send(NoiseEvent { position: tf.translation, loudness: GASP_LOUDNESS, source: NoiseSource::Breathing });
+            info!("Gasped for air!");
+        }
+        return;
+    }
+
+    breath.holding = false;
+    breath.value = (breath.value + BREATH_REGEN * dt).min(1.0);
+    // only audible from inside the spot; out in the open footsteps drown it
+    if hidden.is_some() {
+        *since_breath += dt;
+        if *since_breath >= BREATHING_INTERVAL {
+            *since_breath = 0.0;
+            noise.send(NoiseEvent { position: tf.translation, loudness: BREATHING_LOUDNESS, source: NoiseSource::Breathing });
+        }
+    }
+}
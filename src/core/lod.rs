@@ -0,0 +1,340 @@
+use bevy::ecs::entity::{EntityHashMap, EntityHashSet};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::config::{FULL_SIM_RADIUS, LOD_HYSTERESIS, SIMULATION_RADIUS};
+use crate::core::player::Ethan;
+use crate::states::GameState;
+
+/// Cell size of the simulation grid; about a city block's worth of pavement
+const SIM_GRID_CELL: f32 = 32.0;
+
+/// How much simulation an entity gets, by distance from Ethan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimTier {
+    /// Full AI: navigation, avoidance, reactions
+    Full,
+    /// Straight-line movement along planned legs, a few times a second
+    Coarse,
+    /// Only schedule bookkeeping, once per in-game hour
+    #[default]
+    Dormant,
+}
+
+impl SimTier {
+    /// Tier for an entity at `distance`, given the tier it is in now. Moving
+    /// to a nearer tier means getting `LOD_HYSTERESIS` inside the boundary,
+    /// moving out means getting that far past it, so nothing flickers at the edge.
+    pub fn for_distance(current: SimTier, distance: f32) -> SimTier {
+        let full = if current == SimTier::Full { FULL_SIM_RADIUS + LOD_HYSTERESIS } else { FULL_SIM_RADIUS - LOD_HYSTERESIS };
+        let coarse = if current == SimTier::Dormant { SIMULATION_RADIUS - LOD_HYSTERESIS } else { SIMULATION_RADIUS + LOD_HYSTERESIS };
+        if distance < full {
+            SimTier::Full
+        } else if distance < coarse {
+            SimTier::Coarse
+        } else {
+            SimTier::Dormant
+        }
+    }
+}
+
+/// Opts an entity into tiered simulation
+#[derive(Component, Debug, Clone, Default)]
+pub struct SimLod {
+    pub tier: SimTier,
+    /// Simulated seconds owed, for tiers that don't step every frame
+    pub pending: f32,
+}
+
+/// Marker for entities in `SimTier::Full`, so systems only visit those
+#[derive(Component)]
+pub struct FullSim;
+
+/// Marker for entities in `SimTier::Coarse`
+#[derive(Component)]
+pub struct CoarseSim;
+
+/// Sent when an entity moves between tiers, so its owner can hand state over
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SimTierChanged {
+    pub entity: Entity,
+    pub from: SimTier,
+    pub to: SimTier,
+}
+
+/// Uniform grid over the ground plane (x/z) for "who is near here" queries
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell: f32,
+    cells: HashMap<IVec2, Vec<Entity>>,
+    entries: EntityHashMap<(IVec2, Vec3)>,
+}
+
+impl SpatialHash {
+    pub fn new(cell: f32) -> Self {
+        Self { cell, cells: HashMap::new(), entries: EntityHashMap::default() }
+    }
+
+    fn key(&self, pos: Vec3) -> IVec2 {
+        IVec2::new((pos.x / self.cell).floor() as i32, (pos.z / self.cell).floor() as i32)
+    }
+
+    /// Add an entity, or move it if it is already in the grid
+    pub fn insert(&mut self, entity: Entity, pos: Vec3) {
+        let key = self.key(pos);
+        match self.entries.insert(entity, (key, pos)) {
+            Some((old, _)) if old == key => {}
+            Some((old, _)) => {
+                self.unlink(entity, old);
+                self.cells.entry(key).or_default().push(entity);
+            }
+            None => self.cells.entry(key).or_default().push(entity),
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some((key, _)) = self.entries.remove(&entity) {
+            self.unlink(entity, key);
+        }
+    }
+
+    fn unlink(&mut self, entity: Entity, key: IVec2) {
+        if let Some(bucket) = self.cells.get_mut(&key) {
+            if let Some(i) = bucket.iter().position(|e| *e == entity) {
+                bucket.swap_remove(i);
+            }
+            if bucket.is_empty() {
+                self.cells.remove(&key);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entities within `radius` of `center` on the ground plane, with their positions
+    pub fn within(&self, center: Vec3, radius: f32) -> impl Iterator<Item = (Entity, Vec3)> + '_ {
+        let lo = self.key(center - Vec3::new(radius, 0.0, radius));
+        let hi = self.key(center + Vec3::new(radius, 0.0, radius));
+        let r2 = radius * radius;
+        (lo.x..=hi.x)
+            .flat_map(move |x| (lo.y..=hi.y).map(move |z| IVec2::new(x, z)))
+            .filter_map(move |k| self.cells.get(&k))
+            .flatten()
+            .filter_map(move |e| self.entries.get(e).map(|(_, p)| (*e, *p)))
+            .filter(move |(_, p)| Vec2::new(p.x - center.x, p.z - center.z).length_squared() <= r2)
+    }
+}
+
+/// Positions of every `SimLod` entity, kept up to date as they move
+#[derive(Resource)]
+pub struct SimGrid(pub SpatialHash);
+
+impl Default for SimGrid {
+    fn default() -> Self {
+        Self(SpatialHash::new(SIM_GRID_CELL))
+    }
+}
+
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimGrid>()
+           .add_event::<SimTierChanged>()
+           .add_systems(Update, (track_sim_grid_system, assign_tiers_system)
+               .chain()
+               .run_if(in_state(GameState::OpenWorld).or_else(in_state(GameState::Mission1))));
+    }
+}
+
+/// Only entities that moved (or just appeared) touch the grid
+fn track_sim_grid_system(
+    mut grid: ResMut<SimGrid>,
+    moved: Query<(Entity, &Transform), (With<SimLod>, Changed<Transform>)>,
+    mut removed: RemovedComponents<SimLod>,
+) {
+    for entity in removed.read() {
+        grid.0.remove(entity);
+    }
+    for (entity, tf) in &moved {
+        grid.0.insert(entity, tf.translation);
+    }
+}
+
+/// Re-tier everything near Ethan, plus whatever was awake last frame and has
+/// since dropped out of range. Dormant entities far away are never visited.
+fn assign_tiers_system(
+    mut commands: Commands,
+    grid: Res<SimGrid>,
+    player_q: Query<&Transform, With<Ethan>>,
+    mut lod_q: Query<&mut SimLod>,
+    mut changed: EventWriter<SimTierChanged>,
+    mut awake: Local<EntityHashSet>,
+) {
+    let Ok(player) = player_q.get_single() else { return };
+    let center = player.translation;
+
+    let mut seen = EntityHashSet::with_capacity_and_hasher(awake.len(), Default::default());
+    let mut retier = |entity: Entity, distance: f32, seen: &mut EntityHashSet| {
+        let Ok(mut lod) = lod_q.get_mut(entity) else { return };
+        let to = SimTier::for_distance(lod.tier, distance);
+        if to != SimTier::Dormant {
+            seen.insert(entity);
+        }
+        if to == lod.tier {
+            return;
+        }
+        let from = lod.tier;
+        lod.tier = to;
+        lod.pending = 0.0;
+        let Some(mut e) = commands.get_entity(entity) else { return };
+        match to {
+            SimTier::Full => e.remove::<CoarseSim>().insert(FullSim),
+            SimTier::Coarse => e.remove::<FullSim>().insert(CoarseSim),
+            SimTier::Dormant => e.remove::<(FullSim, CoarseSim)>(),
+        };
+        changed.send(SimTierChanged { entity, from, to });
+    };
+
+    for (entity, pos) in grid.0.within(center, SIMULATION_RADIUS + LOD_HYSTERESIS) {
+        retier(entity, pos.distance(center), &mut seen);
+    }
+    for entity in awake.iter().filter(|e| !seen.contains(*e)).copied().collect::<Vec<_>>() {
+        retier(entity, f32::INFINITY, &mut seen);
+    }
+    *awake = seen;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn tiers_switch_with_hysteresis() {
+        assert_eq!(SimTier::for_distance(SimTier::Dormant, 10.0), SimTier::Full);
+        // just outside the full radius: a full entity stays full, a coarse one stays coarse
+        assert_eq!(SimTier::for_distance(SimTier::Full, FULL_SIM_RADIUS + 1.0), SimTier::Full);
+        assert_eq!(SimTier::for_distance(SimTier::Coarse, FULL_SIM_RADIUS - 1.0), SimTier::Coarse);
+        assert_eq!(SimTier::for_distance(SimTier::Full, FULL_SIM_RADIUS + LOD_HYSTERESIS + 1.0), SimTier::Coarse);
+        assert_eq!(SimTier::for_distance(SimTier::Coarse, SIMULATION_RADIUS + 1.0), SimTier::Coarse);
+        assert_eq!(SimTier::for_distance(SimTier::Dormant, SIMULATION_RADIUS - 1.0), SimTier::Dormant);
+        assert_eq!(SimTier::for_distance(SimTier::Coarse, f32::INFINITY), SimTier::Dormant);
+    }
+
+    #[test]
+    fn spatial_hash_finds_moves_and_forgets() {
+        let mut hash = SpatialHash::new(10.0);
+        let (a, b) = (Entity::from_raw(1), Entity::from_raw(2));
+        hash.insert(a, Vec3::new(1.0, 0.0, 1.0));
+        hash.insert(b, Vec3::new(55.0, 0.0, -3.0));
+        let near = |h: &SpatialHash| h.within(Vec3::ZERO, 20.0).map(|(e, _)| e).collect::<Vec<_>>();
+        assert_eq!(near(&hash), vec![a]);
+
+        hash.insert(b, Vec3::new(-12.0, 0.0, 4.0));
+        hash.insert(a, Vec3::new(100.0, 0.0, 100.0));
+        assert_eq!(near(&hash), vec![b]);
+
+        hash.remove(b);
+        assert!(near(&hash).is_empty());
+        assert_eq!(hash.len(), 1);
+    }
+
+    /// What `assign_tiers_system` replaces: every `SimLod` entity re-tiered
+    /// each frame. The reference the grid is checked against.
+    fn scan_tiers_system(
+        mut commands: Commands,
+        player_q: Query<&Transform, With<Ethan>>,
+        mut lod_q: Query<(Entity, &Transform, &mut SimLod)>,
+    ) {
+        let Ok(player) = player_q.get_single() else { return };
+        for (entity, tf, mut lod) in &mut lod_q {
+            let to = SimTier::for_distance(lod.tier, tf.translation.distance(player.translation));
+            if to == lod.tier {
+                continue;
+            }
+            lod.tier = to;
+            let mut e = commands.entity(entity);
+            match to {
+                SimTier::Full => e.remove::<CoarseSim>().insert(FullSim),
+                SimTier::Coarse => e.remove::<FullSim>().insert(CoarseSim),
+                SimTier::Dormant => e.remove::<(FullSim, CoarseSim)>(),
+            };
+        }
+    }
+
+    /// Ethan at the origin and `count` agents scattered over the town
+    fn city(count: usize) -> (World, Vec<Entity>) {
+        let mut world = World::new();
+        world.init_resource::<SimGrid>();
+        world.init_resource::<Events<SimTierChanged>>();
+        world.spawn((Ethan, Transform::default()));
+        let mut rng = StdRng::seed_from_u64(7);
+        let agents = (0..count)
+            .map(|_| {
+                let pos = Vec3::new(rng.gen_range(-800.0..800.0), 0.0, rng.gen_range(-800.0..800.0));
+                world.spawn((Transform::from_translation(pos), SimLod::default())).id()
+            })
+            .collect();
+        (world, agents)
+    }
+
+    /// Agents in simulation range walk towards Ethan, crossing tiers as they
+    /// go; dormant ones further out stay put, as they do between game hours
+    fn walk_awake_agents(world: &mut World, agents: &[Entity]) {
+        for &entity in agents {
+            let pos = world.get::<Transform>(entity).unwrap().translation;
+            let distance = pos.length();
+            if !(0.01..=SIMULATION_RADIUS + LOD_HYSTERESIS * 2.0).contains(&distance) {
+                continue;
+            }
+            world.get_mut::<Transform>(entity).unwrap().translation -= pos / distance * distance.min(1.5);
+        }
+    }
+
+    /// The real tracking and tiering systems over a city of agents: the same
+    /// tiers as a full scan every frame, within a fixed slice of the frame.
+    /// Run with `cargo test --release tier_pass_benchmark -- --ignored`.
+    #[test]
+    #[ignore]
+    fn tier_pass_benchmark() {
+        const PASSES: u32 = 100;
+        const FRAME_BUDGET: Duration = Duration::from_millis(1);
+        for count in [1_000, 5_000, 20_000] {
+            let (mut grid_world, agents) = city(count);
+            let (mut scan_world, _) = city(count);
+            let mut grid_schedule = Schedule::default();
+            grid_schedule.add_systems((track_sim_grid_system, assign_tiers_system).chain());
+            let mut scan_schedule = Schedule::default();
+            scan_schedule.add_systems(scan_tiers_system);
+
+            let mut grid_time = Duration::ZERO;
+            for pass in 0..PASSES {
+                walk_awake_agents(&mut grid_world, &agents);
+                walk_awake_agents(&mut scan_world, &agents);
+
+                let start = Instant::now();
+                grid_schedule.run(&mut grid_world);
+                grid_time += start.elapsed();
+                scan_schedule.run(&mut scan_world);
+                grid_world.resource_mut::<Events<SimTierChanged>>().update();
+
+                for &entity in &agents {
+                    let tier = |w: &World| w.get::<SimLod>(entity).unwrap().tier;
+                    assert_eq!(tier(&grid_world), tier(&scan_world), "{count} agents, pass {pass}: {entity:?} tiered differently");
+                    assert_eq!(grid_world.get::<FullSim>(entity).is_some(), tier(&grid_world) == SimTier::Full);
+                }
+            }
+            // a small slice of a 60 fps frame, first full insert included
+            let per_frame = grid_time / PASSES;
+            assert!(per_frame < FRAME_BUDGET, "{count} agents: tier pass takes {per_frame:?} a frame");
+        }
+    }
+}
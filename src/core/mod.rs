@@ -1,7 +1,40 @@
+pub mod animation;
+pub mod appearance;
+pub mod camera;
+pub mod clock;
+pub mod damage;
+pub mod food;
+pub mod hallucinations;
+pub mod hiding;
+pub mod lod;
+pub mod navigation;
 pub mod player;
+pub mod npc_ai;
+pub mod perception;
+pub mod reactions;
+pub mod sanity;
+pub mod school;
+pub mod seed;
+pub mod tools;
 pub mod warden;
 pub mod world;
 
+pub use animation::AnimationPlugin;
+pub use appearance::AppearancePlugin;
+pub use camera::CameraPlugin;
+pub use clock::ClockPlugin;
+pub use damage::DamagePlugin;
+pub use food::FoodPlugin;
+pub use hallucinations::HallucinationPlugin;
+pub use hiding::HidingPlugin;
+pub use lod::LodPlugin;
+pub use navigation::NavigationPlugin;
 pub use player::PlayerPlugin;
+pub use npc_ai::NpcAiPlugin;
+pub use perception::PerceptionPlugin;
+pub use reactions::ReactionsPlugin;
+pub use sanity::SanityPlugin;
+pub use seed::WorldSeedPlugin;
+pub use tools::ToolsPlugin;
 pub use warden::WardenPlugin;
 pub use world::WorldPlugin;
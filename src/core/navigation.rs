@@ -0,0 +1,553 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy_rapier3d::prelude::*;
+use bevy_rapier3d::rapier::math::Isometry;
+
+use crate::core::lod::{FullSim, SimLod, SpatialHash};
+use crate::core::school::{self, SchoolLayout, FLOOR_COUNT, SCHOOL_DEPTH, SCHOOL_LENGTH, SCHOOL_ORIGIN};
+
+pub const NAV_CELL_SIZE: f32 = 0.5;
+/// Half-size of the baked ground layer around the origin (covers the road grid)
+const NAV_GROUND_EXTENT: f32 = 220.0;
+/// Obstacles are inflated by this much so agents don't clip corners
+const AGENT_RADIUS: f32 = 0.35;
+/// Vertical band above a layer's floor that must be free of geometry
+const CLEARANCE_MIN: f32 = 0.3;
+const CLEARANCE_MAX: f32 = 1.8;
+/// How far below a layer a collider's top may sit and still count as its floor
+const FLOOR_SNAP: f32 = 0.35;
+/// A* gives up after expanding this many nodes
+const MAX_SEARCH_NODES: usize = 40_000;
+const MAX_PATHS_PER_FRAME: usize = 8;
+/// Agents closer than this to a path corner move on to the next one
+const CORNER_REACHED: f32 = 0.35;
+
+/// Colliders carrying this are skipped when baking (stair ramps become links instead)
+#[derive(Component)]
+pub struct NavIgnore;
+
+/// Area and height of one walkable layer to bake
+#[derive(Debug, Clone, Copy)]
+pub struct NavLayerSpec {
+    pub y: f32,
+    pub min: Vec2,
+    pub max: Vec2,
+    /// Upper floors are only walkable where a collider supports them
+    pub needs_floor: bool,
+}
+
+/// World-space AABB of a static obstacle
+#[derive(Debug, Clone, Copy)]
+pub struct NavObstacle {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+#[derive(Debug, Clone)]
+pub struct NavLayer {
+    pub y: f32,
+    pub min: Vec2,
+    pub width: usize,
+    pub depth: usize,
+    pub walkable: Vec<bool>,
+    /// Index of this layer's first cell in the global node numbering
+    offset: usize,
+}
+
+impl NavLayer {
+    fn cell_of(&self, p: Vec3) -> Option<(usize, usize)> {
+        let lx = ((p.x - self.min.x) / NAV_CELL_SIZE).floor();
+        let lz = ((p.z - self.min.y) / NAV_CELL_SIZE).floor();
+        if lx < 0.0 || lz < 0.0 || lx as usize >= self.width || lz as usize >= self.depth {
+            return None;
+        }
+        Some((lx as usize, lz as usize))
+    }
+
+    fn center_of(&self, ix: usize, iz: usize) -> Vec3 {
+        Vec3::new(
+            self.min.x + (ix as f32 + 0.5) * NAV_CELL_SIZE,
+            self.y,
+            self.min.y + (iz as f32 + 0.5) * NAV_CELL_SIZE,
+        )
+    }
+
+    fn is_open(&self, ix: i64, iz: i64) -> bool {
+        ix >= 0 && iz >= 0 && (ix as usize) < self.width && (iz as usize) < self.depth
+            && self.walkable[iz as usize * self.width + ix as usize]
+    }
+}
+
+/// Layered walkability grid baked from static colliders, with links between
+/// layers where stairs connect them.
+#[derive(Resource, Default)]
+pub struct NavGrid {
+    pub layers: Vec<NavLayer>,
+    links: HashMap<usize, Vec<usize>>,
+    pub baked: bool,
+}
+
+impl NavGrid {
+    pub fn bake(specs: &[NavLayerSpec], obstacles: &[NavObstacle], links: &[(Vec3, Vec3)]) -> Self {
+        let mut layers = Vec::with_capacity(specs.len());
+        let mut offset = 0;
+        for spec in specs {
+            let width = ((spec.max.x - spec.min.x) / NAV_CELL_SIZE).ceil() as usize;
+            let depth = ((spec.max.y - spec.min.y) / NAV_CELL_SIZE).ceil() as usize;
+            let mut supported = vec![!spec.needs_floor; width * depth];
+            let mut blocked = vec![false; width * depth];
+
+            let rasterize = |cells: &mut Vec<bool>, min: Vec3, max: Vec3, pad: f32| {
+                let x0 = ((min.x - pad - spec.min.x) / NAV_CELL_SIZE).floor().max(0.0) as usize;
+                let z0 = ((min.z - pad - spec.min.y) / NAV_CELL_SIZE).floor().max(0.0) as usize;
+                let x1 = (((max.x + pad - spec.min.x) / NAV_CELL_SIZE).ceil().max(0.0) as usize).min(width);
+                let z1 = (((max.z + pad - spec.min.y) / NAV_CELL_SIZE).ceil().max(0.0) as usize).min(depth);
+                for iz in z0..z1 {
+                    for ix in x0..x1 {
+                        cells[iz * width + ix] = true;
+                    }
+                }
+            };
+
+            for o in obstacles {
+                if spec.needs_floor && o.max.y <= spec.y + 0.05 && o.max.y >= spec.y - FLOOR_SNAP {
+                    rasterize(&mut supported, o.min, o.max, -AGENT_RADIUS);
+                }
+                if o.max.y > spec.y + CLEARANCE_MIN && o.min.y < spec.y + CLEARANCE_MAX {
+                    rasterize(&mut blocked, o.min, o.max, AGENT_RADIUS);
+                }
+            }
+
+            let walkable = supported.iter().zip(&blocked).map(|(s, b)| *s && !*b).collect();
+            layers.push(NavLayer { y: spec.y, min: spec.min, width, depth, walkable, offset });
+            offset += width * depth;
+        }
+
+        let mut grid = Self { layers, links: HashMap::new(), baked: true };
+        for &(a, b) in links {
+            if let (Some(na), Some(nb)) = (grid.nearest_walkable(a), grid.nearest_walkable(b)) {
+                grid.links.entry(na).or_default().push(nb);
+                grid.links.entry(nb).or_default().push(na);
+            }
+        }
+        grid
+    }
+
+    /// Highest layer at or just below `p` whose area contains it
+    fn layer_at(&self, p: Vec3) -> Option<usize> {
+        self.layers
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.y <= p.y + 0.5 && l.cell_of(p).is_some())
+            .max_by(|a, b| a.1.y.total_cmp(&b.1.y))
+            .map(|(i, _)| i)
+    }
+
+    fn split(&self, node: usize) -> (usize, usize, usize) {
+        let li = self.layers.iter().rposition(|l| l.offset <= node).unwrap_or(0);
+        let local = node - self.layers[li].offset;
+        (li, local % self.layers[li].width, local / self.layers[li].width)
+    }
+
+    fn node_position(&self, node: usize) -> Vec3 {
+        let (li, ix, iz) = self.split(node);
+        self.layers[li].center_of(ix, iz)
+    }
+
+    pub fn is_walkable(&self, p: Vec3) -> bool {
+        self.layer_at(p)
+            .and_then(|li| self.layers[li].cell_of(p).map(|(ix, iz)| self.layers[li].is_open(ix as i64, iz as i64)))
+            .unwrap_or(false)
+    }
+
+    /// Closest walkable cell to `p` on its layer, searching outward a few metres
+    pub fn nearest_walkable(&self, p: Vec3) -> Option<usize> {
+        let li = self.layer_at(p)?;
+        let layer = &self.layers[li];
+        let (cx, cz) = layer.cell_of(p)?;
+        for r in 0..12i64 {
+            let mut best: Option<(f32, usize)> = None;
+            for dz in -r..=r {
+                for dx in -r..=r {
+                    if dx.abs() != r && dz.abs() != r {
+                        continue;
+                    }
+                    let (ix, iz) = (cx as i64 + dx, cz as i64 + dz);
+                    if layer.is_open(ix, iz) {
+                        let d = layer.center_of(ix as usize, iz as usize).distance_squared(p);
+                        if best.is_none_or(|(bd, _)| d < bd) {
+                            best = Some((d, layer.offset + iz as usize * layer.width + ix as usize));
+                        }
+                    }
+                }
+            }
+            if let Some((_, node)) = best {
+                return Some(node);
+            }
+        }
+        None
+    }
+
+    fn neighbours(&self, node: usize, out: &mut Vec<(usize, f32)>) {
+        out.clear();
+        let (li, ix, iz) = self.split(node);
+        let layer = &self.layers[li];
+        let (ix, iz) = (ix as i64, iz as i64);
+        for dz in -1..=1i64 {
+            for dx in -1..=1i64 {
+                if (dx == 0 && dz == 0) || !layer.is_open(ix + dx, iz + dz) {
+                    continue;
+                }
+                // no corner cutting
+                if dx != 0 && dz != 0 && (!layer.is_open(ix + dx, iz) || !layer.is_open(ix, iz + dz)) {
+                    continue;
+                }
+                let cost = if dx != 0 && dz != 0 { std::f32::consts::SQRT_2 } else { 1.0 } * NAV_CELL_SIZE;
+                out.push((layer.offset + (iz + dz) as usize * layer.width + (ix + dx) as usize, cost));
+            }
+        }
+        if let Some(linked) = self.links.get(&node) {
+            let here = self.node_position(node);
+            out.extend(linked.iter().map(|&n| (n, here.distance(self.node_position(n)))));
+        }
+    }
+
+    /// A* from `from` to `to`. Returns smoothed corner points, ending at `to`'s cell.
+    pub fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        let start = self.nearest_walkable(from)?;
+        let goal = self.nearest_walkable(to)?;
+        let goal_pos = self.node_position(goal);
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<usize, usize> = HashMap::new();
+        let mut g_score: HashMap<usize, f32> = HashMap::new();
+        let mut scratch = Vec::with_capacity(10);
+        g_score.insert(start, 0.0);
+        open.push(OpenNode { f: self.node_position(start).distance(goal_pos), node: start });
+
+        let mut expanded = 0;
+        while let Some(OpenNode { node, .. }) = open.pop() {
+            if node == goal {
+                let mut nodes = vec![goal];
+                let mut cur = goal;
+                while let Some(&prev) = came_from.get(&cur) {
+                    nodes.push(prev);
+                    cur = prev;
+                }
+                nodes.reverse();
+                return Some(self.smooth(&nodes));
+            }
+            expanded += 1;
+            if expanded > MAX_SEARCH_NODES {
+                return None;
+            }
+
+            let g = g_score[&node];
+            self.neighbours(node, &mut scratch);
+            for &(next, cost) in &scratch {
+                let tentative = g + cost;
+                if g_score.get(&next).is_none_or(|&old| tentative < old) {
+                    g_score.insert(next, tentative);
+                    came_from.insert(next, node);
+                    let h = self.node_position(next).distance(goal_pos);
+                    open.push(OpenNode { f: tentative + h, node: next });
+                }
+            }
+        }
+        None
+    }
+
+    /// String-pull the raw cell path: drop corners with a clear straight line past them
+    fn smooth(&self, nodes: &[usize]) -> Vec<Vec3> {
+        let mut points = Vec::new();
+        let mut anchor = 0;
+        let mut i = 1;
+        while i < nodes.len() {
+            let (la, _, _) = self.split(nodes[anchor]);
+            let (li, _, _) = self.split(nodes[i]);
+            let visible = la == li && self.line_open(la, self.node_position(nodes[anchor]), self.node_position(nodes[i]));
+            if !visible {
+                // the previous node is a corner we have to visit
+                points.push(self.node_position(nodes[i - 1]));
+                anchor = i - 1;
+                if la != li {
+                    // crossing a link: keep both ends so agents walk the stairs
+                    points.push(self.node_position(nodes[i]));
+                    anchor = i;
+                }
+            }
+            i += 1;
+        }
+        if let Some(&last) = nodes.last() {
+            points.push(self.node_position(last));
+        }
+        points.dedup_by(|a, b| a.distance_squared(*b) < 1e-4);
+        points
+    }
+
+    fn line_open(&self, li: usize, a: Vec3, b: Vec3) -> bool {
+        let layer = &self.layers[li];
+        let steps = (a.distance(b) / (NAV_CELL_SIZE * 0.5)).ceil().max(1.0) as usize;
+        (0..=steps).all(|s| {
+            let p = a.lerp(b, s as f32 / steps as f32);
+            layer.cell_of(p).is_some_and(|(ix, iz)| layer.is_open(ix as i64, iz as i64))
+        })
+    }
+}
+
+#[derive(PartialEq)]
+struct OpenNode {
+    f: f32,
+    node: usize,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // min-heap on f
+        other.f.total_cmp(&self.f)
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Path-following movement. Set a destination; the navigation systems plan a
+/// path and steer the entity along it, keeping clear of other agents.
+#[derive(Component, Debug, Clone)]
+pub struct NavAgent {
+    pub speed: f32,
+    pub radius: f32,
+    pub velocity: Vec3,
+    destination: Option<Vec3>,
+    path: Vec<Vec3>,
+    needs_path: bool,
+}
+
+impl NavAgent {
+    pub fn new(speed: f32) -> Self {
+        Self { speed, radius: AGENT_RADIUS, velocity: Vec3::ZERO, destination: None, path: Vec::new(), needs_path: false }
+    }
+
+    pub fn set_destination(&mut self, goal: Vec3) {
+        if self.destination.is_none_or(|d| d.distance_squared(goal) > 0.25) {
+            self.destination = Some(goal);
+            self.needs_path = true;
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.destination = None;
+        self.path.clear();
+        self.needs_path = false;
+        self.velocity = Vec3::ZERO;
+    }
+
+    pub fn destination(&self) -> Option<Vec3> {
+        self.destination
+    }
+
+    /// True when there is nowhere to go: arrived, stopped, or the goal was unreachable
+    pub fn is_idle(&self) -> bool {
+        self.destination.is_none()
+    }
+
+    pub fn path(&self) -> &[Vec3] {
+        &self.path
+    }
+}
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>()
+           // after transform propagation so freshly spawned colliders have real positions
+           .add_systems(PostUpdate, bake_nav_grid_system.after(TransformSystem::TransformPropagate).run_if(nav_grid_unbaked))
+           .init_resource::<AgentGrid>()
+           .add_systems(Update, (plan_paths_system, track_agents_system, steer_agents_system).chain());
+    }
+}
+
+fn nav_grid_unbaked(grid: Res<NavGrid>) -> bool {
+    !grid.baked
+}
+
+fn bake_nav_grid_system(
+    mut grid: ResMut<NavGrid>,
+    school: Option<Res<SchoolLayout>>,
+    colliders: Query<(&Collider, &GlobalTransform, Option<&RigidBody>), (Without<NavIgnore>, Without<Sensor>)>,
+) {
+    let mut obstacles = Vec::new();
+    for (collider, gt, body) in &colliders {
+        if !matches!(body, None | Some(RigidBody::Fixed)) {
+            continue;
+        }
+        let tf = gt.compute_transform();
+        let aabb = collider.raw.compute_aabb(&Isometry::from_parts(tf.translation.into(), tf.rotation.into()));
+        obstacles.push(NavObstacle {
+            min: Vec3::new(aabb.mins.x, aabb.mins.y, aabb.mins.z),
+            max: Vec3::new(aabb.maxs.x, aabb.maxs.y, aabb.maxs.z),
+        });
+    }
+    // wait until the world's static geometry has been spawned
+    if obstacles.is_empty() {
+        return;
+    }
+
+    let mut specs = vec![NavLayerSpec {
+        y: 0.0,
+        min: Vec2::splat(-NAV_GROUND_EXTENT),
+        max: Vec2::splat(NAV_GROUND_EXTENT),
+        needs_floor: false,
+    }];
+    let mut links = Vec::new();
+    if let Some(school) = school {
+        let half = Vec2::new(SCHOOL_LENGTH, SCHOOL_DEPTH) * 0.5;
+        let center = Vec2::new(SCHOOL_ORIGIN.x, SCHOOL_ORIGIN.z);
+        for floor in 1..=FLOOR_COUNT {
+            specs.push(NavLayerSpec { y: school::floor_y(floor), min: center - half, max: center + half, needs_floor: true });
+        }
+        for stair in &school.stairs {
+            links.push((stair.bottom, stair.top));
+            // the ramp is ignored as a collider; block the part of it too low to walk under
+            let run = stair.top - stair.bottom;
+            let start = stair.bottom + run.normalize() * 1.5;
+            let half_w = Vec3::new(stair.width * 0.5, 0.0, stair.width * 0.5);
+            obstacles.push(NavObstacle {
+                min: start.min(stair.top) - half_w + Vec3::Y * 0.5,
+                max: start.max(stair.top) + half_w,
+            });
+        }
+    }
+
+    *grid = NavGrid::bake(&specs, &obstacles, &links);
+    let cells: usize = grid.layers.iter().map(|l| l.walkable.len()).sum();
+    info!("Nav grid baked: {} layers, {} cells, {} obstacles", grid.layers.len(), cells, obstacles.len());
+}
+
+fn plan_paths_system(grid: Res<NavGrid>, mut agents: Query<(&Transform, &mut NavAgent)>) {
+    if !grid.baked {
+        return;
+    }
+    let mut budget = MAX_PATHS_PER_FRAME;
+    for (tf, mut agent) in &mut agents {
+        if budget == 0 {
+            break;
+        }
+        if !agent.needs_path {
+            continue;
+        }
+        budget -= 1;
+        agent.needs_path = false;
+        let Some(goal) = agent.destination else { continue };
+        match grid.find_path(tf.translation, goal) {
+            Some(path) => agent.path = path,
+            None => {
+                debug!("No path from {:?} to {:?}", tf.translation, goal);
+                agent.stop();
+            }
+        }
+    }
+}
+
+/// Agents that steer every frame: NPCs in full simulation, and anything
+/// outside the LOD scheme altogether (the Warden)
+type Steered = Or<(With<FullSim>, Without<SimLod>)>;
+
+/// Where the steered agents are, kept across frames for neighbour lookups
+#[derive(Resource)]
+struct AgentGrid {
+    hash: SpatialHash,
+    radii: HashMap<Entity, f32>,
+}
+
+impl Default for AgentGrid {
+    fn default() -> Self {
+        Self { hash: SpatialHash::new(AGENT_RADIUS * 8.0), radii: HashMap::new() }
+    }
+}
+
+/// Only agents that moved, woke up to full simulation or appeared touch the grid
+fn track_agents_system(
+    mut grid: ResMut<AgentGrid>,
+    moved: Query<(Entity, &Transform, &NavAgent), (Steered, Or<(Changed<Transform>, Added<FullSim>, Added<NavAgent>)>)>,
+    mut demoted: RemovedComponents<FullSim>,
+    mut removed: RemovedComponents<NavAgent>,
+) {
+    for entity in demoted.read().chain(removed.read()) {
+        grid.hash.remove(entity);
+        grid.radii.remove(&entity);
+    }
+    for (entity, tf, agent) in &moved {
+        grid.hash.insert(entity, tf.translation);
+        grid.radii.insert(entity, agent.radius);
+    }
+}
+
+/// Follow path corners with simple seek + separation steering
+fn steer_agents_system(
+    time: Res<Time>,
+    grid: Res<AgentGrid>,
+    mut agents: Query<(Entity, &mut Transform, &mut NavAgent), Steered>,
+) {
+    let dt = time.delta_seconds();
+    if dt <= 0.0 {
+        return;
+    }
+    let max_radius = grid.radii.values().copied().fold(0.0f32, f32::max);
+
+    for (entity, mut tf, mut agent) in &mut agents {
+        if agent.destination.is_none() || agent.needs_path {
+            continue;
+        }
+        while agent.path.len() > 1 && agent.path[0].distance(tf.translation) < CORNER_REACHED {
+            agent.path.remove(0);
+        }
+        let Some(&next) = agent.path.first() else {
+            agent.stop();
+            continue;
+        };
+        let to_next = next - tf.translation;
+        if agent.path.len() == 1 && to_next.length() < CORNER_REACHED {
+            agent.stop();
+            continue;
+        }
+
+        let desired = to_next.normalize_or_zero() * agent.speed;
+        // local avoidance: push away from agents inside our personal space
+        let mut separation = Vec3::ZERO;
+        for (other, pos) in grid.hash.within(tf.translation, (agent.radius + max_radius) * 2.0) {
+            if other == entity {
+                continue;
+            }
+            let radius = grid.radii.get(&other).copied().unwrap_or(AGENT_RADIUS);
+            let mut away = tf.translation - pos;
+            away.y = 0.0;
+            let range = (agent.radius + radius) * 2.0;
+            let d = away.length();
+            if d > 0.001 && d < range {
+                separation += away / d * (range - d) / range;
+            }
+        }
+        let steering = desired + separation * agent.speed;
+        agent.velocity = agent.velocity.lerp(steering.clamp_length_max(agent.speed), (dt * 8.0).min(1.0));
+
+        // don't overshoot the final point
+        let step = agent.velocity * dt;
+        tf.translation += if agent.path.len() == 1 { step.clamp_length_max(to_next.length()) } else { step };
+
+        let flat = Vec3::new(agent.velocity.x, 0.0, agent.velocity.z);
+        if flat.length_squared() > 0.01 {
+            // models face +Z, so look the opposite way to walk front-first
+            tf.look_to(-flat, Vec3::Y);
+        }
+    }
+}
@@ -1,10 +1,24 @@
 use bevy::prelude::*;
 use rand::prelude::*;
-use crate::config::SIMULATION_RADIUS;
-use crate::core::player::Ethan;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use crate::core::clock::{HourChanged, WorldClock};
+use crate::core::lod::{CoarseSim, FullSim, SimLod, SimTier, SimTierChanged};
+use crate::core::school::{self, RoomKind, SchoolLayout};
+use crate::core::seed::NpcRng;
+use crate::core::navigation::NavAgent;
+use crate::core::world::{WorldSpawnInfo, PAVEMENT_OFFSET, ROAD_SPACING};
+use crate::data::schedules::{load_npc_schedules, Activity, NpcSchedules, NPC_SCHEDULES_PATH};
+
+/// Nav agent speed for NPCs going about their day
+pub const NPC_WALK_SPEED: f32 = 1.4;
+/// Coarse-tier NPCs are stepped this often rather than every frame
+const COARSE_STEP_SECS: f32 = 0.25;
+/// Commuters work this far along the road from a corner, clear of its lamp post
+const WORK_SPOT_ALONG: f32 = 3.0;
 
 /// An NPC category
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
 pub enum NpcCategory {
     Civilian,
     Trader,
@@ -19,82 +33,383 @@ pub struct Npc {
     pub timer: f32, // action timer for state changes
 }
 
+/// Stable id from the world's spawn plan, so saves can refer to an NPC
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NpcId(pub u32);
+
+/// On an NPC while the player is talking to it; its routine waits
+#[derive(Component)]
+pub struct InConversation;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NpcState {
     Idle,
     Walking,
     Interacting,
+    /// Running from a stink bomb (see `reactions.rs`)
+    Fleeing,
+    /// Gawping at a distraction
+    Gathering,
+    /// Off to tell the Warden what Ethan did
+    Reporting,
+}
+
+impl NpcState {
+    /// Reactions override the schedule until `reactions.rs` hands the NPC back
+    pub fn is_reacting(self) -> bool {
+        matches!(self, NpcState::Fleeing | NpcState::Gathering | NpcState::Reporting)
+    }
+}
+
+/// Where an NPC lives and works, and what its schedule has it doing now
+#[derive(Component, Debug, Clone)]
+pub struct NpcRoutine {
+    pub home: Vec3,
+    /// Chosen on first use: a classroom seat, a market stall or a workplace
+    pub work: Option<Vec3>,
+    pub activity: Option<Activity>,
+    /// Remaining points to walk through to reach the activity
+    legs: VecDeque<Vec3>,
+    /// Where the NPC is walking while out of full simulation, its nav agent parked
+    heading: Option<Vec3>,
+}
+
+impl NpcRoutine {
+    pub fn new(home: Vec3) -> Self {
+        Self { home, work: None, activity: None, legs: VecDeque::new(), heading: None }
+    }
+
+    /// Where the NPC is walking out of full simulation, if anywhere
+    pub fn heading(&self) -> Option<Vec3> {
+        self.heading
+    }
 }
 
 pub struct NpcAiPlugin;
 
 impl Plugin for NpcAiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, npc_ai_system.run_if(in_state(crate::states::GameState::OpenWorld)));
+        let schedules = load_npc_schedules(NPC_SCHEDULES_PATH).unwrap_or_else(|err| {
+            error!("Failed to load NPC schedules, everyone will wander: {:?}", err);
+            NpcSchedules::default()
+        });
+        app.insert_resource(schedules)
+           .add_systems(Update, (
+               npc_tier_handover_system,
+               npc_ai_system,
+               coarse_npc_system,
+               dormant_npc_system,
+           ).chain().run_if(in_state(crate::states::GameState::OpenWorld)));
     }
 }
 
-/// Simple wandering AI:
-/// - Idle: wait a bit then decide to walk or interact
-/// - Walking: move toward a random nearby target (uses simple position translation)
-/// - Interacting: wait (simulate trading/chatting), then go Idle
+/// Schedule-driven AI for NPCs near the player. When the clock moves an NPC
+/// into a new activity it walks there (leg by leg), then behaves according
+/// to the activity:
+/// - Class / Home / Commute: settle and stay put
+/// - Stall / Canteen: trade or chat on the spot, shuffling about a little
+/// - Wander: idle, stroll to random nearby spots, chat
 fn npc_ai_system(
     time: Res<Time>,
-    mut rng: Local<SmallRng>,
-    player_q: Query<&Transform, With<Ethan>>,
-    mut npc_q: Query<(Entity, &mut Npc, &mut Transform)>,
+    clock: Res<WorldClock>,
+    schedules: Res<NpcSchedules>,
+    school: Option<Res<SchoolLayout>>,
+    spawn_info: Res<WorldSpawnInfo>,
+    mut rng: ResMut<NpcRng>,
+    mut npc_q: Query<(&mut Npc, &mut NpcRoutine, &Transform, &mut NavAgent), (With<FullSim>, Without<InConversation>)>,
 ) {
-    // If no player present (Mission1 pre-open-world), skip
-    let Ok(player_tf) = player_q.get_single() else { return };
-    let player_pos = player_tf.translation;
-
-    for (entity, mut npc, mut transform) in &mut npc_q {
-        let d = transform.translation.distance(player_pos);
-        if d > SIMULATION_RADIUS {
-            // skip far-away NPCs for performance
+    let rng = &mut rng.0;
+
+    for (mut npc, mut routine, transform, mut agent) in &mut npc_q {
+        if npc.state.is_reacting() {
             continue;
         }
 
+        let activity = schedules.activity_at(&npc.category, clock.hour);
+        if follow_schedule(activity, &mut npc, &mut routine, transform.translation, school.as_deref(), &spawn_info, rng) {
+            agent.stop();
+        }
+
         npc.timer -= time.delta_seconds();
         match npc.state {
+            NpcState::Walking => {
+                if !agent.is_idle() {
+                    continue;
+                }
+                // arrived (or gave up on an unreachable leg): on to the next one
+                if let Some(next) = routine.legs.pop_front() {
+                    agent.set_destination(next);
+                } else {
+                    let (state, timer) = settle(activity, rng);
+                    npc.state = state;
+                    npc.timer = timer;
+                }
+            }
             NpcState::Idle => {
-                if npc.timer <= 0.0 {
-                    // choose next action
-                    let roll: f32 = rng.gen();
-                    if roll < 0.6 {
-                        // start walking to a nearby spot
+                if npc.timer > 0.0 {
+                    continue;
+                }
+                match activity {
+                    Activity::Wander => {
+                        if rng.gen::<f32>() < 0.6 {
+                            // stroll to a nearby spot; the nav agent walks there around obstacles
+                            let dx = (rng.gen::<f32>() - 0.5) * 8.0;
+                            let dz = (rng.gen::<f32>() - 0.5) * 8.0;
+                            routine.legs.push_back(transform.translation + Vec3::new(dx, 0.0, dz));
+                            npc.state = NpcState::Walking;
+                        } else {
+                            npc.state = NpcState::Interacting;
+                            npc.timer = 1.2 + rng.gen::<f32>() * 2.4;
+                        }
+                    }
+                    _ => npc.timer = 5.0 + rng.gen::<f32>() * 10.0,
+                }
+            }
+            NpcState::Interacting => {
+                if npc.timer > 0.0 {
+                    continue;
+                }
+                match activity {
+                    // step around the stall / table now and then, but stay there
+                    Activity::Stall | Activity::Canteen if rng.gen::<f32>() < 0.3 => {
+                        if let Some(work) = routine.work.filter(|_| activity == Activity::Stall) {
+                            routine.legs.push_back(work + Vec3::new(rng.gen_range(-1.0..1.0), 0.0, rng.gen_range(-1.0..1.0)));
+                        } else {
+                            let dx = rng.gen_range(-1.5..1.5);
+                            routine.legs.push_back(transform.translation + Vec3::new(dx, 0.0, 0.0));
+                        }
                         npc.state = NpcState::Walking;
-                        npc.timer = 2.0 + rng.gen::<f32>() * 4.0;
-                        // choose a small random displacement target by storing in translation's rotation (cheap packing)
-                        let dx = (rng.gen::<f32>() - 0.5) * 8.0;
-                        let dz = (rng.gen::<f32>() - 0.5) * 8.0;
-                        transform.translation += Vec3::new(dx, 0.0, dz);
-                    } else {
-                        // interact briefly (e.g., trade or chat)
-                        npc.state = NpcState::Interacting;
-                        npc.timer = 1.2 + rng.gen::<f32>() * 2.4;
+                    }
+                    Activity::Stall | Activity::Canteen => npc.timer = 2.0 + rng.gen::<f32>() * 4.0,
+                    _ => {
+                        npc.state = NpcState::Idle;
+                        npc.timer = 0.2 + rng.gen::<f32>() * 1.0;
                     }
                 }
             }
-            NpcState::Walking => {
-                // move forward slowly toward transform.translation's set position (we used transform as target in Idle)
-                // For simplicity we move toward the player+offset (we already added offset to translation),
-                // so walking is implicit during the idle->walk step above and timer counts down.
+            NpcState::Fleeing | NpcState::Gathering | NpcState::Reporting => {}
+        }
+    }
+}
+
+/// Switch to `activity` if the clock has moved on, planning the walk there.
+/// Returns true when a new walk was planned.
+fn follow_schedule(
+    activity: Activity,
+    npc: &mut Npc,
+    routine: &mut NpcRoutine,
+    from: Vec3,
+    school: Option<&SchoolLayout>,
+    spawn_info: &WorldSpawnInfo,
+    rng: &mut StdRng,
+) -> bool {
+    if routine.activity == Some(activity) {
+        return false;
+    }
+    routine.activity = Some(activity);
+    routine.legs = plan_legs(activity, routine, from, school, spawn_info, rng);
+    routine.heading = None;
+    npc.state = NpcState::Walking;
+    npc.timer = 0.0;
+    true
+}
+
+/// Hand an NPC's walk between its nav agent (full tier) and the straight-line
+/// heading used further out, so nobody freezes or restarts at a tier boundary
+fn npc_tier_handover_system(
+    mut events: EventReader<SimTierChanged>,
+    mut npc_q: Query<(&mut NpcRoutine, &mut NavAgent), With<Npc>>,
+) {
+    for ev in events.read() {
+        let Ok((mut routine, mut agent)) = npc_q.get_mut(ev.entity) else { continue };
+        if ev.from == SimTier::Full {
+            routine.heading = agent.destination();
+            agent.stop();
+        } else if ev.to == SimTier::Full {
+            if let Some(heading) = routine.heading.take() {
+                agent.set_destination(heading);
+            }
+        }
+    }
+}
+
+/// NPCs in the middle distance keep to the same schedule, but walk straight
+/// along their legs a few times a second instead of pathfinding every frame.
+/// Idling and chatting collapse into a single wait.
+fn coarse_npc_system(
+    time: Res<Time>,
+    clock: Res<WorldClock>,
+    schedules: Res<NpcSchedules>,
+    school: Option<Res<SchoolLayout>>,
+    spawn_info: Res<WorldSpawnInfo>,
+    mut rng: ResMut<NpcRng>,
+    mut npc_q: Query<(&mut Npc, &mut NpcRoutine, &mut Transform, &mut SimLod, &NavAgent), With<CoarseSim>>,
+) {
+    let rng = &mut rng.0;
+
+    for (mut npc, mut routine, mut tf, mut lod, agent) in &mut npc_q {
+        lod.pending += time.delta_seconds();
+        if lod.pending < COARSE_STEP_SECS {
+            continue;
+        }
+        let step = std::mem::take(&mut lod.pending);
+
+        // reactions started up close just keep walking to where they were going
+        if !npc.state.is_reacting() {
+            let activity = schedules.activity_at(&npc.category, clock.hour);
+            follow_schedule(activity, &mut npc, &mut routine, tf.translation, school.as_deref(), &spawn_info, rng);
+            if npc.state != NpcState::Walking {
+                npc.timer -= step;
                 if npc.timer <= 0.0 {
-                    npc.state = NpcState::Idle;
-                    npc.timer = 0.5 + rng.gen::<f32>() * 2.0;
-                } else {
-                    // slight bobbing: animate via small vertical sin offset (visual only)
-                    let offset = (time.elapsed_seconds() * 6.0).sin() * 0.01;
-                    transform.translation.y = offset;
+                    let (state, timer) = settle(activity, rng);
+                    npc.state = state;
+                    npc.timer = timer;
+                    if activity == Activity::Wander && rng.gen::<f32>() < 0.6 {
+                        routine.legs.push_back(tf.translation + Vec3::new(rng.gen_range(-4.0..4.0), 0.0, rng.gen_range(-4.0..4.0)));
+                        npc.state = NpcState::Walking;
+                    }
                 }
+                continue;
             }
-            NpcState::Interacting => {
-                if npc.timer <= 0.0 {
-                    npc.state = NpcState::Idle;
-                    npc.timer = 0.2 + rng.gen::<f32>() * 1.0;
+            if routine.heading.is_none() {
+                routine.heading = routine.legs.pop_front();
+            }
+            if routine.heading.is_none() {
+                let (state, timer) = settle(activity, rng);
+                npc.state = state;
+                npc.timer = timer;
+                continue;
+            }
+        }
+
+        let Some(heading) = routine.heading else { continue };
+        let to = heading - tf.translation;
+        let reach = agent.speed * step;
+        if to.length() <= reach {
+            tf.translation = heading;
+            routine.heading = None;
+        } else {
+            let dir = to.normalize();
+            tf.translation += dir * reach;
+            let flat = Vec3::new(dir.x, 0.0, dir.z);
+            if flat.length_squared() > 0.01 {
+                tf.look_to(-flat, Vec3::Y);
+            }
+        }
+    }
+}
+
+/// Far-away NPCs are only bookkept: once per in-game hour each one is put
+/// where its schedule says it would be by now. Nobody is close enough to see
+/// them skip the walk.
+fn dormant_npc_system(
+    mut hours: EventReader<HourChanged>,
+    clock: Res<WorldClock>,
+    schedules: Res<NpcSchedules>,
+    school: Option<Res<SchoolLayout>>,
+    spawn_info: Res<WorldSpawnInfo>,
+    mut rng: ResMut<NpcRng>,
+    mut npc_q: Query<(&mut Npc, &mut NpcRoutine, &mut Transform, &mut NavAgent), (Without<FullSim>, Without<CoarseSim>, Without<InConversation>)>,
+) {
+    if hours.read().last().is_none() {
+        return;
+    }
+    let rng = &mut rng.0;
+
+    for (mut npc, mut routine, mut tf, mut agent) in &mut npc_q {
+        if npc.state.is_reacting() {
+            // whatever they were running from or to is long over
+            agent.speed = NPC_WALK_SPEED;
+            routine.activity = None;
+        }
+        let activity = schedules.activity_at(&npc.category, clock.hour);
+        let planned = follow_schedule(activity, &mut npc, &mut routine, tf.translation, school.as_deref(), &spawn_info, rng);
+        if planned || npc.state == NpcState::Walking {
+            let heading = routine.heading.take();
+            if let Some(arrival) = heading.into_iter().chain(routine.legs.drain(..)).last() {
+                tf.translation = arrival;
+            }
+        }
+        let (state, timer) = settle(activity, rng);
+        npc.state = state;
+        npc.timer = timer;
+    }
+}
+
+/// What an NPC does once it reaches the place for `activity`
+fn settle(activity: Activity, rng: &mut StdRng) -> (NpcState, f32) {
+    match activity {
+        Activity::Stall | Activity::Canteen => (NpcState::Interacting, 2.0 + rng.gen::<f32>() * 4.0),
+        Activity::Wander => (NpcState::Idle, 0.5 + rng.gen::<f32>() * 2.0),
+        Activity::Home | Activity::Class | Activity::Commute => (NpcState::Idle, 5.0 + rng.gen::<f32>() * 10.0),
+    }
+}
+
+/// Points to walk through to get from `from` to where `activity` happens
+fn plan_legs(
+    activity: Activity,
+    routine: &mut NpcRoutine,
+    from: Vec3,
+    school: Option<&SchoolLayout>,
+    spawn_info: &WorldSpawnInfo,
+    rng: &mut StdRng,
+) -> VecDeque<Vec3> {
+    match activity {
+        Activity::Home => along_roads(from, routine.home),
+        Activity::Class => {
+            let home = routine.home;
+            let seat = *routine.work.get_or_insert_with(|| {
+                school
+                    .and_then(|s| s.rooms_of_kind(RoomKind::Classroom).choose(rng).map(|r| r.center()))
+                    .unwrap_or(home)
+            });
+            VecDeque::from([seat])
+        }
+        Activity::Canteen => {
+            let canteen = school.and_then(|s| s.rooms_of_kind(RoomKind::Canteen).next().cloned());
+            match canteen {
+                Some(room) => {
+                    let x = rng.gen_range((room.min.x + 2.0)..(room.max.x - 2.0));
+                    let z = rng.gen_range((room.min.y + 2.0)..(room.max.y - 2.0));
+                    VecDeque::from([Vec3::new(x, room.door.y, z)])
                 }
+                None => VecDeque::new(),
             }
         }
+        Activity::Stall => {
+            // the stall nearest home, standing just behind the counter
+            let home = routine.home;
+            let stall = *routine.work.get_or_insert_with(|| {
+                spawn_info
+                    .market_spots
+                    .iter()
+                    .min_by(|a, b| a.distance_squared(home).total_cmp(&b.distance_squared(home)))
+                    .map_or(home, |s| *s + Vec3::Z * 1.5)
+            });
+            VecDeque::from([stall])
+        }
+        Activity::Commute => {
+            let work = *routine.work.get_or_insert_with(|| loop {
+                let x = rng.gen_range(-4..=4) as f32 * ROAD_SPACING + PAVEMENT_OFFSET;
+                let z = rng.gen_range(-4..=4) as f32 * ROAD_SPACING + PAVEMENT_OFFSET + WORK_SPOT_ALONG;
+                let spot = Vec3::new(x, 0.0, z);
+                if !school::in_footprint(spot, 1.0) {
+                    break spot;
+                }
+            });
+            along_roads(from, work)
+        }
+        Activity::Wander => VecDeque::new(),
     }
 }
+
+/// L-shaped walk on the pavement: onto the nearest north-south road, along it
+/// to the destination's latitude, then across to the destination
+fn along_roads(from: Vec3, to: Vec3) -> VecDeque<Vec3> {
+    let road_x = (from.x / ROAD_SPACING).round() * ROAD_SPACING + PAVEMENT_OFFSET;
+    VecDeque::from([
+        Vec3::new(road_x, 0.0, from.z),
+        Vec3::new(road_x, 0.0, to.z),
+        to,
+    ])
+}
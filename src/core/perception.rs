@@ -0,0 +1,293 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::core::hiding::Hidden;
+use crate::core::player::{Ethan, Locomotion};
+use crate::core::school::SchoolLayout;
+
+/// Eye height used for sight lines
+const EYE_HEIGHT: f32 = 1.6;
+/// Point on the player the watcher tries to see
+const TARGET_HEIGHT: f32 = 1.0;
+/// Light level a lit torch guarantees around the player
+const CARRIED_LIGHT_LEVEL: f32 = 0.9;
+/// Walls halve how far a sound carries
+const OCCLUDED_NOISE_FACTOR: f32 = 0.5;
+/// How far the player's movement carries per mode, in metres
+const CROUCH_NOISE: f32 = 1.5;
+const WALK_NOISE: f32 = 5.0;
+const SPRINT_NOISE: f32 = 12.0;
+/// Panting while exhausted, audible even standing still
+const EXHAUSTED_NOISE: f32 = 4.0;
+/// Suspicion per second from hearing the player's movement at point-blank range
+const PLAYER_NOISE_GAIN: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseSource {
+    Footsteps,
+    Door,
+    Item,
+    Breathing,
+}
+
+/// A sound somebody might hear. `loudness` is the radius in metres it carries
+/// in the open.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct NoiseEvent {
+    pub position: Vec3,
+    pub loudness: f32,
+    pub source: NoiseSource,
+}
+
+/// Sight and hearing for an AI watcher. Suspicion builds while the player is
+/// seen or heard and decays otherwise; at 1.0 the watcher is certain.
+#[derive(Component, Debug, Clone)]
+pub struct Perception {
+    pub vision_range: f32,
+    pub fov_deg: f32,
+    /// Multiplier on a noise's loudness radius
+    pub hearing: f32,
+    /// Suspicion gained per second when the player is fully lit at point-blank range
+    pub sight_gain: f32,
+    pub decay: f32,
+    pub suspicion: f32,
+    pub sees_player: bool,
+    pub last_known: Option<Vec3>,
+    /// Most recent noise heard this frame, if any
+    pub heard: Option<NoiseEvent>,
+}
+
+impl Default for Perception {
+    fn default() -> Self {
+        Self {
+            vision_range: 12.0,
+            fov_deg: 70.0,
+            hearing: 1.0,
+            sight_gain: 1.6,
+            decay: 0.12,
+            suspicion: 0.0,
+            sees_player: false,
+            last_known: None,
+            heard: None,
+        }
+    }
+}
+
+/// What the player's own movement sounds like this frame. Unlike `NoiseEvent`
+/// it is continuous: listeners in range keep hearing it while it lasts.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct PlayerNoise {
+    /// Radius in metres it carries in the open; 0 is silent
+    pub level: f32,
+    pub breathing: bool,
+}
+
+impl PlayerNoise {
+    pub fn from_motion(motion: &Locomotion) -> Self {
+        let steps = match (motion.speed > 0.0, motion.crouching, motion.sprinting) {
+            (false, _, _) => 0.0,
+            (true, true, _) => CROUCH_NOISE,
+            (true, false, true) => SPRINT_NOISE,
+            (true, false, false) => WALK_NOISE,
+        };
+        let panting = if motion.exhausted { EXHAUSTED_NOISE } else { 0.0 };
+        Self { level: steps.max(panting), breathing: panting > steps }
+    }
+
+    pub fn event(&self, position: Vec3) -> Option<NoiseEvent> {
+        let source = if self.breathing { NoiseSource::Breathing } else { NoiseSource::Footsteps };
+        (self.level > 0.0).then_some(NoiseEvent { position, loudness: self.level, source })
+    }
+}
+
+/// A light the player carries (torch). While lit the player is easy to see in the dark.
+/// `tools::torch_system` keeps it in step with the torch's switch.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct CarriedLight {
+    pub lit: bool,
+}
+
+pub struct PerceptionPlugin;
+
+impl Plugin for PerceptionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NoiseEvent>()
+           .add_systems(Update, (
+               player_noise_system,
+               door_noise_system,
+               perception_system,
+           ).chain().run_if(in_state(crate::states::GameState::Mission1)));
+    }
+}
+
+/// How lit a point is, 0 (pitch dark) to 1 (daylight), from ambient, point,
+/// spot and directional lights. Walls are ignored; this is a gameplay estimate.
+pub fn light_level_at(
+    pos: Vec3,
+    ambient: Option<&AmbientLight>,
+    points: &Query<(&GlobalTransform, &PointLight)>,
+    spots: &Query<(&GlobalTransform, &SpotLight)>,
+    suns: &Query<&DirectionalLight>,
+) -> f32 {
+    let mut level = ambient.map(|a| a.brightness * 0.5).unwrap_or(0.0);
+    for (gt, light) in points {
+        let d = gt.translation().distance(pos);
+        if d < light.range {
+            level += (1.0 - d / light.range) * (light.intensity / 800.0).min(1.0);
+        }
+    }
+    for (gt, light) in spots {
+        let to = pos - gt.translation();
+        let d = to.length();
+        if d < light.range && d > 0.0 {
+            let angle = gt.forward().angle_between(to / d);
+            if angle < light.outer_angle {
+                level += (1.0 - d / light.range) * (light.intensity / 800.0).min(1.0);
+            }
+        }
+    }
+    for sun in suns {
+        level += (sun.illuminance / 10_000.0).min(1.0);
+    }
+    level.clamp(0.0, 1.0)
+}
+
+/// Whether static geometry blocks the straight line from `from` to `to`
+pub fn line_blocked(ctx: &RapierContext, from: Vec3, to: Vec3) -> bool {
+    let delta = to - from;
+    let dist = delta.length();
+    if dist < 0.01 {
+        return false;
+    }
+    ctx.cast_ray(from, delta / dist, dist - 0.2, true, QueryFilter::only_fixed()).is_some()
+}
+
+/// Footsteps and panting from how Ethan is moving; hidden he makes none
+/// (his breathing in the spot is `hiding::breath_system`'s business)
+fn player_noise_system(mut player_q: Query<(&Locomotion, &mut PlayerNoise, Option<&Hidden>), With<Ethan>>) {
+    for (motion, mut noise, hidden) in &mut player_q {
+        *noise = if hidden.is_some() { PlayerNoise::default() } else { PlayerNoise::from_motion(motion) };
+    }
+}
+
+/// How far `noise` reaches a listener at `ear`, after walls
+pub fn noise_reach(ctx: &RapierContext, noise: &NoiseEvent, ear: Vec3, hearing: f32) -> f32 {
+    let reach = noise.loudness * hearing;
+    if line_blocked(ctx, noise.position + Vec3::Y * 0.5, ear) {
+        reach * OCCLUDED_NOISE_FACTOR
+    } else {
+        reach
+    }
+}
+
+/// School doors creak when the player passes through the doorway
+fn door_noise_system(
+    school: Option<Res<SchoolLayout>>,
+    mut inside: Local<Option<usize>>,
+    player_q: Query<&Transform, With<Ethan>>,
+    mut noise: EventWriter<NoiseEvent>,
+) {
+    let Some(school) = school else { return };
+    let Ok(tf) = player_q.get_single() else { return };
+    let near = school.rooms.iter().position(|r| r.door.distance(tf.translation) < 0.8);
+    if let Some(room) = near.filter(|_| near != *inside) {
+        let door = school.rooms[room].door;
+        noise.send(NoiseEvent { position: door, loudness: 9.0, source: NoiseSource::Door });
+    }
+    *inside = near;
+}
+
+fn perception_system(
+    time: Res<Time>,
+    rapier: Res<RapierContext>,
+    ambient: Option<Res<AmbientLight>>,
+    points: Query<(&GlobalTransform, &PointLight)>,
+    spots: Query<(&GlobalTransform, &SpotLight)>,
+    suns: Query<&DirectionalLight>,
+    mut noises: EventReader<NoiseEvent>,
+    player_q: Query<(&Transform, Option<&CarriedLight>, Option<&Hidden>, Option<&PlayerNoise>), With<Ethan>>,
+    mut watchers: Query<(&Transform, &mut Perception)>,
+) {
+    let dt = time.delta_seconds();
+    let heard = noises.read().copied().collect::<Vec<_>>();
+    let Ok((player_tf, carried, hidden, player_noise)) = player_q.get_single() else { return };
+    let movement = player_noise.and_then(|n| n.event(player_tf.translation));
+    // someone who watched the player climb in still knows where they are
+    let concealed = hidden.is_some_and(|h| !h.observed);
+    let target = player_tf.translation + Vec3::Y * TARGET_HEIGHT;
+
+    let mut light = light_level_at(player_tf.translation, ambient.as_deref(), &points, &spots, &suns);
+    if carried.is_some_and(|c| c.lit) {
+        light = light.max(CARRIED_LIGHT_LEVEL);
+    }
+
+    for (tf, mut p) in &mut watchers {
+        let eye = tf.translation + Vec3::Y * EYE_HEIGHT;
+        let to_target = target - eye;
+        let dist = to_target.length();
+
+        // sight: range, cone, then occlusion; models face +Z, the transform's back
+        let in_cone = dist <= p.vision_range
+            && tf.back().angle_between(to_target).to_degrees() <= p.fov_deg * 0.5;
+        p.sees_player = !concealed && in_cone && !line_blocked(&rapier, eye, target);
+
+        if p.sees_player {
+            // dark + far is slow to notice, lit + close is almost instant
+            let visibility = (0.15 + 0.85 * light) * (1.0 - 0.6 * dist / p.vision_range);
+            p.suspicion += p.sight_gain * visibility * dt;
+            p.last_known = Some(player_tf.translation);
+        } else {
+            p.suspicion -= p.decay * dt;
+        }
+
+        // hearing: loudest noise that reaches us this frame
+        p.heard = None;
+        for n in &heard {
+            let reach = noise_reach(&rapier, n, eye, p.hearing);
+            let d = n.position.distance(tf.translation);
+            if d < reach {
+                let strength = 1.0 - d / reach;
+                if p.heard.is_none_or(|h| h.loudness < n.loudness) {
+                    p.heard = Some(*n);
+                }
+                p.suspicion += 0.25 * strength;
+                p.last_known = Some(n.position);
+            }
+        }
+        // the player's own movement is heard for as long as it goes on
+        if let Some(n) = movement {
+            let reach = noise_reach(&rapier, &n, eye, p.hearing);
+            let d = n.position.distance(tf.translation);
+            if d < reach {
+                if p.heard.is_none_or(|h| h.loudness < n.loudness) {
+                    p.heard = Some(n);
+                }
+                p.suspicion += PLAYER_NOISE_GAIN * (1.0 - d / reach) * dt;
+                p.last_known = Some(n.position);
+            }
+        }
+
+        p.suspicion = p.suspicion.clamp(0.0, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn movement_modes_rank_by_loudness() {
+        let level = |speed: f32, crouching: bool, sprinting: bool, exhausted: bool| {
+            PlayerNoise::from_motion(&Locomotion { speed, crouching, sprinting, exhausted, ..default() }).level
+        };
+        let still = level(0.0, false, false, false);
+        let crouch = level(1.8, true, false, false);
+        let walk = level(4.0, false, false, false);
+        let sprint = level(6.5, false, true, false);
+        assert_eq!(still, 0.0);
+        assert!(still < crouch && crouch < walk && walk < sprint);
+        // panting gives away an exhausted player who has stopped to rest
+        let resting = PlayerNoise::from_motion(&Locomotion { exhausted: true, ..default() });
+        assert!(resting.level > crouch && resting.breathing);
+    }
+}
@@ -1,7 +1,17 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 use crate::systems::controls::Controls;
-use crate::systems::inventory::Inventory;
+use bevy::asset::LoadState;
+use bevy::audio::Volume;
+use bevy::ecs::query::QuerySingleError;
+use crate::core::hiding::{Breath, Hidden};
+use crate::core::perception::{CarriedLight, PlayerNoise};
+use crate::core::animation::Animated;
+use crate::core::damage::{DamageEvent, DamageSource};
+use crate::core::food::StatusEffects;
+use crate::systems::dialogue::ActiveDialogue;
+use crate::systems::trading::ActiveTrade;
+use crate::systems::inventory_ui::InventoryScreen;
 
 #[derive(Component)]
 pub struct Ethan;
@@ -11,19 +21,94 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(crate::states::GameState::Mission1), spawn_player)
-           .add_systems(Update, player_input_system.run_if(in_state(crate::states::GameState::Mission1)));
+           .add_systems(Update, (player_input_system, heavy_breathing_system).chain().run_if(in_state(crate::states::GameState::Mission1)));
     }
 }
 
 #[derive(Component)]
 pub struct PlayerController;
 
-#[derive(Resource, Default, Clone)]
+const WALK_SPEED: f32 = 4.0;
+const SPRINT_SPEED: f32 = 6.5;
+const CROUCH_SPEED: f32 = 1.8;
+/// Speed multiplier while out of breath
+const EXHAUSTED_SPEED_FACTOR: f32 = 0.6;
+/// Stamina per second: spent sprinting, recovered walking / standing still
+const STAMINA_DRAIN: f32 = 20.0;
+const STAMINA_REGEN_WALKING: f32 = 8.0;
+const STAMINA_REGEN_RESTING: f32 = 15.0;
+/// Fraction of max stamina needed before an exhausted Ethan can sprint again
+const EXHAUSTION_RECOVERY: f32 = 0.35;
+const HEAVY_BREATHING_SOUND: &str = "sounds/heavy_breathing.wav";
+/// Volume of the pant while exhausted, and while catching his breath after holding it
+const EXHAUSTED_BREATHING_VOLUME: f32 = 1.0;
+const WINDED_BREATHING_VOLUME: f32 = 0.7;
+/// Volume change per second, so the pant fades in and out
+const BREATHING_FADE: f32 = 1.5;
+const GRAVITY: f32 = 9.81;
+const MAX_FALL_SPEED: f32 = 30.0;
+/// Landing slower than this (about a 3 m drop) doesn't hurt
+const SAFE_LANDING_SPEED: f32 = 8.0;
+/// Health lost per m/s of landing speed over the safe limit
+const FALL_DAMAGE_PER_SPEED: f32 = 6.0;
+const CAPSULE_RADIUS: f32 = 0.35;
+pub const STAND_HEIGHT: f32 = 1.8;
+pub const CROUCH_HEIGHT: f32 = 1.1;
+
+/// How Ethan is moving this frame, driven by `player_input_system`
+#[derive(Component, Debug, Default, Clone)]
+pub struct Locomotion {
+    /// Metres per second, negative while falling
+    pub vertical_speed: f32,
+    /// Horizontal speed actually requested this frame
+    pub speed: f32,
+    pub crouching: bool,
+    pub sprinting: bool,
+    pub grounded: bool,
+    /// Ran out of stamina; slowed and panting until it recovers
+    pub exhausted: bool,
+}
+
+/// Looping pant on Ethan, silent until he is out of breath
+#[derive(Component)]
+struct HeavyBreathing;
+
+/// Capsule of the given height standing on the entity origin (Ethan's feet)
+pub fn body_collider(height: f32) -> Collider {
+    let half_segment = (height * 0.5 - CAPSULE_RADIUS).max(0.0);
+    Collider::compound(vec![(
+        Vec3::Y * height * 0.5,
+        Quat::IDENTITY,
+        Collider::capsule_y(half_segment, CAPSULE_RADIUS),
+    )])
+}
+
+/// Walks up the school stairs (about 25 degrees) and kerbs, slides off anything steeper
+fn character_controller() -> KinematicCharacterController {
+    KinematicCharacterController {
+        offset: CharacterLength::Absolute(0.02),
+        up: Vec3::Y,
+        max_slope_climb_angle: 45f32.to_radians(),
+        min_slope_slide_angle: 35f32.to_radians(),
+        autostep: Some(CharacterAutostep {
+            max_height: CharacterLength::Absolute(0.35),
+            min_width: CharacterLength::Absolute(0.2),
+            include_dynamic_bodies: false,
+        }),
+        snap_to_ground: Some(CharacterLength::Absolute(0.3)),
+        slide: true,
+        ..default()
+    }
+}
+
+#[derive(Resource, Clone)]
 pub struct PlayerStats {
     pub health: f32,
     pub max_health: f32,
     pub hunger: f32,
     pub max_hunger: f32,
+    pub stamina: f32,
+    pub max_stamina: f32,
 }
 
 impl Default for PlayerStats {
@@ -33,18 +118,23 @@ impl Default for PlayerStats {
             max_health: 100.0,
             hunger: 100.0,
             max_hunger: 100.0,
+            stamina: 100.0,
+            max_stamina: 100.0,
         }
     }
 }
 
 fn spawn_player(
     mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    asset_server: Res<AssetServer>,
     handles: Res<crate::data::assets_loader::Handles>,
 ) {
-    let start = Vec3::new(0.0, 0.0, 0.0);
+    // a little above the ground slab; gravity settles him on the first frames
+    let start = Vec3::new(0.0, 0.2, 0.0);
 
     // spawn the player scene if available, otherwise spawn a capsule
-    if handles.man.is_loaded() {
+    if !matches!(asset_server.load_state(&handles.man), LoadState::Failed(_)) {
         commands.spawn((
             SceneBundle {
                 scene: handles.man.clone(),
@@ -54,57 +144,220 @@ fn spawn_player(
             Ethan,
             PlayerController,
             RigidBody::KinematicPositionBased,
-            Collider::capsule_y(0.9, 0.35),
+            body_collider(STAND_HEIGHT),
+            character_controller(),
+            Locomotion::default(),
+            PlayerNoise::default(),
             LockedAxes::ROTATION_LOCKED,
+            CarriedLight::default(),
+            Breath::default(),
+            Animated::new("Man.glb").actor("ethan"),
         ));
     } else {
         commands.spawn((
-            PbrBundle {
-                mesh: Mesh::from(shape::Capsule { radius: 0.35, depth: 1.8 }).into(),
-                material: Default::default(),
-                transform: Transform::from_translation(start),
-                ..default()
-            },
+            SpatialBundle::from_transform(Transform::from_translation(start)),
             Ethan,
             PlayerController,
             RigidBody::KinematicPositionBased,
-            Collider::capsule_y(0.9, 0.35),
+            body_collider(STAND_HEIGHT),
+            character_controller(),
+            Locomotion::default(),
+            PlayerNoise::default(),
             LockedAxes::ROTATION_LOCKED,
-        ));
+            CarriedLight::default(),
+            Breath::default(),
+        ))
+        .with_children(|body| {
+            // the capsule mesh is centred, the entity origin is at his feet
+            body.spawn(PbrBundle {
+                mesh: meshes.add(Capsule3d::new(CAPSULE_RADIUS, STAND_HEIGHT - CAPSULE_RADIUS * 2.0)),
+                material: Default::default(),
+                transform: Transform::from_translation(Vec3::Y * STAND_HEIGHT * 0.5),
+                ..default()
+            });
+        });
     }
 
     commands.insert_resource(PlayerStats::default());
     info!("Player spawned.");
 }
 
+/// Moves Ethan through the Rapier character controller so walls, lockers and
+/// cars stop him, stairs and kerbs carry him up and gravity brings him down.
+/// Movement keys are relative to the 3D camera when there is one.
 fn player_input_system(
-    keyboard: Res<Input<KeyCode>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
     controls: Res<Controls>,
-    mut q: Query<&mut Transform, With<Ethan>>,
+    rapier: Res<RapierContext>,
+    mut q: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut KinematicCharacterController,
+            &mut Locomotion,
+            &mut Collider,
+            Option<&KinematicCharacterControllerOutput>,
+            Option<&Hidden>,
+        ),
+        With<Ethan>,
+    >,
+    cameras: Query<&GlobalTransform, (With<Camera3d>, Without<Ethan>)>,
     mut stats: ResMut<PlayerStats>,
+    mut damage: EventWriter<DamageEvent>,
+    effects: Option<Res<StatusEffects>>,
+    dialogue: Option<Res<ActiveDialogue>>,
+    trade: Option<Res<ActiveTrade>>,
+    screen: Option<Res<InventoryScreen>>,
 ) {
-    let Ok(mut tf) = q.get_single_mut() else { return };
-
-    let mut dir = Vec3::ZERO;
-    if keyboard.pressed(controls.move_forward) { dir.z -= 1.0; }
-    if keyboard.pressed(controls.move_back) { dir.z += 1.0; }
-    if keyboard.pressed(controls.move_left) { dir.x -= 1.0; }
-    if keyboard.pressed(controls.move_right) { dir.x += 1.0; }
-
-    if dir != Vec3::ZERO {
-        let speed = 6.0;
-        tf.translation += dir.normalize() * speed * time.delta_seconds();
-        tf.look_to(-dir, Vec3::Y);
+    let Ok((entity, mut tf, mut controller, mut motion, mut collider, output, hidden)) = q.get_single_mut() else { return };
+    let dt = time.delta_seconds();
+
+    // strafe keys peek instead of walking while hidden
+    if hidden.is_some() {
+        controller.translation = None;
+        motion.vertical_speed = 0.0;
+        motion.speed = 0.0;
+        motion.sprinting = false;
+    } else {
+        // a conversation, a stall or the open bag holds Ethan where he stands;
+        // the move keys pick replies and wares instead
+        let busy = dialogue.is_some() || trade.is_some() || screen.is_some_and(|s| s.open);
+        let axis = |pos: KeyCode, neg: KeyCode| {
+            if busy {
+                return 0.0;
+            }
+            keyboard.pressed(pos) as i32 as f32 - keyboard.pressed(neg) as i32 as f32
+        };
+        let forward_input = axis(controls.move_forward, controls.move_back);
+        let right_input = axis(controls.move_right, controls.move_left);
+
+        let (forward, right) = cameras
+            .iter()
+            .next()
+            .map(|cam| (flat(*cam.forward()), flat(*cam.right())))
+            .filter(|(f, r)| *f != Vec3::ZERO && *r != Vec3::ZERO)
+            .unwrap_or((Vec3::NEG_Z, Vec3::X));
+        let dir = (forward * forward_input + right * right_input).normalize_or_zero();
+
+        // stand back up only if there is headroom
+        let wants_crouch = keyboard.pressed(controls.crouch);
+        if wants_crouch && !motion.crouching {
+            motion.crouching = true;
+            *collider = body_collider(CROUCH_HEIGHT);
+        } else if !wants_crouch && motion.crouching {
+            let head = tf.translation + Vec3::Y * (STAND_HEIGHT - CAPSULE_RADIUS);
+            let filter = QueryFilter::default().exclude_collider(entity).exclude_sensors();
+            if rapier.intersection_with_shape(head, Quat::IDENTITY, &Collider::ball(CAPSULE_RADIUS * 0.9), filter).is_none() {
+                motion.crouching = false;
+                *collider = body_collider(STAND_HEIGHT);
+            }
+        }
+
+        motion.sprinting = keyboard.pressed(controls.sprint)
+            && !motion.crouching
+            && !motion.exhausted
+            && !effects.as_ref().is_some_and(|e| e.nauseous())
+            && stats.stamina > 0.0
+            && dir != Vec3::ZERO;
+        let mut speed = if motion.crouching {
+            CROUCH_SPEED
+        } else if motion.sprinting {
+            SPRINT_SPEED
+        } else {
+            WALK_SPEED
+        };
+        if motion.exhausted {
+            speed *= EXHAUSTED_SPEED_FACTOR;
+        }
+
+        let was_grounded = motion.grounded;
+        motion.grounded = output.is_some_and(|o| o.grounded);
+        let impact = -motion.vertical_speed;
+        if motion.grounded && !was_grounded && impact > SAFE_LANDING_SPEED {
+            damage.send(DamageEvent { source: DamageSource::Fall, amount: (impact - SAFE_LANDING_SPEED) * FALL_DAMAGE_PER_SPEED });
+        }
+        motion.vertical_speed = if motion.grounded {
+            // keep a little downward push so slopes and stair edges stay snapped
+            -0.5
+        } else {
+            (motion.vertical_speed - GRAVITY * dt).max(-MAX_FALL_SPEED)
+        };
+
+        motion.speed = if dir != Vec3::ZERO { speed } else { 0.0 };
+        controller.translation = Some(dir * speed * dt + Vec3::Y * motion.vertical_speed * dt);
+        if dir != Vec3::ZERO {
+            tf.look_to(-dir, Vec3::Y);
+        }
+    }
+
+    if motion.sprinting {
+        stats.stamina = (stats.stamina - STAMINA_DRAIN * dt).max(0.0);
+        if stats.stamina <= 0.0 {
+            motion.exhausted = true;
+            motion.sprinting = false;
+            info!("Ethan is out of breath.");
+        }
+    } else {
+        let regen = if motion.speed > 0.0 { STAMINA_REGEN_WALKING } else { STAMINA_REGEN_RESTING };
+        stats.stamina = (stats.stamina + regen * dt).min(stats.max_stamina);
+        if motion.exhausted && stats.stamina >= stats.max_stamina * EXHAUSTION_RECOVERY {
+            motion.exhausted = false;
+        }
+    }
+
+    if motion.speed > 0.0 {
         // hunger depletes while moving
-        stats.hunger = (stats.hunger - 0.6 * time.delta_seconds()).clamp(0.0, stats.max_hunger);
+        stats.hunger = (stats.hunger - 0.6 * dt).clamp(0.0, stats.max_hunger);
     } else {
         // some regen
-        stats.hunger = (stats.hunger + 0.05 * time.delta_seconds()).clamp(0.0, stats.max_hunger);
+        stats.hunger = (stats.hunger + 0.05 * dt).clamp(0.0, stats.max_hunger);
     }
 
     // if hunger zero, gradually reduce health
     if stats.hunger <= 0.0 {
-        stats.health = (stats.health - 2.0 * time.delta_seconds()).max(0.0);
+        damage.send(DamageEvent { source: DamageSource::Starvation, amount: 2.0 * dt });
     }
 }
+
+/// Fade the pant in while Ethan is exhausted or short of air after holding
+/// his breath, and out while he holds it or has his wind back
+fn heavy_breathing_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    player_q: Query<(Entity, &Locomotion, &Breath), With<Ethan>>,
+    // the sink only turns up once the sound has loaded and started
+    breathing: Query<Option<&AudioSink>, With<HeavyBreathing>>,
+) {
+    let Ok((player, motion, breath)) = player_q.get_single() else { return };
+    let target = if breath.holding {
+        0.0
+    } else if motion.exhausted {
+        EXHAUSTED_BREATHING_VOLUME
+    } else {
+        (1.0 - breath.value) * WINDED_BREATHING_VOLUME
+    };
+    match breathing.get_single() {
+        Ok(Some(sink)) => {
+            let step = BREATHING_FADE * time.delta_seconds();
+            sink.set_volume(sink.volume() + (target - sink.volume()).clamp(-step, step));
+        }
+        Err(QuerySingleError::NoEntities(_)) => {
+            commands.entity(player).with_children(|p| {
+                p.spawn((
+                    AudioBundle {
+                        source: asset_server.load(HEAVY_BREATHING_SOUND),
+                        settings: PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+                    },
+                    HeavyBreathing,
+                ));
+            });
+        }
+        _ => {}
+    }
+}
+
+fn flat(v: Vec3) -> Vec3 {
+    Vec3::new(v.x, 0.0, v.z).normalize_or_zero()
+}
@@ -0,0 +1,421 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::clock::HourChanged;
+use crate::core::lod::{CoarseSim, FullSim, SimGrid};
+use crate::core::navigation::NavAgent;
+use crate::core::npc_ai::{Npc, NpcCategory, NpcRoutine, NpcState, NPC_WALK_SPEED};
+use crate::core::perception::{line_blocked, noise_reach, NoiseEvent, NoiseSource, PlayerNoise};
+use crate::core::player::Ethan;
+use crate::core::school;
+use crate::core::seed::NpcRng;
+use crate::core::world::MissionEntity;
+use crate::states::GameState;
+use crate::systems::controls::Controls;
+use crate::data::items::ItemDb;
+use crate::systems::inventory::Inventory;
+
+/// How far ahead of Ethan a prank lands
+const THROW_DISTANCE: f32 = 6.0;
+const STINK_RADIUS: f32 = 6.0;
+const STINK_SECS: f32 = 12.0;
+const FLEE_DISTANCE: f32 = 14.0;
+const FLEE_SPEED: f32 = 3.6;
+const FLEE_SECS: f32 = 6.0;
+const DISTRACTION_RADIUS: f32 = 12.0;
+const DISTRACTION_SECS: f32 = 8.0;
+/// Onlookers stand this far from a distraction
+const GATHER_RING: f32 = 2.2;
+const WITNESS_RANGE: f32 = 15.0;
+const WITNESS_FOV_DEG: f32 = 140.0;
+/// Chance a witness (other than a trader) goes to tell the Warden
+const REPORT_CHANCE: f64 = 0.5;
+const GOSSIP_RANGE: f32 = 3.0;
+/// How long a witness keeps talking about what they saw
+const GOSSIP_SECS: f32 = 180.0;
+/// Each retelling is shorter-lived; below this it isn't worth passing on
+const GOSSIP_MIN_SECS: f32 = 20.0;
+const NOTORIETY_PER_REPORT: f32 = 0.15;
+const NOTORIETY_PER_GOSSIP: f32 = 0.02;
+const NOTORIETY_DECAY_PER_DAY: f32 = 0.1;
+
+/// What a prank item does when thrown; items name it in their `Prank` use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrankKind {
+    StinkBomb,
+    PaperPlane,
+}
+
+/// Ethan threw a prank from `from`; it landed at `landed`
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PrankThrown {
+    pub kind: PrankKind,
+    pub from: Vec3,
+    pub landed: Vec3,
+}
+
+/// A witness reached the school and told on Ethan
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerReported {
+    pub witness: Entity,
+    pub seen_at: Vec3,
+}
+
+/// Lingering stink: NPCs inside run away
+#[derive(Component)]
+pub struct StinkCloud {
+    pub radius: f32,
+    pub remaining: f32,
+}
+
+/// Something worth gawping at: NPCs nearby gather round
+#[derive(Component)]
+pub struct Distraction {
+    pub radius: f32,
+    pub remaining: f32,
+}
+
+/// How much the town talks about Ethan, 0..1. Makes the Warden sharper.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct Notoriety(pub f32);
+
+impl Notoriety {
+    pub fn raise(&mut self, amount: f32) {
+        self.0 = (self.0 + amount).clamp(0.0, 1.0);
+    }
+}
+
+/// On an NPC that saw Ethan misbehave and is on the way to report it
+#[derive(Component)]
+pub struct Witnessed {
+    pub seen_at: Vec3,
+}
+
+/// On an NPC with a story to pass on
+#[derive(Component)]
+pub struct Gossip {
+    pub remaining: f32,
+    /// Already passed it on; each NPC retells a story once
+    pub told: bool,
+}
+
+pub struct ReactionsPlugin;
+
+impl Plugin for ReactionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Notoriety>()
+           .add_event::<PrankThrown>()
+           .add_event::<PlayerReported>()
+           .add_systems(Update, (throw_prank_system, stimulus_decay_system)
+               .run_if(in_state(GameState::Mission1).or_else(in_state(GameState::OpenWorld))))
+           .add_systems(Update, (
+               npc_hearing_system,
+               witness_system,
+               npc_stimulus_system,
+               report_system,
+               gossip_system,
+           ).chain().run_if(in_state(GameState::Mission1).or_else(in_state(GameState::OpenWorld))))
+           .add_systems(Update, notoriety_decay_system);
+    }
+}
+
+fn throw_prank_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<StandardMaterial>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    mut inventory: ResMut<Inventory>,
+    items: Res<ItemDb>,
+    player_q: Query<&Transform, With<Ethan>>,
+    mut thrown: EventWriter<PrankThrown>,
+    mut noise: EventWriter<NoiseEvent>,
+) {
+    if !keyboard.just_pressed(controls.prank) {
+        return;
+    }
+    let Ok(tf) = player_q.get_single() else { return };
+    let prank = |id: &str| if inventory.count(id) > 0 { items.get(id).and_then(|d| d.prank()) } else { None };
+    // the equipped prank, else whichever is in the bag (first in item order)
+    let choice = inventory.equipped_id().filter(|id| prank(id).is_some()).map(str::to_string)
+        .or_else(|| items.defs.iter().map(|d| d.id.clone()).find(|id| prank(id).is_some()));
+    let Some((id, kind)) = choice.and_then(|id| prank(&id).map(|k| (id, k))) else {
+        info!("No pranks to throw.");
+        return;
+    };
+    inventory.take(&id);
+
+    // model front is +Z, i.e. the transform's back
+    let facing = Vec3::new(tf.back().x, 0.0, tf.back().z).normalize_or_zero();
+    let landed = tf.translation + facing * THROW_DISTANCE;
+    match kind {
+        PrankKind::StinkBomb => {
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Sphere::new(STINK_RADIUS * 0.5).mesh().uv(16, 8)),
+                    material: mats.add(StandardMaterial {
+                        base_color: Color::srgba(0.45, 0.6, 0.15, 0.35),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    }),
+                    transform: Transform::from_translation(landed + Vec3::Y * 0.5),
+                    ..default()
+                },
+                StinkCloud { radius: STINK_RADIUS, remaining: STINK_SECS },
+                MissionEntity,
+            ));
+            noise.send(NoiseEvent { position: landed, loudness: 10.0, source: NoiseSource::Item });
+        }
+        PrankKind::PaperPlane => {
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cuboid::new(0.3, 0.05, 0.4)),
+                    material: mats.add(StandardMaterial::from(Color::WHITE)),
+                    transform: Transform::from_translation(landed + Vec3::Y * 0.05),
+                    ..default()
+                },
+                Distraction { radius: DISTRACTION_RADIUS, remaining: DISTRACTION_SECS },
+                MissionEntity,
+            ));
+            noise.send(NoiseEvent { position: landed, loudness: 3.0, source: NoiseSource::Item });
+        }
+    }
+    thrown.send(PrankThrown { kind, from: tf.translation, landed });
+    info!("Threw a {:?}", kind);
+}
+
+fn stimulus_decay_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut clouds: Query<(Entity, &mut StinkCloud)>,
+    mut distractions: Query<(Entity, &mut Distraction)>,
+) {
+    let dt = time.delta_seconds();
+    for (e, mut cloud) in &mut clouds {
+        cloud.remaining -= dt;
+        if cloud.remaining <= 0.0 {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+    for (e, mut distraction) in &mut distractions {
+        distraction.remaining -= dt;
+        if distraction.remaining <= 0.0 {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+/// Put an NPC back on its daily routine after a reaction
+fn end_reaction(npc: &mut Npc, routine: &mut NpcRoutine, agent: &mut NavAgent) {
+    agent.stop();
+    agent.speed = NPC_WALK_SPEED;
+    npc.state = NpcState::Walking;
+    // forces the schedule to re-plan the way back to the current activity
+    routine.activity = None;
+}
+
+/// NPCs who could see Ethan throw a prank start gossiping, and some head off to tell the Warden
+fn witness_system(
+    mut commands: Commands,
+    mut events: EventReader<PrankThrown>,
+    rapier: Res<RapierContext>,
+    mut rng: ResMut<NpcRng>,
+    player_q: Query<&PlayerNoise, With<Ethan>>,
+    mut npc_q: Query<(Entity, &mut Npc, &Transform, &mut NavAgent), (With<FullSim>, Without<Witnessed>)>,
+) {
+    let player_noise = player_q.get_single().ok();
+    for ev in events.read() {
+        let movement = player_noise.and_then(|n| n.event(ev.from));
+        for (entity, mut npc, tf, mut agent) in &mut npc_q {
+            if npc.state == NpcState::Fleeing {
+                continue;
+            }
+            let eye = tf.translation + Vec3::Y * 1.6;
+            let to_player = ev.from + Vec3::Y * 1.0 - eye;
+            let saw = to_player.length() <= WITNESS_RANGE
+                && tf.back().angle_between(to_player).to_degrees() <= WITNESS_FOV_DEG * 0.5
+                && !line_blocked(&rapier, eye, ev.from + Vec3::Y);
+            // a player sprinting up behind them turns heads too
+            let heard = movement.is_some_and(|n| tf.translation.distance(ev.from) < noise_reach(&rapier, &n, eye, 1.0));
+            if !saw && !heard {
+                continue;
+            }
+            commands.entity(entity).insert(Gossip { remaining: GOSSIP_SECS, told: false });
+            // traders won't leave their stalls, but they'll talk
+            if npc.category != NpcCategory::Trader && rng.0.gen_bool(REPORT_CHANCE) {
+                commands.entity(entity).insert(Witnessed { seen_at: ev.from });
+                npc.state = NpcState::Reporting;
+                agent.speed = NPC_WALK_SPEED * 1.5;
+                agent.set_destination(report_point());
+                info!("An NPC saw the {:?} and is off to tell the Warden", ev.kind);
+            }
+        }
+    }
+}
+
+/// NPCs standing about turn to look when they hear Ethan coming: a sprint
+/// carries further than a walk, and a crouch barely at all
+fn npc_hearing_system(
+    rapier: Res<RapierContext>,
+    player_q: Query<(&Transform, &PlayerNoise), With<Ethan>>,
+    mut npc_q: Query<(&Npc, &mut Transform), (With<FullSim>, Without<Ethan>)>,
+) {
+    let Ok((player_tf, noise)) = player_q.get_single() else { return };
+    let Some(movement) = noise.event(player_tf.translation) else { return };
+    for (npc, mut tf) in &mut npc_q {
+        if !matches!(npc.state, NpcState::Idle | NpcState::Interacting) {
+            continue;
+        }
+        let eye = tf.translation + Vec3::Y * 1.6;
+        if tf.translation.distance(player_tf.translation) >= noise_reach(&rapier, &movement, eye, 1.0) {
+            continue;
+        }
+        let to_player = Vec3::new(player_tf.translation.x - tf.translation.x, 0.0, player_tf.translation.z - tf.translation.z);
+        if to_player.length_squared() > 0.01 {
+            // models face +Z, so look the opposite way
+            tf.look_to(-to_player, Vec3::Y);
+        }
+    }
+}
+
+/// Where witnesses go to report: the school's entrance
+fn report_point() -> Vec3 {
+    school::entrance()
+}
+
+/// Flee stink clouds, gather round distractions, and go back to routine afterwards
+fn npc_stimulus_system(
+    time: Res<Time>,
+    mut rng: ResMut<NpcRng>,
+    clouds: Query<(&Transform, &StinkCloud)>,
+    distractions: Query<(&Transform, &Distraction)>,
+    mut npc_q: Query<(&mut Npc, &mut NpcRoutine, &mut Transform, &mut NavAgent), (With<FullSim>, Without<StinkCloud>, Without<Distraction>)>,
+) {
+    let dt = time.delta_seconds();
+    for (mut npc, mut routine, mut tf, mut agent) in &mut npc_q {
+        let pos = tf.translation;
+        let stink = clouds.iter().find(|(c, cloud)| c.translation.distance(pos) < cloud.radius);
+        if let Some((cloud_tf, _)) = stink {
+            if npc.state != NpcState::Fleeing {
+                let mut away = pos - cloud_tf.translation;
+                away.y = 0.0;
+                let away = if away.length_squared() < 0.01 { Vec3::X } else { away.normalize() };
+                npc.state = NpcState::Fleeing;
+                npc.timer = FLEE_SECS;
+                agent.speed = FLEE_SPEED;
+                agent.set_destination(pos + away * FLEE_DISTANCE);
+            }
+            continue;
+        }
+
+        match npc.state {
+            // a witness on the way to the Warden only turns back for a stink cloud
+            NpcState::Reporting => {}
+            NpcState::Fleeing => {
+                npc.timer -= dt;
+                if npc.timer <= 0.0 || agent.is_idle() {
+                    end_reaction(&mut npc, &mut routine, &mut agent);
+                }
+            }
+            NpcState::Gathering => {
+                let nearest = distractions
+                    .iter()
+                    .filter(|(d, distraction)| d.translation.distance(pos) < distraction.radius + GATHER_RING)
+                    .min_by(|a, b| a.0.translation.distance(pos).total_cmp(&b.0.translation.distance(pos)));
+                match nearest {
+                    Some((d, _)) => {
+                        // stand and stare once there
+                        if agent.is_idle() {
+                            let look = Vec3::new(d.translation.x - pos.x, 0.0, d.translation.z - pos.z);
+                            if look.length_squared() > 0.01 {
+                                tf.look_to(-look, Vec3::Y);
+                            }
+                        }
+                    }
+                    None => end_reaction(&mut npc, &mut routine, &mut agent),
+                }
+            }
+            _ => {
+                let nearby = distractions.iter().find(|(d, distraction)| d.translation.distance(pos) < distraction.radius);
+                if let Some((d, _)) = nearby {
+                    let angle = rng.0.gen::<f32>() * std::f32::consts::TAU;
+                    npc.state = NpcState::Gathering;
+                    agent.set_destination(d.translation + Vec3::new(angle.cos(), 0.0, angle.sin()) * GATHER_RING);
+                }
+            }
+        }
+    }
+}
+
+fn report_system(
+    mut commands: Commands,
+    mut notoriety: ResMut<Notoriety>,
+    mut reported: EventWriter<PlayerReported>,
+    mut npc_q: Query<(Entity, &mut Npc, &mut NpcRoutine, &mut NavAgent, &Witnessed, Has<FullSim>), Or<(With<FullSim>, With<CoarseSim>)>>,
+) {
+    for (entity, mut npc, mut routine, mut agent, witnessed, full) in &mut npc_q {
+        if npc.state != NpcState::Reporting {
+            // scared off on the way
+            commands.entity(entity).remove::<Witnessed>();
+            continue;
+        }
+        // further out the walk is a straight-line heading instead of the nav agent
+        let arrived = if full { agent.is_idle() } else { routine.heading().is_none() };
+        if !arrived {
+            continue;
+        }
+        reported.send(PlayerReported { witness: entity, seen_at: witnessed.seen_at });
+        notoriety.raise(NOTORIETY_PER_REPORT);
+        info!("Ethan was reported to the Warden (notoriety {:.2})", notoriety.0);
+        commands.entity(entity).remove::<Witnessed>();
+        end_reaction(&mut npc, &mut routine, &mut agent);
+    }
+}
+
+/// Stories spread between NPCs who pass close by: each one that has heard
+/// it tells the nearest NPC who hasn't, once, adding to notoriety
+fn gossip_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut notoriety: ResMut<Notoriety>,
+    grid: Res<SimGrid>,
+    mut tellers: Query<(Entity, &Transform, &mut Gossip)>,
+    listeners: Query<(), (With<Npc>, Without<Gossip>)>,
+) {
+    let dt = time.delta_seconds();
+    let mut told = Vec::new();
+    for (entity, tf, mut gossip) in &mut tellers {
+        gossip.remaining -= dt;
+        if gossip.remaining <= 0.0 {
+            commands.entity(entity).remove::<Gossip>();
+            continue;
+        }
+        let retold = gossip.remaining * 0.6;
+        if gossip.told || retold < GOSSIP_MIN_SECS {
+            continue;
+        }
+        let nearest = grid
+            .0
+            .within(tf.translation, GOSSIP_RANGE)
+            .filter(|(listener, _)| listeners.contains(*listener) && !told.contains(listener))
+            .min_by(|a, b| a.1.distance_squared(tf.translation).total_cmp(&b.1.distance_squared(tf.translation)));
+        if let Some((listener, _)) = nearest {
+            told.push(listener);
+            gossip.told = true;
+            commands.entity(listener).insert(Gossip { remaining: retold, told: false });
+            notoriety.raise(NOTORIETY_PER_GOSSIP);
+        }
+    }
+}
+
+/// Talk dies down: notoriety falls a little each midnight
+fn notoriety_decay_system(mut hours: EventReader<HourChanged>, mut notoriety: ResMut<Notoriety>) {
+    for h in hours.read() {
+        if h.hour == 0 {
+            notoriety.raise(-NOTORIETY_DECAY_PER_DAY);
+        }
+    }
+}
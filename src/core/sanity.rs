@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::core::perception::{light_level_at, CarriedLight, Perception};
+use crate::core::player::Ethan;
+use crate::core::reactions::PlayerReported;
+use crate::core::warden::TheWarden;
+use crate::narrative::ActiveTimeline;
+use crate::progression::GameProgress;
+use crate::route_events::PlayerCaught;
+use crate::states::GameState;
+
+/// Below this light level Ethan's nerves start to go
+const DARK_LEVEL: f32 = 0.25;
+/// Sanity per second lost in full darkness
+const DARK_DRAIN: f32 = 0.012;
+/// The Warden unsettles Ethan from this far away, worse the closer he is
+const WARDEN_DREAD_RADIUS: f32 = 14.0;
+const WARDEN_DRAIN: f32 = 0.03;
+/// Extra drain per second while the Warden is actually looking at him
+const WARDEN_SEEN_DRAIN: f32 = 0.04;
+/// Recovery per second somewhere lit with the Warden far off
+const CALM_REGEN: f32 = 0.006;
+const CAUGHT_SHOCK: f32 = 0.25;
+const REPORTED_SHOCK: f32 = 0.05;
+
+/// How close Ethan is to breaking, from tier thresholds on `Sanity::value`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SanityTier {
+    Breaking,
+    Frayed,
+    Uneasy,
+    Stable,
+}
+
+impl SanityTier {
+    pub fn of(value: f32) -> Self {
+        match value {
+            v if v > 0.7 => SanityTier::Stable,
+            v if v > 0.45 => SanityTier::Uneasy,
+            v if v > 0.2 => SanityTier::Frayed,
+            _ => SanityTier::Breaking,
+        }
+    }
+}
+
+/// Ethan's grip on reality, 0 (gone) to 1 (fine). Low sanity brings on
+/// hallucinations and opens the Fragmented Mind route (`route_mapping::route_unlocked`).
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Sanity {
+    pub value: f32,
+}
+
+impl Default for Sanity {
+    fn default() -> Self {
+        Self { value: 1.0 }
+    }
+}
+
+impl Sanity {
+    pub fn change(&mut self, amount: f32) {
+        self.value = (self.value + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn tier(&self) -> SanityTier {
+        SanityTier::of(self.value)
+    }
+}
+
+/// A sudden hit to sanity from something Ethan went through
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SanityShock {
+    pub amount: f32,
+}
+
+pub struct SanityPlugin;
+
+impl Plugin for SanityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Sanity>()
+           .add_event::<SanityShock>()
+           .add_systems(Update, sanity_environment_system.run_if(in_state(GameState::Mission1)))
+           .add_systems(Update, (timeline_sanity_system, sanity_shock_system, record_sanity_system).chain());
+    }
+}
+
+/// Darkness and the Warden wear Ethan down; light and distance let him recover
+fn sanity_environment_system(
+    time: Res<Time>,
+    ambient: Option<Res<AmbientLight>>,
+    points: Query<(&GlobalTransform, &PointLight)>,
+    spots: Query<(&GlobalTransform, &SpotLight)>,
+    suns: Query<&DirectionalLight>,
+    player_q: Query<(&Transform, Option<&CarriedLight>), With<Ethan>>,
+    wardens: Query<(&Transform, &Perception), With<TheWarden>>,
+    mut sanity: ResMut<Sanity>,
+) {
+    let Ok((tf, carried)) = player_q.get_single() else { return };
+    let dt = time.delta_seconds();
+
+    let mut light = light_level_at(tf.translation, ambient.as_deref(), &points, &spots, &suns);
+    if carried.is_some_and(|c| c.lit) {
+        light = light.max(DARK_LEVEL);
+    }
+    let mut drain = 0.0;
+    if light < DARK_LEVEL {
+        drain += DARK_DRAIN * (1.0 - light / DARK_LEVEL);
+    }
+    for (warden_tf, perception) in &wardens {
+        let d = warden_tf.translation.distance(tf.translation);
+        if d < WARDEN_DREAD_RADIUS {
+            drain += WARDEN_DRAIN * (1.0 - d / WARDEN_DREAD_RADIUS);
+        }
+        if perception.sees_player {
+            drain += WARDEN_SEEN_DRAIN;
+        }
+    }
+
+    if drain > 0.0 {
+        sanity.change(-drain * dt);
+    } else {
+        sanity.change(CALM_REGEN * dt);
+    }
+}
+
+/// Timeline frames can carry a `sanity` change, applied once as the frame comes up
+fn timeline_sanity_system(
+    active: Option<Res<ActiveTimeline>>,
+    mut last: Local<Option<(String, usize)>>,
+    mut shocks: EventWriter<SanityShock>,
+) {
+    let Some(active) = active else {
+        *last = None;
+        return;
+    };
+    let Some(frame) = active.current_frame() else { return };
+    let key = (active.timeline.title.clone(), active.current);
+    if last.as_ref() == Some(&key) {
+        return;
+    }
+    *last = Some(key);
+    if frame.sanity != 0.0 {
+        shocks.send(SanityShock { amount: frame.sanity });
+    }
+}
+
+fn sanity_shock_system(
+    mut shocks: EventReader<SanityShock>,
+    mut caught: EventReader<PlayerCaught>,
+    mut reported: EventReader<PlayerReported>,
+    mut sanity: ResMut<Sanity>,
+) {
+    for shock in shocks.read() {
+        sanity.change(shock.amount);
+    }
+    for _ in caught.read() {
+        sanity.change(-CAUGHT_SHOCK);
+    }
+    for _ in reported.read() {
+        sanity.change(-REPORTED_SHOCK);
+    }
+}
+
+/// Remember the lowest Ethan has sunk; route unlocks depend on it
+fn record_sanity_system(sanity: Res<Sanity>, progress: Option<ResMut<GameProgress>>) {
+    let Some(mut progress) = progress else { return };
+    if sanity.value < progress.lowest_sanity {
+        progress.lowest_sanity = sanity.value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_mapping::route_unlocked;
+    use bevy::state::app::StatesPlugin;
+
+    #[test]
+    fn breaking_opens_the_fragmented_mind() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, SanityPlugin))
+           .init_state::<GameState>()
+           .add_event::<PlayerCaught>()
+           .add_event::<PlayerReported>()
+           .init_resource::<GameProgress>();
+        app.update();
+        assert!(!route_unlocked(4, app.world().resource::<GameProgress>()));
+
+        // a bad night: timeline scares and a couple of captures
+        for n in 1..=3 {
+            app.world_mut().send_event(SanityShock { amount: -0.2 });
+            app.world_mut().send_event(PlayerCaught { times: n });
+            app.update();
+        }
+        assert_eq!(app.world().resource::<Sanity>().tier(), SanityTier::Breaking);
+        assert!(route_unlocked(4, app.world().resource::<GameProgress>()));
+
+        // recovering afterwards doesn't close it again
+        app.world_mut().send_event(SanityShock { amount: 1.0 });
+        app.update();
+        assert!(route_unlocked(4, app.world().resource::<GameProgress>()));
+    }
+}
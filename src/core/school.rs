@@ -0,0 +1,854 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::data::assets_loader::Handles;
+use crate::core::world::{on_road, MissionEntity};
+use crate::core::navigation::NavIgnore;
+use crate::core::hiding::HidingSpot;
+
+// building footprint (world units = metres)
+pub const SCHOOL_ORIGIN: Vec3 = Vec3::new(40.0, 0.0, 20.0);
+pub const SCHOOL_LENGTH: f32 = 112.0; // along X
+pub const SCHOOL_DEPTH: f32 = 30.0; // along Z
+pub const FLOOR_HEIGHT: f32 = 4.0;
+pub const FLOOR_COUNT: u32 = 2;
+
+const CORRIDOR_WIDTH: f32 = 4.0;
+const WALL_THICKNESS: f32 = 0.3;
+const SLAB_THICKNESS: f32 = 0.2;
+const PARAPET_HEIGHT: f32 = 1.1;
+const DOOR_WIDTH: f32 = 1.6;
+const DOOR_HEIGHT: f32 = 2.6;
+const STAIRWELL_WIDTH: f32 = 8.0;
+const STAIR_WIDTH: f32 = 3.0;
+/// Landing strip kept between the corridor wall and the stairwell opening.
+const LANDING_DEPTH: f32 = 2.0;
+/// Kenney furniture is modelled at roughly half scale.
+const FURNITURE_SCALE: f32 = 2.0;
+/// plant_bushLarge.glb is ~0.37 units wide
+const BUSH_SCALE: f32 = 6.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomKind {
+    Classroom,
+    Chapel,
+    Canteen,
+    Storage,
+    Stairwell,
+}
+
+#[derive(Debug, Clone)]
+pub struct Room {
+    pub kind: RoomKind,
+    pub floor: u32,
+    /// (x, z) corner with the smallest coordinates
+    pub min: Vec2,
+    pub max: Vec2,
+    /// Centre of the doorway on the corridor wall, at floor level
+    pub door: Vec3,
+}
+
+impl Room {
+    pub fn center(&self) -> Vec3 {
+        let c = (self.min + self.max) * 0.5;
+        Vec3::new(c.x, floor_y(self.floor), c.y)
+    }
+
+    pub fn contains(&self, p: Vec3) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.z >= self.min.y && p.z <= self.max.y
+    }
+}
+
+/// Axis-aligned solid block (walls, slabs, parapets)
+#[derive(Debug, Clone, Copy)]
+pub struct Block {
+    pub center: Vec3,
+    pub half_extents: Vec3,
+}
+
+/// Inclined ramp carrying the stairs between two floors
+#[derive(Debug, Clone, Copy)]
+pub struct Stair {
+    pub bottom: Vec3,
+    pub top: Vec3,
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropKind {
+    Desk,
+    Chair,
+    TeacherDesk,
+    Bookcase,
+    Pew,
+    Locker,
+    Door,
+    Bush,
+}
+
+impl PropKind {
+    /// Full extents in metres, used for colliders and fallback meshes.
+    pub fn size(self) -> Vec3 {
+        match self {
+            PropKind::Desk | PropKind::TeacherDesk => Vec3::new(1.46, 0.76, 1.12),
+            PropKind::Chair => Vec3::new(0.96, 0.84, 0.88),
+            PropKind::Bookcase => Vec3::new(1.6, 1.58, 0.5),
+            PropKind::Pew => Vec3::new(0.8, 0.94, 0.4),
+            PropKind::Locker => Vec3::new(0.9, 2.0, 0.5),
+            PropKind::Door => Vec3::new(DOOR_WIDTH, DOOR_HEIGHT, 0.08),
+            PropKind::Bush => Vec3::new(2.2, 1.45, 2.0),
+        }
+    }
+
+    /// Kenney models have their origin at a corner; this is the model-space
+    /// centre of the footprint so the scene can be re-centred on the prop.
+    fn model_center(self) -> Vec3 {
+        match self {
+            PropKind::Desk | PropKind::TeacherDesk => Vec3::new(0.355, 0.0, -0.1),
+            PropKind::Chair => Vec3::new(0.07, 0.0, -0.06),
+            PropKind::Bookcase => Vec3::new(0.4, 0.0, -0.125),
+            PropKind::Pew => Vec3::new(0.2, 0.0, -0.1),
+            PropKind::Door => Vec3::new(0.0, 0.0, 0.025),
+            PropKind::Locker | PropKind::Bush => Vec3::ZERO,
+        }
+    }
+
+    fn model_scale(self) -> Vec3 {
+        match self {
+            // door-white.glb is 0.24 x 0.5 units
+            PropKind::Door => Vec3::new(DOOR_WIDTH / 0.24, DOOR_HEIGHT / 0.5, 1.6),
+            PropKind::Bush => Vec3::splat(BUSH_SCALE),
+            _ => Vec3::splat(FURNITURE_SCALE),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Prop {
+    pub kind: PropKind,
+    /// Floor-level transform; the prop's footprint is centred on it and its
+    /// front (model +Z) faces away from the `looking_to` direction
+    pub transform: Transform,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HidingKind {
+    Locker,
+    UnderDesk,
+    Bush,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HidingSpotInfo {
+    pub kind: HidingKind,
+    pub position: Vec3,
+    /// Direction the player faces while hidden (out of the locker / from under the desk)
+    pub facing: Vec3,
+    pub floor: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaypointKind {
+    Corridor,
+    Doorway,
+    Room(RoomKind),
+    Stairs,
+    Rooftop,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Waypoint {
+    pub position: Vec3,
+    pub kind: WaypointKind,
+    pub floor: u32,
+}
+
+/// Output of the floor-plan generator. Inserted as a resource so AI and
+/// stealth systems can query rooms, waypoints and hiding spots.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SchoolLayout {
+    pub seed: u64,
+    pub rooms: Vec<Room>,
+    pub walls: Vec<Block>,
+    pub slabs: Vec<Block>,
+    pub stairs: Vec<Stair>,
+    pub props: Vec<Prop>,
+    pub waypoints: Vec<Waypoint>,
+    pub hiding_spots: Vec<HidingSpotInfo>,
+}
+
+impl SchoolLayout {
+    pub fn rooms_of_kind(&self, kind: RoomKind) -> impl Iterator<Item = &Room> {
+        self.rooms.iter().filter(move |r| r.kind == kind)
+    }
+
+    pub fn waypoints_of_kind(&self, kind: WaypointKind) -> impl Iterator<Item = &Waypoint> {
+        self.waypoints.iter().filter(move |w| w.kind == kind)
+    }
+}
+
+/// Marker on the entity carrying a hiding spot's prop (locker, teacher desk, bush).
+/// The index points into `SchoolLayout::hiding_spots`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HidingSpotAnchor {
+    pub kind: HidingKind,
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct SchoolDoor;
+
+pub fn floor_y(floor: u32) -> f32 {
+    floor as f32 * FLOOR_HEIGHT + SLAB_THICKNESS
+}
+
+/// Just outside the ground-floor corridor's west end, where the school is entered
+pub fn entrance() -> Vec3 {
+    Vec3::new(SCHOOL_ORIGIN.x - SCHOOL_LENGTH * 0.5 - 1.5, 0.0, SCHOOL_ORIGIN.z)
+}
+
+/// Whether `pos` is within `margin` of the school's footprint on the ground
+pub fn in_footprint(pos: Vec3, margin: f32) -> bool {
+    let half = Vec2::new(SCHOOL_LENGTH, SCHOOL_DEPTH) * 0.5 + margin;
+    (pos.x - SCHOOL_ORIGIN.x).abs() < half.x && (pos.z - SCHOOL_ORIGIN.z).abs() < half.y
+}
+
+struct Bounds {
+    min_x: f32,
+    max_x: f32,
+    min_z: f32,
+    max_z: f32,
+    corridor_s: f32,
+    corridor_n: f32,
+}
+
+impl Bounds {
+    fn new() -> Self {
+        Self {
+            min_x: SCHOOL_ORIGIN.x - SCHOOL_LENGTH * 0.5,
+            max_x: SCHOOL_ORIGIN.x + SCHOOL_LENGTH * 0.5,
+            min_z: SCHOOL_ORIGIN.z - SCHOOL_DEPTH * 0.5,
+            max_z: SCHOOL_ORIGIN.z + SCHOOL_DEPTH * 0.5,
+            corridor_s: SCHOOL_ORIGIN.z - CORRIDOR_WIDTH * 0.5,
+            corridor_n: SCHOOL_ORIGIN.z + CORRIDOR_WIDTH * 0.5,
+        }
+    }
+
+    fn west_stairwell(&self) -> (f32, f32) {
+        (self.min_x, self.min_x + STAIRWELL_WIDTH)
+    }
+
+    fn east_stairwell(&self) -> (f32, f32) {
+        (self.max_x - STAIRWELL_WIDTH, self.max_x)
+    }
+}
+
+/// Generate the school floor plan. The same seed always yields the same layout.
+pub fn generate_school_layout(seed: u64) -> SchoolLayout {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let b = Bounds::new();
+    let mut layout = SchoolLayout { seed, ..default() };
+
+    for floor in 0..FLOOR_COUNT {
+        let y = floor_y(floor);
+
+        // north row: stairwells at both ends, classrooms between; the canteen is on the ground floor
+        let (wx0, wx1) = b.west_stairwell();
+        let (ex0, ex1) = b.east_stairwell();
+        let mut north = vec![(wx0, wx1, RoomKind::Stairwell)];
+        north.extend(partition_row(&mut rng, wx1, ex0, (floor == 0).then_some(RoomKind::Canteen)));
+        north.push((ex0, ex1, RoomKind::Stairwell));
+        // south row: full length, the chapel sits on the ground floor
+        let south = partition_row(&mut rng, b.min_x, b.max_x, (floor == 0).then_some(RoomKind::Chapel));
+
+        for &(x0, x1, kind) in &north {
+            let door_x = door_offset(&mut rng, x0, x1, kind);
+            layout.rooms.push(Room {
+                kind,
+                floor,
+                min: Vec2::new(x0, b.corridor_n),
+                max: Vec2::new(x1, b.max_z),
+                door: Vec3::new(door_x, y, b.corridor_n),
+            });
+        }
+        for &(x0, x1, kind) in &south {
+            let door_x = door_offset(&mut rng, x0, x1, kind);
+            layout.rooms.push(Room {
+                kind,
+                floor,
+                min: Vec2::new(x0, b.min_z),
+                max: Vec2::new(x1, b.corridor_s),
+                door: Vec3::new(door_x, y, b.corridor_s),
+            });
+        }
+
+        build_floor_walls(&mut layout, &b, floor, &north, &south);
+        place_lockers(&mut layout, &mut rng, &b, floor);
+    }
+
+    build_slabs_and_stairs(&mut layout, &b);
+
+    let rooms = layout.rooms.clone();
+    for room in &rooms {
+        furnish_room(&mut layout, &mut rng, &b, room);
+    }
+
+    place_bushes(&mut layout, &mut rng, &b);
+    build_waypoints(&mut layout, &b);
+    layout
+}
+
+/// Split the span [x0, x1] into rooms of varying width. A `hall` (chapel,
+/// canteen) takes one large slot near the start of the row.
+fn partition_row(rng: &mut StdRng, x0: f32, x1: f32, hall: Option<RoomKind>) -> Vec<(f32, f32, RoomKind)> {
+    let mut rooms = Vec::new();
+    let hall_slot = hall.map(|kind| (rng.gen_range(1..4), kind));
+    let mut x = x0;
+    let mut slot = 0;
+    while x1 - x > 0.01 {
+        let (kind, width) = if let Some((_, kind)) = hall_slot.filter(|(s, _)| *s == slot) {
+            (kind, rng.gen_range(18.0..22.0))
+        } else if rng.gen_bool(0.2) {
+            (RoomKind::Storage, rng.gen_range(5.0..7.0))
+        } else {
+            (RoomKind::Classroom, rng.gen_range(9.0..14.0))
+        };
+        // never leave a sliver too narrow to be a room
+        let remaining = x1 - x;
+        let width = if remaining - width < 5.0 { remaining } else { width };
+        rooms.push((x, x + width, kind));
+        x += width;
+        slot += 1;
+    }
+    rooms
+}
+
+fn door_offset(rng: &mut StdRng, x0: f32, x1: f32, kind: RoomKind) -> f32 {
+    match kind {
+        RoomKind::Stairwell => (x0 + x1) * 0.5,
+        _ => rng.gen_range((x0 + 1.5)..(x1 - 1.5)),
+    }
+}
+
+fn build_floor_walls(
+    layout: &mut SchoolLayout,
+    b: &Bounds,
+    floor: u32,
+    north: &[(f32, f32, RoomKind)],
+    south: &[(f32, f32, RoomKind)],
+) {
+    let y0 = floor_y(floor);
+    let h = FLOOR_HEIGHT - SLAB_THICKNESS;
+    let walls = &mut layout.walls;
+
+    // outer shell; the corridor opens to the outside on the ground floor
+    let entrances: &[f32] = if floor == 0 { &[SCHOOL_ORIGIN.z] } else { &[] };
+    push_wall(walls, true, b.min_z, b.min_x, b.max_x, y0, h, &[]);
+    push_wall(walls, true, b.max_z, b.min_x, b.max_x, y0, h, &[]);
+    push_wall(walls, false, b.min_x, b.min_z, b.max_z, y0, h, entrances);
+    push_wall(walls, false, b.max_x, b.min_z, b.max_z, y0, h, entrances);
+
+    // corridor walls with a doorway per room
+    let floor_rooms = layout.rooms.iter().filter(|r| r.floor == floor);
+    let (north_doors, south_doors): (Vec<_>, Vec<_>) = floor_rooms.partition(|r| r.door.z == b.corridor_n);
+    let north_gaps = north_doors.iter().map(|r| r.door.x).collect::<Vec<_>>();
+    let south_gaps = south_doors.iter().map(|r| r.door.x).collect::<Vec<_>>();
+    push_wall(walls, true, b.corridor_n, b.min_x, b.max_x, y0, h, &north_gaps);
+    push_wall(walls, true, b.corridor_s, b.min_x, b.max_x, y0, h, &south_gaps);
+
+    // partitions between neighbouring rooms
+    for pair in north.windows(2) {
+        push_wall(walls, false, pair[0].1, b.corridor_n, b.max_z, y0, h, &[]);
+    }
+    for pair in south.windows(2) {
+        push_wall(walls, false, pair[0].1, b.min_z, b.corridor_s, y0, h, &[]);
+    }
+}
+
+/// Push a wall running along X (`along_x`) or Z at `fixed`, from `a` to `b`,
+/// leaving a doorway with a lintel at every gap centre.
+fn push_wall(walls: &mut Vec<Block>, along_x: bool, fixed: f32, a: f32, b: f32, y0: f32, height: f32, gaps: &[f32]) {
+    let mut segment = |from: f32, to: f32, bottom: f32, h: f32| {
+        if to - from < 0.05 || h <= 0.0 {
+            return;
+        }
+        let mid = (from + to) * 0.5;
+        let half_len = (to - from) * 0.5;
+        let (center, half_extents) = if along_x {
+            (Vec3::new(mid, bottom + h * 0.5, fixed), Vec3::new(half_len, h * 0.5, WALL_THICKNESS * 0.5))
+        } else {
+            (Vec3::new(fixed, bottom + h * 0.5, mid), Vec3::new(WALL_THICKNESS * 0.5, h * 0.5, half_len))
+        };
+        walls.push(Block { center, half_extents });
+    };
+
+    let mut gaps = gaps.to_vec();
+    gaps.sort_by(|x, y| x.total_cmp(y));
+    let mut cursor = a;
+    for g in gaps {
+        let (g0, g1) = (g - DOOR_WIDTH * 0.5, g + DOOR_WIDTH * 0.5);
+        segment(cursor, g0, y0, height);
+        segment(g0, g1, y0 + DOOR_HEIGHT, height - DOOR_HEIGHT);
+        cursor = g1;
+    }
+    segment(cursor, b, y0, height);
+}
+
+fn place_lockers(layout: &mut SchoolLayout, rng: &mut StdRng, b: &Bounds, floor: u32) {
+    let y = floor_y(floor);
+    let size = PropKind::Locker.size();
+    for (wall_z, facing) in [(b.corridor_n, -Vec3::Z), (b.corridor_s, Vec3::Z)] {
+        let doors = layout
+            .rooms
+            .iter()
+            .filter(|r| r.floor == floor && r.door.z == wall_z)
+            .map(|r| r.door.x)
+            .collect::<Vec<_>>();
+        // x spans already lined with lockers on this wall
+        let mut taken: Vec<(f32, f32)> = Vec::new();
+        let banks = rng.gen_range(4..7);
+        for _ in 0..banks {
+            let start = rng.gen_range((b.min_x + 2.0)..(b.max_x - 5.0));
+            let count = rng.gen_range(2..5);
+            let span = (start - size.x * 0.5, start + (count as f32 - 0.5) * size.x);
+            let clear = (0..count).all(|i| {
+                let x = start + i as f32 * size.x;
+                doors.iter().all(|d| (x - d).abs() > DOOR_WIDTH + size.x)
+            });
+            if !clear || taken.iter().any(|&(lo, hi)| span.0 < hi && lo < span.1) {
+                continue;
+            }
+            taken.push(span);
+            for i in 0..count {
+                let pos = Vec3::new(
+                    start + i as f32 * size.x,
+                    y,
+                    wall_z + facing.z * (WALL_THICKNESS * 0.5 + size.z * 0.5),
+                );
+                let transform = Transform::from_translation(pos).looking_to(-facing, Vec3::Y);
+                layout.props.push(Prop { kind: PropKind::Locker, transform });
+                layout.hiding_spots.push(HidingSpotInfo { kind: HidingKind::Locker, position: pos, facing, floor });
+            }
+        }
+    }
+}
+
+/// Hedge bushes around the outside of the school, on the ground, wherever
+/// the footprint is clear of the roads and the corridor entrances.
+fn place_bushes(layout: &mut SchoolLayout, rng: &mut StdRng, b: &Bounds) {
+    let size = PropKind::Bush.size();
+    // (row start, row direction, row length, outward facing)
+    let rows = [
+        (Vec3::new(b.min_x, 0.0, b.min_z - size.z), Vec3::X, SCHOOL_LENGTH, -Vec3::Z),
+        (Vec3::new(b.min_x, 0.0, b.max_z + size.z), Vec3::X, SCHOOL_LENGTH, Vec3::Z),
+        (Vec3::new(b.min_x - size.z, 0.0, b.min_z), Vec3::Z, SCHOOL_DEPTH, -Vec3::X),
+        (Vec3::new(b.max_x + size.z, 0.0, b.min_z), Vec3::Z, SCHOOL_DEPTH, Vec3::X),
+    ];
+    for (start, along, length, facing) in rows {
+        let half = along * size.x * 0.5 + facing.abs() * size.z * 0.5;
+        let mut d = rng.gen_range(2.0..6.0);
+        while d < length - 2.0 {
+            let pos = start + along * d;
+            d += rng.gen_range(7.0..14.0);
+            let corners = [Vec3::new(-1.0, 0.0, -1.0), Vec3::new(-1.0, 0.0, 1.0), Vec3::new(1.0, 0.0, -1.0), Vec3::new(1.0, 0.0, 1.0)];
+            if corners.iter().any(|c| on_road(pos + *c * half)) {
+                continue;
+            }
+            // keep the corridor's open ends clear
+            if along == Vec3::Z && (pos.z - SCHOOL_ORIGIN.z).abs() < CORRIDOR_WIDTH * 0.5 + half.z + 0.5 {
+                continue;
+            }
+            let transform = Transform::from_translation(pos).looking_to(-facing, Vec3::Y);
+            layout.props.push(Prop { kind: PropKind::Bush, transform });
+            layout.hiding_spots.push(HidingSpotInfo { kind: HidingKind::Bush, position: pos, facing, floor: 0 });
+        }
+    }
+}
+
+fn build_slabs_and_stairs(layout: &mut SchoolLayout, b: &Bounds) {
+    let opening_z0 = b.corridor_n + LANDING_DEPTH;
+    let stairwells = [b.west_stairwell(), b.east_stairwell()];
+
+    // ground slab covers everything
+    push_slab(&mut layout.slabs, b.min_x, b.max_x, b.min_z, b.max_z, 0.0);
+
+    for level in 1..=FLOOR_COUNT {
+        // stairs reach the roof through the west stairwell only
+        let wells = if level == FLOOR_COUNT { &stairwells[..1] } else { &stairwells[..] };
+        let bottom = (level as f32) * FLOOR_HEIGHT;
+
+        // switchback: odd flights climb south in the west lane of the well and
+        // land on the strip by the corridor, even flights climb back north in
+        // the east lane to a landing against the outer wall. Each slab is only
+        // open above the flight arriving through it.
+        let southward = level % 2 == 1;
+        let holes: Vec<(f32, f32)> = wells
+            .iter()
+            .map(|&(h0, h1)| if southward { (h0, (h0 + h1) * 0.5) } else { ((h0 + h1) * 0.5, h1) })
+            .collect();
+        let (hole_z0, hole_z1) = if southward { (opening_z0, b.max_z) } else { (opening_z0, b.max_z - LANDING_DEPTH) };
+
+        // everything south of the stairwell openings
+        push_slab(&mut layout.slabs, b.min_x, b.max_x, b.min_z, hole_z0, bottom);
+        // north of the openings, the landing against the outer wall
+        push_slab(&mut layout.slabs, b.min_x, b.max_x, hole_z1, b.max_z, bottom);
+        // the band between, split around the openings
+        let mut cursor = b.min_x;
+        for &(h0, h1) in &holes {
+            push_slab(&mut layout.slabs, cursor, h0, hole_z0, hole_z1, bottom);
+            cursor = h1;
+        }
+        push_slab(&mut layout.slabs, cursor, b.max_x, hole_z0, hole_z1, bottom);
+
+        for &(h0, h1) in &holes {
+            let x = (h0 + h1) * 0.5;
+            let (z0, z1) = if southward { (b.max_z - 0.5, opening_z0) } else { (opening_z0, b.max_z - LANDING_DEPTH) };
+            layout.stairs.push(Stair {
+                bottom: Vec3::new(x, floor_y(level - 1), z0),
+                top: Vec3::new(x, floor_y(level), z1),
+                width: STAIR_WIDTH,
+            });
+        }
+    }
+
+    // rooftop parapet
+    let roof = floor_y(FLOOR_COUNT);
+    push_wall(&mut layout.walls, true, b.min_z, b.min_x, b.max_x, roof, PARAPET_HEIGHT, &[]);
+    push_wall(&mut layout.walls, true, b.max_z, b.min_x, b.max_x, roof, PARAPET_HEIGHT, &[]);
+    push_wall(&mut layout.walls, false, b.min_x, b.min_z, b.max_z, roof, PARAPET_HEIGHT, &[]);
+    push_wall(&mut layout.walls, false, b.max_x, b.min_z, b.max_z, roof, PARAPET_HEIGHT, &[]);
+}
+
+fn push_slab(slabs: &mut Vec<Block>, x0: f32, x1: f32, z0: f32, z1: f32, bottom: f32) {
+    if x1 - x0 < 0.05 || z1 - z0 < 0.05 {
+        return;
+    }
+    slabs.push(Block {
+        center: Vec3::new((x0 + x1) * 0.5, bottom + SLAB_THICKNESS * 0.5, (z0 + z1) * 0.5),
+        half_extents: Vec3::new((x1 - x0) * 0.5, SLAB_THICKNESS * 0.5, (z1 - z0) * 0.5),
+    });
+}
+
+fn furnish_room(layout: &mut SchoolLayout, rng: &mut StdRng, b: &Bounds, room: &Room) {
+    let y = room.door.y;
+    let north = room.door.z == b.corridor_n;
+    // z of the outer wall and the direction pointing into the room from the corridor
+    let (outer_z, inward) = if north { (room.max.y, 1.0) } else { (room.min.y, -1.0) };
+    let inset = WALL_THICKNESS * 0.5 + 0.3;
+
+    // door leaf swung open against the corridor wall, inside the room
+    let hinge_x = room.door.x - DOOR_WIDTH * 0.5;
+    let door_pos = Vec3::new(hinge_x - 0.1, y, room.door.z + inward * (DOOR_WIDTH * 0.5 + WALL_THICKNESS));
+    layout.props.push(Prop {
+        kind: PropKind::Door,
+        transform: Transform::from_translation(door_pos).looking_to(Vec3::X, Vec3::Y),
+    });
+
+    match room.kind {
+        RoomKind::Classroom => {
+            // pupils face +X towards the teacher's desk
+            let front_x = room.max.x - 1.6;
+            let teacher = Vec3::new(front_x, y, (room.min.y + room.max.y) * 0.5);
+            layout.props.push(Prop {
+                kind: PropKind::TeacherDesk,
+                transform: Transform::from_translation(teacher).looking_to(Vec3::X, Vec3::Y),
+            });
+            layout.hiding_spots.push(HidingSpotInfo {
+                kind: HidingKind::UnderDesk,
+                position: teacher,
+                facing: -Vec3::X,
+                floor: room.floor,
+            });
+
+            let z_near = room.door.z + inward * 2.6;
+            let z_far = outer_z - inward * 1.6;
+            let (z0, z1) = if z_near < z_far { (z_near, z_far) } else { (z_far, z_near) };
+            let mut x = room.min.x + 2.0;
+            while x < front_x - 2.4 {
+                let mut z = z0;
+                while z <= z1 {
+                    // a few empty seats so rooms don't all look stamped out
+                    if rng.gen_bool(0.9) {
+                        let desk = Vec3::new(x, y, z);
+                        layout.props.push(Prop { kind: PropKind::Desk, transform: Transform::from_translation(desk).looking_to(-Vec3::X, Vec3::Y) });
+                        layout.props.push(Prop {
+                            kind: PropKind::Chair,
+                            transform: Transform::from_translation(desk - Vec3::X * 0.8).looking_to(-Vec3::X, Vec3::Y),
+                        });
+                    }
+                    z += 2.2;
+                }
+                x += 2.4;
+            }
+
+            let shelf = Vec3::new(room.min.x + 1.2, y, outer_z - inward * inset);
+            layout.props.push(Prop {
+                kind: PropKind::Bookcase,
+                transform: Transform::from_translation(shelf).looking_to(Vec3::Z * inward, Vec3::Y),
+            });
+        }
+        RoomKind::Chapel => {
+            let altar = Vec3::new(room.max.x - 2.0, y, (room.min.y + room.max.y) * 0.5);
+            layout.props.push(Prop { kind: PropKind::TeacherDesk, transform: Transform::from_translation(altar).looking_to(Vec3::X, Vec3::Y) });
+            // two blocks of pews with a centre aisle
+            let aisle = altar.z;
+            let mut x = room.min.x + 2.0;
+            while x < altar.x - 3.0 {
+                for side in [-1.0, 1.0] {
+                    for seat in 1..=4 {
+                        let z = aisle + side * (0.6 + seat as f32 * 0.85);
+                        if z > room.min.y + 0.6 && z < room.max.y - 0.6 {
+                            layout.props.push(Prop {
+                                kind: PropKind::Pew,
+                                transform: Transform::from_translation(Vec3::new(x, y, z)).looking_to(-Vec3::X, Vec3::Y),
+                            });
+                        }
+                    }
+                }
+                x += 1.6;
+            }
+        }
+        RoomKind::Canteen => {
+            // long tables of pushed-together desks, benches of chairs either side
+            let z_near = room.door.z + inward * 2.8;
+            let z_far = outer_z - inward * 1.8;
+            let (z0, z1) = if z_near < z_far { (z_near, z_far) } else { (z_far, z_near) };
+            let mut z = z0;
+            while z <= z1 {
+                let mut x = room.min.x + 2.0;
+                while x < room.max.x - 2.0 {
+                    let table = Vec3::new(x, y, z);
+                    layout.props.push(Prop { kind: PropKind::Desk, transform: Transform::from_translation(table).looking_to(Vec3::Z, Vec3::Y) });
+                    for side in [-1.0, 1.0] {
+                        if rng.gen_bool(0.85) {
+                            layout.props.push(Prop {
+                                kind: PropKind::Chair,
+                                transform: Transform::from_translation(table + Vec3::Z * side * 0.9).looking_to(Vec3::Z * side, Vec3::Y),
+                            });
+                        }
+                    }
+                    x += 1.5;
+                }
+                z += 3.4;
+            }
+        }
+        RoomKind::Storage => {
+            let mut x = room.min.x + 1.0;
+            while x < room.max.x - 0.9 {
+                let shelf = Vec3::new(x, y, outer_z - inward * inset);
+                layout.props.push(Prop {
+                    kind: PropKind::Bookcase,
+                    transform: Transform::from_translation(shelf).looking_to(Vec3::Z * inward, Vec3::Y),
+                });
+                x += 1.7;
+            }
+        }
+        RoomKind::Stairwell => {}
+    }
+}
+
+fn build_waypoints(layout: &mut SchoolLayout, b: &Bounds) {
+    let mut points = Vec::new();
+    for floor in 0..FLOOR_COUNT {
+        let y = floor_y(floor);
+        let mut x = b.min_x + 2.0;
+        while x <= b.max_x - 2.0 {
+            points.push(Waypoint { position: Vec3::new(x, y, SCHOOL_ORIGIN.z), kind: WaypointKind::Corridor, floor });
+            x += 6.0;
+        }
+    }
+
+    for room in &layout.rooms {
+        let inward = if room.door.z == b.corridor_n { Vec3::Z } else { -Vec3::Z };
+        points.push(Waypoint { position: room.door - inward * 1.0, kind: WaypointKind::Doorway, floor: room.floor });
+        points.push(Waypoint { position: room.door + inward * 1.0, kind: WaypointKind::Doorway, floor: room.floor });
+        points.push(Waypoint { position: room.center(), kind: WaypointKind::Room(room.kind), floor: room.floor });
+    }
+
+    for stair in &layout.stairs {
+        let floor = ((stair.bottom.y - SLAB_THICKNESS) / FLOOR_HEIGHT).round() as u32;
+        points.push(Waypoint { position: stair.bottom, kind: WaypointKind::Stairs, floor });
+        points.push(Waypoint { position: stair.top, kind: WaypointKind::Stairs, floor: floor + 1 });
+    }
+
+    let roof = floor_y(FLOOR_COUNT);
+    for (x, z) in [(b.min_x + 6.0, b.min_z + 3.0), (SCHOOL_ORIGIN.x, SCHOOL_ORIGIN.z), (b.max_x - 6.0, b.min_z + 3.0), (b.max_x - 6.0, b.max_z - 3.0)] {
+        points.push(Waypoint { position: Vec3::new(x, roof, z), kind: WaypointKind::Rooftop, floor: FLOOR_COUNT });
+    }
+
+    layout.waypoints = points;
+}
+
+/// Spawn the generated school: walls, slabs and stairs as fixed colliders,
+/// furniture from the Kenney kits (box fallback when a model is missing).
+pub fn spawn_school(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    mats: &mut ResMut<Assets<StandardMaterial>>,
+    handles: &Handles,
+    layout: &SchoolLayout,
+) {
+    let wall_mat = mats.add(StandardMaterial::from(Color::srgb(0.88, 0.88, 0.9)));
+    let floor_mat = mats.add(StandardMaterial::from(Color::srgb(0.9, 0.9, 0.84)));
+    let stair_mat = mats.add(StandardMaterial::from(Color::srgb(0.55, 0.52, 0.5)));
+    let prop_mat = mats.add(StandardMaterial::from(Color::srgb(0.45, 0.32, 0.22)));
+    let locker_mat = mats.add(StandardMaterial::from(Color::srgb(0.3, 0.42, 0.55)));
+
+    for (block, mat) in layout.walls.iter().map(|w| (w, &wall_mat)).chain(layout.slabs.iter().map(|s| (s, &floor_mat))) {
+        let he = block.half_extents;
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Cuboid::new(he.x * 2.0, he.y * 2.0, he.z * 2.0)),
+                material: mat.clone(),
+                transform: Transform::from_translation(block.center),
+                ..default()
+            },
+            Collider::cuboid(he.x, he.y, he.z),
+            RigidBody::Fixed,
+            MissionEntity,
+        ));
+    }
+
+    for stair in &layout.stairs {
+        let run = stair.top - stair.bottom;
+        let length = run.length();
+        let thickness = 0.3;
+        let rotation = Quat::from_rotation_arc(Vec3::Z, run.normalize());
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Cuboid::new(stair.width, thickness, length)),
+                material: stair_mat.clone(),
+                transform: Transform::from_translation((stair.bottom + stair.top) * 0.5 - Vec3::Y * thickness * 0.5)
+                    .with_rotation(rotation),
+                ..default()
+            },
+            Collider::cuboid(stair.width * 0.5, thickness * 0.5, length * 0.5),
+            RigidBody::Fixed,
+            // stairs are baked as links between floors, not as obstacles
+            NavIgnore,
+            MissionEntity,
+        ));
+    }
+
+    let hiding_index = |pos: Vec3| layout.hiding_spots.iter().position(|h| h.position.distance_squared(pos) < 0.01);
+
+    for prop in &layout.props {
+        let size = prop.kind.size();
+        let scene = match prop.kind {
+            PropKind::Desk | PropKind::TeacherDesk => handles.furniture_desk.clone(),
+            PropKind::Chair => handles.furniture_chair.clone(),
+            PropKind::Bookcase => handles.furniture_bookcase.clone(),
+            PropKind::Pew => handles.furniture_bench.clone(),
+            PropKind::Door => handles.building_door.clone(),
+            PropKind::Bush => handles.nature_bush.clone(),
+            PropKind::Locker => None,
+        };
+
+        let mut entity = commands.spawn((SpatialBundle::from_transform(prop.transform), MissionEntity));
+        if prop.kind == PropKind::Door {
+            entity.insert(SchoolDoor);
+        }
+        if let Some(index) = hiding_index(prop.transform.translation) {
+            let spot = layout.hiding_spots[index];
+            entity.insert((HidingSpotAnchor { kind: spot.kind, index }, HidingSpot::new(spot.kind, spot.facing)));
+        }
+        entity.with_children(|parent| {
+            if let Some(scene) = scene {
+                let model_scale = prop.kind.model_scale();
+                parent.spawn(SceneBundle {
+                    scene,
+                    transform: Transform::from_translation(-prop.kind.model_center() * model_scale).with_scale(model_scale),
+                    ..default()
+                });
+            } else {
+                parent.spawn(PbrBundle {
+                    mesh: meshes.add(Cuboid::new(size.x, size.y, size.z)),
+                    material: if prop.kind == PropKind::Locker { locker_mat.clone() } else { prop_mat.clone() },
+                    transform: Transform::from_translation(Vec3::Y * size.y * 0.5),
+                    ..default()
+                });
+            }
+            parent.spawn((
+                TransformBundle::from_transform(Transform::from_translation(Vec3::Y * size.y * 0.5)),
+                Collider::cuboid(size.x * 0.5, size.y * 0.5, size.z * 0.5),
+                RigidBody::Fixed,
+            ));
+        });
+    }
+
+    info!(
+        "School generated (seed {}): {} rooms, {} props, {} waypoints, {} hiding spots",
+        layout.seed,
+        layout.rooms.len(),
+        layout.props.len(),
+        layout.waypoints.len(),
+        layout.hiding_spots.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A slab whose top surface holds `p`
+    fn supported(layout: &SchoolLayout, p: Vec3) -> bool {
+        layout.slabs.iter().any(|s| {
+            let top = s.center.y + s.half_extents.y;
+            (top - p.y).abs() < 0.01
+                && (p.x - s.center.x).abs() <= s.half_extents.x + 0.01
+                && (p.z - s.center.z).abs() <= s.half_extents.z + 0.01
+        })
+    }
+
+    #[test]
+    fn stairs_climb_from_the_ground_to_the_roof() {
+        let layout = generate_school_layout(7);
+        for stair in &layout.stairs {
+            assert!(supported(&layout, stair.bottom), "flight starts in mid-air at {:?}", stair.bottom);
+            assert!(supported(&layout, stair.top), "flight lands in mid-air at {:?}", stair.top);
+            // the slab it climbs to is open above the ramp
+            for t in [0.1, 0.3, 0.5, 0.7, 0.9] {
+                let p = stair.bottom.lerp(stair.top, t);
+                assert!(!supported(&layout, Vec3::new(p.x, stair.top.y, p.z)), "flight runs into a slab at {p:?}");
+            }
+        }
+        for floor in 1..=FLOOR_COUNT {
+            let flight = |s: &&Stair| (s.bottom.y - floor_y(floor - 1)).abs() < 0.01 && (s.top.y - floor_y(floor)).abs() < 0.01;
+            assert!(layout.stairs.iter().any(|s| flight(&s)), "no flight up to floor {floor}");
+        }
+    }
+
+    #[test]
+    fn bushes_stay_off_the_roads() {
+        let half = PropKind::Bush.size() * 0.5;
+        for seed in 0..32 {
+            let layout = generate_school_layout(seed);
+            let bushes: Vec<Vec3> = layout.props.iter().filter(|p| p.kind == PropKind::Bush).map(|p| p.transform.translation).collect();
+            assert!(!bushes.is_empty(), "seed {seed}: no bushes at all");
+            for pos in bushes {
+                // whichever way it faces, the footprint fits in its larger half extent
+                let r = half.x.max(half.z);
+                for c in [Vec3::new(-r, 0.0, -r), Vec3::new(-r, 0.0, r), Vec3::new(r, 0.0, -r), Vec3::new(r, 0.0, r)] {
+                    assert!(!on_road(pos + c), "seed {seed}: bush at {pos:?} sits on a road");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn locker_banks_do_not_overlap() {
+        let width = PropKind::Locker.size().x;
+        for seed in 0..32 {
+            let layout = generate_school_layout(seed);
+            let lockers: Vec<Vec3> = layout.props.iter().filter(|p| p.kind == PropKind::Locker).map(|p| p.transform.translation).collect();
+            for (i, a) in lockers.iter().enumerate() {
+                for b in &lockers[i + 1..] {
+                    let same_wall = (a.y - b.y).abs() < 0.01 && (a.z - b.z).abs() < 0.01;
+                    assert!(!same_wall || (a.x - b.x).abs() >= width - 0.01, "seed {seed}: lockers at {a:?} and {b:?} overlap");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+
+use crate::systems::settings::Settings;
+
+/// Single seed every world-gen and simulation RNG stream is derived from.
+/// Resolved at startup from `--seed <n>`, then `Settings::world_seed`, then entropy.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldSeed(pub u64);
+
+impl WorldSeed {
+    /// Derive an independent sub-seed for a named stream ("school", "npcs", ...)
+    pub fn derive(&self, stream: &str) -> u64 {
+        // FNV-1a over the stream name, mixed with the world seed (splitmix64 finaliser)
+        let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+        for b in stream.bytes() {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x0100_0000_01b3);
+        }
+        let mut z = h ^ self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn stream(&self, stream: &str) -> StdRng {
+        StdRng::seed_from_u64(self.derive(stream))
+    }
+}
+
+/// RNG used by NPC decision making at runtime
+#[derive(Resource)]
+pub struct NpcRng(pub StdRng);
+
+/// RNG used by the car spawner and traffic AI
+#[derive(Resource)]
+pub struct TrafficRng(pub StdRng);
+
+/// RNG behind when and how Ethan hallucinates
+#[derive(Resource)]
+pub struct HallucinationRng(pub StdRng);
+
+/// RNG for what food and finds turn up in the world
+#[derive(Resource)]
+pub struct LootRng(pub StdRng);
+
+pub struct WorldSeedPlugin;
+
+impl Plugin for WorldSeedPlugin {
+    fn build(&self, app: &mut App) {
+        let from_settings = app.world().get_resource::<Settings>().and_then(|s| s.world_seed);
+        let seed = match seed_from_args().or(from_settings) {
+            Some(s) => WorldSeed(s),
+            None => WorldSeed(rand::thread_rng().gen()),
+        };
+        info!("World seed: {} (pass --seed {} to reproduce this world)", seed.0, seed.0);
+
+        app.insert_resource(seed)
+           .insert_resource(NpcRng(seed.stream("npc_ai")))
+           .insert_resource(TrafficRng(seed.stream("traffic")))
+           .insert_resource(HallucinationRng(seed.stream("hallucinations")))
+           .insert_resource(LootRng(seed.stream("loot")));
+    }
+}
+
+/// Accepts `--seed 1234` or `--seed=1234`
+fn seed_from_args() -> Option<u64> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if let Some(value) = arg.strip_prefix("--seed=") {
+            return value.parse().ok();
+        }
+        if arg == "--seed" {
+            return args.next().and_then(|v| v.parse().ok());
+        }
+    }
+    None
+}
@@ -0,0 +1,317 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::collections::HashMap;
+
+use crate::core::food::Eating;
+use crate::core::hiding::Hidden;
+use crate::core::perception::{CarriedLight, NoiseEvent, NoiseSource};
+use crate::core::player::Ethan;
+use crate::core::school;
+use crate::core::seed::WorldSeed;
+use crate::core::world::{on_road, MissionEntity};
+use crate::data::items::{ItemDb, ItemUse};
+use crate::states::GameState;
+use crate::systems::controls::Controls;
+use crate::systems::inventory::{Inventory, ItemPickup};
+use crate::systems::inventory_ui::InventoryScreen;
+
+/// Ground cells are this many metres across
+const CELL_SIZE: f32 = 1.0;
+/// How far ahead of Ethan a tool reaches
+const TOOL_REACH: f32 = 1.0;
+/// Seconds between swings of the shovel or hoe
+const TOOL_COOLDOWN: f32 = 0.6;
+/// How far digging and tilling carry, in metres
+const TOOL_NOISE: f32 = 4.0;
+/// Above this Ethan is off the ground (school floors, roofs)
+const GROUND_MAX_Y: f32 = 0.5;
+/// A lit torch loses one durability every this many seconds
+const TORCH_BURN_SECS: f32 = 3.0;
+const TORCH_INTENSITY: f32 = 1200.0;
+const TORCH_RANGE: f32 = 18.0;
+/// What the shovel yields
+pub const DIRT_ITEM: &str = "utility_dirt";
+/// Chance an undug cell has something buried in it
+const BURIED_CHANCE: f64 = 0.12;
+/// What turns up buried, with weights
+pub const BURIED_LOOT: &[(&str, u32)] = &[("prank_paperplane", 3), ("food_leftovers", 2), ("prank_stinkbomb", 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroundCell {
+    /// Turned over by the hoe
+    Tilled,
+    /// Dug out by the shovel; nothing more comes of it
+    Dug,
+}
+
+/// Ground Ethan has worked, by cell. Cells not listed are untouched grass.
+#[derive(Resource, Debug, Default)]
+pub struct GroundCells {
+    pub cells: HashMap<IVec2, GroundCell>,
+    patches: HashMap<IVec2, Entity>,
+}
+
+/// Whether the torch is switched on; it only shines while equipped
+#[derive(Resource, Debug, Default)]
+pub struct TorchState {
+    pub on: bool,
+    burn: f32,
+}
+
+#[derive(Component)]
+struct TorchBeam;
+
+#[derive(Resource, Debug, Default)]
+struct ToolCooldown(f32);
+
+pub struct ToolsPlugin;
+
+impl Plugin for ToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GroundCells>()
+           .init_resource::<TorchState>()
+           .init_resource::<ToolCooldown>()
+           .add_systems(OnEnter(GameState::MissionComplete), clear_ground_cells)
+           .add_systems(OnEnter(GameState::GameOver), clear_ground_cells)
+           .add_systems(OnEnter(GameState::Title), clear_ground_cells)
+           .add_systems(Update, (spawn_torch_beam, torch_system, ground_tool_system)
+               .chain()
+               .run_if(in_state(GameState::Mission1).or_else(in_state(GameState::OpenWorld))));
+    }
+}
+
+/// The ground cell under `pos`
+pub fn cell_at(pos: Vec3) -> IVec2 {
+    IVec2::new((pos.x / CELL_SIZE).floor() as i32, (pos.z / CELL_SIZE).floor() as i32)
+}
+
+fn cell_centre(cell: IVec2) -> Vec3 {
+    Vec3::new((cell.x as f32 + 0.5) * CELL_SIZE, 0.0, (cell.y as f32 + 0.5) * CELL_SIZE)
+}
+
+/// Open grass: not road, not under the school
+pub fn is_grass(cell: IVec2) -> bool {
+    let pos = cell_centre(cell);
+    !school::in_footprint(pos, 0.0) && !on_road(pos)
+}
+
+/// What is buried in `cell`, the same every time for a given world seed
+pub fn buried_item(seed: &WorldSeed, cell: IVec2) -> Option<&'static str> {
+    let mut rng = seed.stream(&format!("buried:{}:{}", cell.x, cell.y));
+    if !rng.gen_bool(BURIED_CHANCE) {
+        return None;
+    }
+    BURIED_LOOT.choose_weighted(&mut rng, |(_, w)| *w).ok().map(|(id, _)| *id)
+}
+
+/// Wear the equipped tool by one; true if that broke it, emptying its slot
+fn wear_equipped(inv: &mut Inventory) -> bool {
+    let Some(durability) = inv.equipped_mut().and_then(|s| s.durability.as_mut()) else { return false };
+    *durability -= 1;
+    if *durability > 0 {
+        return false;
+    }
+    let slot = inv.hotbar;
+    inv.remove_slot(slot);
+    true
+}
+
+fn equipped_use(inv: &Inventory, items: &ItemDb, wanted: ItemUse) -> bool {
+    inv.equipped_id().and_then(|id| items.get(id)).is_some_and(|d| d.has_use(wanted))
+}
+
+/// Ethan's torch beam, switched off until the torch is
+fn spawn_torch_beam(mut commands: Commands, player_q: Query<Entity, Added<Ethan>>) {
+    for player in &player_q {
+        commands.entity(player).with_children(|p| {
+            p.spawn((
+                SpotLightBundle {
+                    spot_light: SpotLight {
+                        intensity: 0.0,
+                        range: TORCH_RANGE,
+                        inner_angle: 0.3,
+                        outer_angle: 0.55,
+                        color: Color::srgb(1.0, 0.85, 0.6),
+                        shadows_enabled: false,
+                        ..default()
+                    },
+                    // model front is +Z; aim slightly down at the path ahead
+                    transform: Transform::from_xyz(0.0, 1.4, 0.3).looking_to(Vec3::new(0.0, -0.25, 1.0), Vec3::Y),
+                    ..default()
+                },
+                TorchBeam,
+            ));
+        });
+    }
+}
+
+/// The use key switches an equipped torch on and off. It burns durability
+/// while on and goes out for good when that runs out.
+fn torch_system(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    screen: Res<InventoryScreen>,
+    items: Res<ItemDb>,
+    mut inv: ResMut<Inventory>,
+    mut torch: ResMut<TorchState>,
+    mut player_q: Query<&mut CarriedLight, With<Ethan>>,
+    mut beams: Query<&mut SpotLight, With<TorchBeam>>,
+) {
+    if !equipped_use(&inv, &items, ItemUse::Light) {
+        torch.on = false;
+    } else if keyboard.just_pressed(controls.use_tool) && !screen.open {
+        torch.on = !torch.on;
+        torch.burn = 0.0;
+    }
+
+    if torch.on {
+        torch.burn += time.delta_seconds();
+        if torch.burn >= TORCH_BURN_SECS {
+            torch.burn -= TORCH_BURN_SECS;
+            if wear_equipped(&mut inv) {
+                torch.on = false;
+                info!("The torch burned out.");
+            }
+        }
+    }
+
+    for mut light in &mut player_q {
+        if light.lit != torch.on {
+            light.lit = torch.on;
+        }
+    }
+    let intensity = if torch.on { TORCH_INTENSITY } else { 0.0 };
+    for mut beam in &mut beams {
+        if beam.intensity != intensity {
+            beam.intensity = intensity;
+        }
+    }
+}
+
+/// The use key digs with an equipped shovel or tills with an equipped hoe,
+/// on the grass cell just ahead of Ethan
+fn ground_tool_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    screen: Res<InventoryScreen>,
+    items: Res<ItemDb>,
+    seed: Res<WorldSeed>,
+    mut inv: ResMut<Inventory>,
+    mut ground: ResMut<GroundCells>,
+    mut cooldown: ResMut<ToolCooldown>,
+    mut noises: EventWriter<NoiseEvent>,
+    player_q: Query<&Transform, (With<Ethan>, Without<Hidden>, Without<Eating>)>,
+) {
+    cooldown.0 = (cooldown.0 - time.delta_seconds()).max(0.0);
+    if !keyboard.just_pressed(controls.use_tool) || screen.open || cooldown.0 > 0.0 {
+        return;
+    }
+    let Some(tool) = inv.equipped_id().and_then(|id| items.get(id)) else { return };
+    let dig = tool.has_use(ItemUse::Dig);
+    if !dig && !tool.has_use(ItemUse::Till) {
+        return;
+    }
+    let tool_name = tool.name.clone();
+    let Ok(tf) = player_q.get_single() else { return };
+    // model front is +Z, i.e. the transform's back
+    let facing = Vec3::new(tf.back().x, 0.0, tf.back().z).normalize_or_zero();
+    let cell = cell_at(tf.translation + facing * TOOL_REACH);
+    if tf.translation.y > GROUND_MAX_Y || !is_grass(cell) {
+        info!("The ground here can't be worked.");
+        return;
+    }
+
+    let state = ground.cells.get(&cell).copied();
+    let result = if dig {
+        if state == Some(GroundCell::Dug) {
+            info!("Nothing more to dig here.");
+            return;
+        }
+        let Some(dirt) = items.get(DIRT_ITEM) else { return };
+        if !inv.add(dirt) {
+            info!("No room for the dirt.");
+            return;
+        }
+        if let Some(id) = buried_item(&seed, cell) {
+            info!("Ethan dug something up.");
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cuboid::from_length(0.3)),
+                    material: mats.add(StandardMaterial::from(Color::srgb(0.6, 0.55, 0.5))),
+                    transform: Transform::from_translation(cell_centre(cell) + Vec3::Y * 0.15),
+                    ..default()
+                },
+                ItemPickup::new(id),
+                MissionEntity,
+            ));
+        }
+        GroundCell::Dug
+    } else {
+        if state.is_some() {
+            info!("This ground is already turned over.");
+            return;
+        }
+        GroundCell::Tilled
+    };
+
+    let color = match result {
+        GroundCell::Tilled => Color::srgb(0.36, 0.25, 0.14),
+        GroundCell::Dug => Color::srgb(0.22, 0.15, 0.08),
+    };
+    if let Some(old) = ground.patches.remove(&cell) {
+        commands.entity(old).despawn_recursive();
+    }
+    let patch = commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cuboid::new(CELL_SIZE, 0.02, CELL_SIZE)),
+            material: mats.add(StandardMaterial::from(color)),
+            transform: Transform::from_translation(cell_centre(cell) + Vec3::Y * 0.01),
+            ..default()
+        },
+        MissionEntity,
+    )).id();
+    ground.patches.insert(cell, patch);
+    ground.cells.insert(cell, result);
+
+    cooldown.0 = TOOL_COOLDOWN;
+    noises.send(NoiseEvent { position: tf.translation, loudness: TOOL_NOISE, source: NoiseSource::Item });
+    if wear_equipped(&mut inv) {
+        info!("The {} broke.", tool_name);
+    }
+}
+
+/// Worked ground and the torch switch last until the mission really ends
+fn clear_ground_cells(mut commands: Commands, mut ground: ResMut<GroundCells>, mut torch: ResMut<TorchState>) {
+    for (_, patch) in ground.patches.drain() {
+        commands.entity(patch).despawn_recursive();
+    }
+    ground.cells.clear();
+    *torch = TorchState::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roads_and_school_are_not_grass() {
+        assert!(!is_grass(cell_at(Vec3::new(0.5, 0.0, 100.5))));
+        assert!(!is_grass(cell_at(school::SCHOOL_ORIGIN)));
+        assert!(is_grass(cell_at(Vec3::new(20.5, 0.0, -20.5))));
+    }
+
+    #[test]
+    fn buried_items_are_fixed_by_seed() {
+        let seed = WorldSeed(7);
+        let cells: Vec<_> = (0..200).map(|x| IVec2::new(x, 3)).collect();
+        let first: Vec<_> = cells.iter().map(|c| buried_item(&seed, *c)).collect();
+        assert_eq!(first, cells.iter().map(|c| buried_item(&seed, *c)).collect::<Vec<_>>());
+        assert!(first.iter().any(Option::is_some));
+        assert!(first.iter().any(Option::is_none));
+    }
+}
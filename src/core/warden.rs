@@ -1,90 +1,440 @@
+use bevy::color::palettes::css;
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
 use crate::states::GameState;
+use crate::core::navigation::{NavAgent, NavGrid};
+use crate::core::animation::{Animated, ClipTarget, PlayClip};
+use crate::core::clock::{HourChanged, WorldClock};
+use crate::core::damage::{DamageEvent, DamageSource};
+use crate::core::hiding::{Hidden, HidingSpot};
+use crate::core::perception::Perception;
+use crate::core::reactions::{Notoriety, PlayerReported};
+use crate::core::seed::WorldSeed;
+use crate::data::patrol::{load_patrol_route, PatrolRoute, PATROL_ROUTES_PATH};
+
+/// The Warden's model, one of `CHARACTER_MODELS`; nobody else wears it
+pub const WARDEN_MODEL: &str = "Punk.glb";
+const PATROL_SPEED: f32 = 2.0;
+const INVESTIGATE_SPEED: f32 = 2.6;
+const SEARCH_SPEED: f32 = 2.2;
+const CHASE_START_SPEED: f32 = 3.0;
+const CHASE_MAX_SPEED: f32 = 5.5;
+/// Seconds to go from chase start speed to max speed
+const CHASE_RAMP_SECS: f32 = 3.0;
+/// Suspicion needed to go and look at the last known position
+const INVESTIGATE_THRESHOLD: f32 = 0.25;
+/// Suspicion needed (while seeing the player) to give chase
+const CHASE_THRESHOLD: f32 = 0.6;
+const CATCH_DISTANCE: f32 = 1.2;
+/// The blow that ends a chase; hurts, but never kills
+const CATCH_DAMAGE: f32 = 20.0;
+/// Chase turns into an investigation after this long without sight
+const LOSE_SIGHT_SECS: f32 = 4.0;
+const SEARCH_SECS: f32 = 12.0;
+const SEARCH_RADIUS: f32 = 6.0;
+/// Chance a search leg goes to a hiding spot rather than a random point
+const CHECK_SPOT_CHANCE: f64 = 0.6;
+/// Distance in front of a hiding spot the Warden stands to open / look into it
+const CHECK_SPOT_REACH: f32 = 1.0;
+/// How long he spends rummaging in a hiding spot
+const CHECK_SPOT_SECS: f32 = 1.5;
+/// The Warden walks the upstairs route between these hours
+const NIGHT_SHIFT_START: u32 = 22;
+const NIGHT_SHIFT_END: u32 = 6;
+/// Suspicion a tip-off from a witness gives, enough to go and look
+const REPORT_SUSPICION: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WardenMode {
+    Patrol,
+    /// Walk to where the player was last seen or heard
+    Investigate { target: Vec3 },
+    /// Poke around an area until the timer runs out
+    Search { center: Vec3, remaining: f32 },
+    /// Open a locker / look under a desk / part a bush during a search
+    CheckHidingSpot { spot: Entity, center: Vec3, remaining: f32 },
+    /// Run after the player; `elapsed` drives the speed ramp
+    Chase { elapsed: f32, unseen: f32 },
+    /// Give up and walk back to the patrol route
+    Return,
+}
 
 #[derive(Component)]
 pub struct TheWarden {
-    pub waypoints: Vec<Vec3>,
+    pub route: PatrolRoute,
     pub current: usize,
-    pub vision_range: f32,
-    pub fov_deg: f32,
+    pub mode: WardenMode,
+    /// Time left lingering at the current waypoint or hiding spot
+    pub pause: f32,
+    /// Hiding spots already looked into during the current search
+    pub checked: Vec<Entity>,
+    /// Patrol route level currently walked (see `patrol_routes.ron`)
+    pub shift_route: &'static str,
 }
 
+/// Randomness for search spots, derived from the world seed
+#[derive(Resource)]
+pub struct WardenRng(pub StdRng);
+
+/// Toggle with F3: vision cones, state and paths
+#[derive(Resource, Default)]
+pub struct WardenDebug(pub bool);
+
 pub struct WardenPlugin;
 
 impl Plugin for WardenPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Mission1), spawn_warden)
-           .add_systems(Update, warden_ai_system.run_if(in_state(GameState::Mission1)));
+        app.init_resource::<WardenDebug>()
+           .add_systems(OnEnter(GameState::Mission1), spawn_warden)
+           .add_systems(Update, warden_alertness_system)
+           .add_systems(Update, (warden_shift_system, warden_ai_system, warden_debug_gizmos_system).chain().run_if(in_state(GameState::Mission1)));
     }
 }
 
-fn spawn_warden(mut commands: Commands, handles: Res<crate::data::assets_loader::Handles>) {
-    let waypoints = vec![
-        Vec3::new(-12.0, 0.0, -6.0),
-        Vec3::new(12.0, 0.0, -6.0),
-        Vec3::new(12.0, 0.0, 8.0),
-        Vec3::new(-12.0, 0.0, 8.0),
-    ];
+/// Which patrol route the Warden walks at a given hour
+fn shift_route_for(hour: u32) -> &'static str {
+    if !(NIGHT_SHIFT_END..NIGHT_SHIFT_START).contains(&hour) { "mission1_upstairs" } else { "mission1" }
+}
+
+fn spawn_warden(
+    mut commands: Commands,
+    handles: Res<crate::data::assets_loader::Handles>,
+    seed: Res<WorldSeed>,
+    clock: Res<WorldClock>,
+) {
+    let shift_route = shift_route_for(clock.whole_hour());
+    let route = load_patrol_route(PATROL_ROUTES_PATH, shift_route).unwrap_or_else(|err| {
+        error!("Failed to load Warden patrol route: {:?}", err);
+        PatrolRoute {
+            waypoints: vec![Vec3::new(-10.0, 0.2, 20.0), Vec3::new(90.0, 0.2, 20.0)],
+            pause_secs: 1.0,
+        }
+    });
+    let start = route.waypoints[0];
 
-    // spawn model if available
+    commands.insert_resource(WardenRng(seed.stream("warden")));
     commands.spawn((
-        if handles.punk.is_loaded() {
-            SceneBundle {
-                scene: handles.punk.clone(),
-                transform: Transform::from_translation(waypoints[0]),
-                ..default()
-            }
-        } else {
-            SceneBundle {
-                scene: handles.punk.clone(), // fallback same
-                transform: Transform::from_translation(waypoints[0]),
-                ..default()
-            }
+        SceneBundle {
+            scene: handles.punk.clone(),
+            transform: Transform::from_translation(start),
+            ..default()
         },
         TheWarden {
-            waypoints,
+            route,
             current: 0,
-            vision_range: 12.0,
-            fov_deg: 70.0,
+            mode: WardenMode::Patrol,
+            pause: 0.0,
+            checked: Vec::new(),
+            shift_route,
         },
+        Perception::default(),
+        NavAgent::new(PATROL_SPEED),
+        Animated::new(WARDEN_MODEL).actor("warden"),
         RigidBody::KinematicPositionBased,
         Collider::capsule_y(0.8, 0.25),
     ));
 }
 
+/// Swap patrol routes when the clock crosses a shift boundary. A Warden busy
+/// with the player finishes that first and picks the new route up on return.
+fn warden_shift_system(mut hours: EventReader<HourChanged>, mut query: Query<&mut TheWarden>) {
+    let Some(changed) = hours.read().last() else { return };
+    let wanted = shift_route_for(changed.hour);
+    for mut warden in &mut query {
+        if warden.shift_route == wanted {
+            continue;
+        }
+        match load_patrol_route(PATROL_ROUTES_PATH, wanted) {
+            Ok(route) => {
+                info!("Warden: shift change to route '{}'", wanted);
+                warden.route = route;
+                warden.current = 0;
+                warden.shift_route = wanted;
+                if warden.mode == WardenMode::Patrol {
+                    warden.mode = WardenMode::Return;
+                }
+            }
+            Err(err) => error!("Failed to load Warden shift route: {:?}", err),
+        }
+    }
+}
+
+/// The more the town gossips about Ethan, the sharper the Warden's eyes and
+/// ears. A witness's report sends a Warden on duty to look where Ethan was seen.
+fn warden_alertness_system(
+    notoriety: Res<Notoriety>,
+    mut reports: EventReader<PlayerReported>,
+    mut query: Query<(Ref<TheWarden>, &mut Perception)>,
+) {
+    let base = Perception::default();
+    for (warden, mut perception) in &mut query {
+        if notoriety.is_changed() || warden.is_added() {
+            perception.sight_gain = base.sight_gain * (1.0 + notoriety.0);
+            perception.hearing = base.hearing * (1.0 + 0.5 * notoriety.0);
+        }
+    }
+    for report in reports.read() {
+        for (_, mut perception) in &mut query {
+            perception.last_known = Some(report.seen_at);
+            perception.suspicion = perception.suspicion.max(REPORT_SUSPICION);
+        }
+    }
+}
+
 fn warden_ai_system(
     time: Res<Time>,
-    mut query: Query<(&mut Transform, &mut TheWarden)>,
-    player_q: Query<&Transform, With<crate::core::player::Ethan>>,
+    grid: Res<NavGrid>,
+    mut rng: ResMut<WardenRng>,
+    mut query: Query<(Entity, &Transform, &mut TheWarden, &mut NavAgent, &Perception)>,
+    player_q: Query<(&Transform, Option<&Hidden>), With<crate::core::player::Ethan>>,
+    spots: Query<(Entity, &GlobalTransform, &HidingSpot)>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut clips: EventWriter<PlayClip>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    let Ok((entity, tf, mut warden, mut agent, perception)) = query.get_single_mut() else { return };
+    let Ok((player_tf, hidden)) = player_q.get_single() else { return };
+    let dt = time.delta_seconds();
+    let pos = tf.translation;
+
+    // seeing the player with enough suspicion always wins
+    if perception.sees_player && perception.suspicion >= CHASE_THRESHOLD && !matches!(warden.mode, WardenMode::Chase { .. }) {
+        info!("Warden: chasing");
+        warden.mode = WardenMode::Chase { elapsed: 0.0, unseen: 0.0 };
+    }
+
+    let mode = warden.mode;
+    warden.mode = match mode {
+        WardenMode::Patrol | WardenMode::Return if perception.suspicion >= INVESTIGATE_THRESHOLD || perception.heard.is_some() => {
+            match perception.last_known {
+                Some(target) => {
+                    info!("Warden: investigating {:?}", target);
+                    WardenMode::Investigate { target }
+                }
+                None => mode,
+            }
+        }
+        WardenMode::Patrol => {
+            agent.speed = PATROL_SPEED;
+            if agent.is_idle() {
+                warden.pause -= dt;
+                if warden.pause <= 0.0 {
+                    warden.current = (warden.current + 1) % warden.route.waypoints.len();
+                    agent.set_destination(warden.route.waypoints[warden.current]);
+                    warden.pause = warden.route.pause_secs;
+                }
+            }
+            WardenMode::Patrol
+        }
+        WardenMode::Investigate { target } => {
+            agent.speed = INVESTIGATE_SPEED;
+            // fresh clues pull the investigation along
+            let target = perception.last_known.unwrap_or(target);
+            agent.set_destination(target);
+            if agent.is_idle() || pos.distance(target) < 1.0 {
+                agent.stop();
+                warden.checked.clear();
+                WardenMode::Search { center: target, remaining: SEARCH_SECS }
+            } else {
+                WardenMode::Investigate { target }
+            }
+        }
+        WardenMode::Search { center, remaining } => {
+            agent.speed = SEARCH_SPEED;
+            let remaining = remaining - dt;
+            if remaining <= 0.0 {
+                info!("Warden: giving up the search");
+                WardenMode::Return
+            } else if agent.is_idle() && warden.pause > 0.0 {
+                warden.pause -= dt;
+                WardenMode::Search { center, remaining }
+            } else if agent.is_idle() {
+                // a spot he watched the player climb into comes first
+                let seen_into = hidden.filter(|h| h.observed).map(|h| h.spot).filter(|s| !warden.checked.contains(s));
+                let spot = seen_into.or_else(|| {
+                    if rng.0.gen_bool(CHECK_SPOT_CHANCE) { nearest_unchecked_spot(&spots, &warden.checked, center) } else { None }
+                });
+                match spot.and_then(|s| spots.get(s).ok()) {
+                    Some((spot, gt, info)) => {
+                        warden.checked.push(spot);
+                        agent.set_destination(gt.translation() + info.facing * CHECK_SPOT_REACH);
+                        WardenMode::CheckHidingSpot { spot, center, remaining }
+                    }
+                    None => {
+                        if let Some(point) = pick_search_spot(&grid, &mut rng.0, center) {
+                            agent.set_destination(point);
+                        }
+                        WardenMode::Search { center, remaining }
+                    }
+                }
+            } else {
+                WardenMode::Search { center, remaining }
+            }
+        }
+        WardenMode::CheckHidingSpot { spot, center, remaining } => {
+            agent.speed = SEARCH_SPEED;
+            let remaining = remaining - dt;
+            match spots.get(spot) {
+                Ok((_, gt, info)) if agent.is_idle() || pos.distance(gt.translation() + info.facing * CHECK_SPOT_REACH) < 0.5 => {
+                    agent.stop();
+                    clips.send(PlayClip { target: ClipTarget::Entity(entity), clip: "Interact".into(), secs: CHECK_SPOT_SECS });
+                    if hidden.is_some_and(|h| h.spot == spot) {
+                        info!("Warden: found the player hiding in a {:?}", info.kind);
+                        next_state.set(GameState::Caught);
+                        return;
+                    }
+                    warden.pause = CHECK_SPOT_SECS;
+                    WardenMode::Search { center, remaining }
+                }
+                Ok(_) if remaining > 0.0 => WardenMode::CheckHidingSpot { spot, center, remaining },
+                _ => WardenMode::Search { center, remaining },
+            }
+        }
+        WardenMode::Chase { elapsed, unseen } => {
+            let elapsed = elapsed + dt;
+            let ramp = (elapsed / CHASE_RAMP_SECS).min(1.0);
+            agent.speed = CHASE_START_SPEED + (CHASE_MAX_SPEED - CHASE_START_SPEED) * ramp;
+
+            if pos.distance(player_tf.translation) <= CATCH_DISTANCE {
+                agent.stop();
+                clips.send(PlayClip { target: ClipTarget::Entity(entity), clip: "Punch_Right".into(), secs: 1.0 });
+                damage.send(DamageEvent { source: DamageSource::Warden, amount: CATCH_DAMAGE });
+                next_state.set(GameState::Caught);
+                return;
+            }
+
+            let unseen = if perception.sees_player { 0.0 } else { unseen + dt };
+            if unseen >= LOSE_SIGHT_SECS {
+                info!("Warden: lost the player");
+                let target = perception.last_known.unwrap_or(player_tf.translation);
+                WardenMode::Investigate { target }
+            } else {
+                // keep heading for the player while in sight, else the last place seen
+                let target = if perception.sees_player { player_tf.translation } else { perception.last_known.unwrap_or(player_tf.translation) };
+                agent.set_destination(target);
+                WardenMode::Chase { elapsed, unseen }
+            }
+        }
+        WardenMode::Return => {
+            agent.speed = PATROL_SPEED;
+            let (nearest, point) = warden
+                .route
+                .waypoints
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1.distance_squared(pos).total_cmp(&b.1.distance_squared(pos)))
+                .map(|(i, p)| (i, *p))
+                .unwrap_or((0, pos));
+            agent.set_destination(point);
+            if pos.distance(point) < 1.0 {
+                warden.current = nearest;
+                warden.pause = 0.0;
+                agent.stop();
+                WardenMode::Patrol
+            } else {
+                WardenMode::Return
+            }
+        }
+    };
+}
+
+/// Closest hiding spot to `center` within the search radius not yet looked into
+fn nearest_unchecked_spot(spots: &Query<(Entity, &GlobalTransform, &HidingSpot)>, checked: &[Entity], center: Vec3) -> Option<Entity> {
+    spots
+        .iter()
+        .filter(|(e, gt, _)| !checked.contains(e) && gt.translation().distance(center) <= SEARCH_RADIUS)
+        .min_by(|a, b| a.1.translation().distance_squared(center).total_cmp(&b.1.translation().distance_squared(center)))
+        .map(|(e, _, _)| e)
+}
+
+/// Random walkable point near `center` to check while searching
+fn pick_search_spot(grid: &NavGrid, rng: &mut StdRng, center: Vec3) -> Option<Vec3> {
+    (0..8).map(|_| {
+        let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+        let r = rng.gen::<f32>().sqrt() * SEARCH_RADIUS;
+        center + Vec3::new(angle.cos() * r, 0.0, angle.sin() * r)
+    }).find(|p| grid.is_walkable(*p))
+}
+
+fn warden_debug_gizmos_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut debug: ResMut<WardenDebug>,
+    mut gizmos: Gizmos,
+    query: Query<(&Transform, &TheWarden, &NavAgent, &Perception)>,
 ) {
-    let Ok((mut tf, mut warden)) = query.get_single_mut() else { return };
-    let Ok(player_tf) = player_q.get_single() else { return };
-
-    let to_player = player_tf.translation - tf.translation;
-    let dist = to_player.length();
-    if dist <= warden.vision_range {
-        // simple direct spot
-        let dir = to_player.normalize();
-        let forward = tf.forward();
-        let angle = forward.dot(dir).clamp(-1.0,1.0).acos().to_degrees();
-        if angle <= warden.fov_deg * 0.5 {
-            next_state.set(GameState::Caught);
-            return;
+    if keyboard.just_pressed(KeyCode::F3) {
+        debug.0 = !debug.0;
+    }
+    if !debug.0 {
+        return;
+    }
+
+    for (tf, warden, agent, perception) in &query {
+        let color = match warden.mode {
+            WardenMode::Patrol => Color::from(css::GREEN),
+            WardenMode::Investigate { .. } => Color::from(css::YELLOW),
+            WardenMode::Search { .. } | WardenMode::CheckHidingSpot { .. } => Color::from(css::ORANGE),
+            WardenMode::Chase { .. } => Color::from(css::RED),
+            WardenMode::Return => Color::from(css::AQUA),
+        };
+        let eye = tf.translation + Vec3::Y * 1.6;
+
+        // vision cone: two edges and an arc
+        let half = (perception.fov_deg * 0.5).to_radians();
+        // the Warden's model faces +Z
+        let forward = *tf.back();
+        let steps = 12;
+        let mut prev = None;
+        for i in 0..=steps {
+            let a = -half + (2.0 * half) * i as f32 / steps as f32;
+            let edge = eye + Quat::from_rotation_y(a).mul_vec3(forward) * perception.vision_range;
+            if i == 0 || i == steps {
+                gizmos.line(eye, edge, color);
+            }
+            if let Some(p) = prev {
+                gizmos.line(p, edge, color);
+            }
+            prev = Some(edge);
+        }
+
+        // suspicion bar above the head
+        let head = tf.translation + Vec3::Y * 2.3;
+        gizmos.line(head - Vec3::X * 0.5, head + Vec3::X * 0.5, Color::from(css::DARK_GRAY));
+        gizmos.line(head - Vec3::X * 0.5, head - Vec3::X * 0.5 + Vec3::X * perception.suspicion, color);
+
+        if let Some(known) = perception.last_known {
+            gizmos.sphere(known, Quat::IDENTITY, 0.3, Color::from(css::FUCHSIA));
+        }
+        let mut from = tf.translation;
+        for p in agent.path() {
+            gizmos.line(from, *p, Color::WHITE);
+            from = *p;
+        }
+        for (i, w) in warden.route.waypoints.iter().enumerate() {
+            let c = if i == warden.current { Color::from(css::GREEN) } else { Color::from(css::GRAY) };
+            gizmos.circle(*w, Dir3::Y, 0.4, c);
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::patrol::PatrolRouteDef;
+    use std::collections::HashMap;
 
-    // patrol
-    let target = warden.waypoints[warden.current];
-    let mut dir_to = target - tf.translation;
-    dir_to.y = 0.0;
-    let d = dir_to.length();
-    if d > 0.3 {
-        let speed = 2.0;
-        tf.translation += dir_to.normalize() * speed * time.delta_seconds();
-        tf.look_at(target, Vec3::Y);
-    } else {
-        warden.current = (warden.current + 1) % warden.waypoints.len();
+    #[test]
+    fn every_patrol_route_is_walked_on_some_shift() {
+        let text = std::fs::read_to_string(PATROL_ROUTES_PATH).unwrap();
+        let routes: HashMap<String, PatrolRouteDef> = ron::from_str(&text).unwrap();
+        let walked: Vec<&str> = (0..24).map(shift_route_for).collect();
+        for name in routes.keys() {
+            assert!(walked.contains(&name.as_str()), "patrol route '{name}' is never walked");
+        }
+        for name in walked {
+            assert!(load_patrol_route(PATROL_ROUTES_PATH, name).is_ok(), "shift route '{name}' doesn't load");
+        }
     }
 }
@@ -1,28 +1,65 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
+use rand::Rng;
+use std::collections::HashSet;
 
 use crate::data::assets_loader::Handles;
 use crate::core::npc_ai::NpcCategory;
-use crate::config::{CAR_SPAWN_RADIUS, SIMULATION_RADIUS};
 use crate::core::npc_ai::Npc; // component
 use crate::core::npc_ai::NpcState;
+use crate::core::npc_ai::NpcRoutine;
+use crate::core::npc_ai::NpcId;
+use crate::core::npc_ai::NPC_WALK_SPEED;
+use crate::core::lod::SimLod;
+use crate::core::animation::Animated;
+use crate::core::appearance::{Appearance, NpcName};
+use crate::data::archetypes::{load_archetypes, AppearanceChoice, Archetypes, ARCHETYPES_PATH, CLUSTER_RADIUS};
+use crate::data::voices::{load_voices, Voices, VOICES_PATH};
+use crate::core::school::{self, generate_school_layout, spawn_school};
+use crate::core::seed::WorldSeed;
+use crate::core::navigation::NavAgent;
+use crate::data::items::DEFAULT_FOOD;
+use crate::systems::inventory::ItemPickup;
 
 #[derive(Component)]
 pub struct MissionEntity;
 
+/// Roads run every `ROAD_SPACING` metres along both axes, `ROAD_COUNT` each side of the origin
+pub const ROAD_SPACING: f32 = 40.0;
+pub const ROAD_WIDTH: f32 = 8.0;
+/// Pavement distance from a road's centre line
+pub const PAVEMENT_OFFSET: f32 = ROAD_WIDTH * 0.5 + 1.0;
+const ROAD_COUNT: i32 = 5;
+
+/// Lamp post light, switched on at night by the world clock
+#[derive(Component)]
+pub struct StreetLight;
+
 /// Resource listing important spawn points for cars and NPCs
 #[derive(Resource, Default)]
 pub struct WorldSpawnInfo {
     pub car_spawn_points: Vec<Vec3>,
     pub npc_spawn_points: Vec<Vec3>,
     pub market_spots: Vec<Vec3>,
+    /// Where food turns up by the stalls; restocked over time
+    pub food_spots: Vec<Vec3>,
 }
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
+        let voices = load_voices(VOICES_PATH).unwrap_or_else(|err| {
+            error!("Failed to load voices, NPCs talk in silence: {:?}", err);
+            Voices::default()
+        });
+        let archetypes = load_archetypes(ARCHETYPES_PATH, &voices).unwrap_or_else(|err| {
+            error!("Failed to load character archetypes, NPCs get stock models: {:?}", err);
+            Archetypes::default()
+        });
         app.insert_resource(WorldSpawnInfo::default())
+           .insert_resource(archetypes)
+           .insert_resource(voices)
            .add_systems(Startup, setup_world);
     }
 }
@@ -33,14 +70,16 @@ fn setup_world(
     mut mats: ResMut<Assets<StandardMaterial>>,
     mut spawn_info: ResMut<WorldSpawnInfo>,
     handles: Res<Handles>,
+    seed: Res<WorldSeed>,
+    archetypes: Res<Archetypes>,
 ) {
-    info!("Setting up the open world...");
+    info!("Setting up the open world (seed {})...", seed.0);
 
     // GROUND
-    let ground_material = mats.add(Color::rgb(0.12, 0.45, 0.12).into());
+    let ground_material = mats.add(StandardMaterial::from(Color::srgb(0.12, 0.45, 0.12)));
     commands.spawn((
         PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Plane { size: 1600.0 })),
+            mesh: meshes.add(Plane3d::default().mesh().size(1600.0, 1600.0)),
             material: ground_material,
             ..default()
         },
@@ -49,8 +88,10 @@ fn setup_world(
         MissionEntity,
     ));
 
-    // PRIMARY SCHOOL BUILDING (maze-like)
-    spawn_school(&mut commands, &mut meshes, &mut mats);
+    // PRIMARY SCHOOL BUILDING (generated floor plan, walkable interior)
+    let school = generate_school_layout(seed.derive("school"));
+    spawn_school(&mut commands, &mut meshes, &mut mats, &handles, &school);
+    commands.insert_resource(school);
 
     // ROADS GRID
     spawn_road_grid(&mut commands, &mut meshes, &mut mats, &mut spawn_info);
@@ -62,52 +103,20 @@ fn setup_world(
     generate_npc_spawn_points(&mut spawn_info);
 
     // Optionally: spawn a few NPCs immediately to populate the world
-    spawn_initial_npcs(&mut commands, &handles, &spawn_info);
+    let npc_plan = plan_initial_npcs(&mut seed.stream("world_npcs"), &spawn_info.npc_spawn_points, &archetypes);
+    spawn_initial_npcs(&mut commands, &handles, &archetypes, &npc_plan);
 
     // Car spawn points are created in spawn_road_grid (used by CarsPlugin/car spawner)
     info!("World setup complete: {} NPC spawn points, {} car spawn points", spawn_info.npc_spawn_points.len(), spawn_info.car_spawn_points.len());
 }
 
-fn spawn_school(commands: &mut Commands, meshes: &mut ResMut<Assets<Mesh>>, mats: &mut ResMut<Assets<StandardMaterial>>) {
-    let wall_mat = mats.add(StandardMaterial::from(Color::rgb(0.88, 0.88, 0.9)));
-    let floor_mat = mats.add(StandardMaterial::from(Color::rgb(0.9, 0.9, 0.84)));
-
-    // outer shell
-    commands.spawn((
-        PbrBundle {
-            mesh: meshes.add(Mesh::from(shape::Box::new(120.0, 12.5, 90.0))),
-            material: wall_mat.clone(),
-            transform: Transform::from_translation(Vec3::new(40.0, 6.25, 0.0)),
-            ..default()
-        },
-        MissionEntity,
-    ));
-
-    // inner corridors / rooms
-    for rx in -4..=4 {
-        for rz in -3..=3 {
-            let room_pos = Vec3::new(40.0 + rx as f32 * 9.0, 0.0, rz as f32 * 9.0);
-            commands.spawn((
-                PbrBundle {
-                    mesh: meshes.add(Mesh::from(shape::Box::new(7.5, 4.0, 7.5))),
-                    material: floor_mat.clone(),
-                    transform: Transform::from_translation(room_pos + Vec3::Y * 2.0),
-                    ..default()
-                },
-                MissionEntity,
-            ));
-            // small lockers / obstacles
-            commands.spawn((
-                PbrBundle {
-                    mesh: meshes.add(Mesh::from(shape::Box::new(0.9, 2.0, 0.5))),
-                    material: wall_mat.clone(),
-                    transform: Transform::from_translation(room_pos + Vec3::new(-2.2, 1.0, 0.0)),
-                    ..default()
-                },
-                MissionEntity,
-            ));
-        }
-    }
+/// Whether `pos` is on one of the roads
+pub fn on_road(pos: Vec3) -> bool {
+    let near = |v: f32| {
+        let i = (v / ROAD_SPACING).round();
+        i.abs() <= ROAD_COUNT as f32 && (v - i * ROAD_SPACING).abs() < ROAD_WIDTH * 0.5
+    };
+    near(pos.x) || near(pos.z)
 }
 
 fn spawn_road_grid(
@@ -116,31 +125,31 @@ fn spawn_road_grid(
     mats: &mut ResMut<Assets<StandardMaterial>>,
     spawn_info: &mut WorldSpawnInfo,
 ) {
-    let road_mat = mats.add(StandardMaterial::from(Color::rgb(0.08, 0.08, 0.08)));
+    let road_mat = mats.add(StandardMaterial::from(Color::srgb(0.08, 0.08, 0.08)));
     // grid of roads centered around origin
-    for i in -5..=5 {
+    for i in -ROAD_COUNT..=ROAD_COUNT {
         // horizontal roads (long)
-        let z = i as f32 * 40.0;
-        let road_ent = commands.spawn((
+        let z = i as f32 * ROAD_SPACING;
+        commands.spawn((
             PbrBundle {
-                mesh: meshes.add(Mesh::from(shape::Box::new(1600.0, 0.2, 8.0))),
+                mesh: meshes.add(Cuboid::new(1600.0, 0.2, ROAD_WIDTH)),
                 material: road_mat.clone(),
                 transform: Transform::from_translation(Vec3::new(0.0, 0.05, z)),
                 ..default()
             },
             MissionEntity,
-        )).id();
+        ));
 
         // create spawn points at both ends of the road segment for cars
         spawn_info.car_spawn_points.push(Vec3::new(-780.0, 0.5, z));
         spawn_info.car_spawn_points.push(Vec3::new(780.0, 0.5, z));
     }
 
-    for i in -5..=5 {
-        let x = i as f32 * 40.0;
+    for i in -ROAD_COUNT..=ROAD_COUNT {
+        let x = i as f32 * ROAD_SPACING;
         commands.spawn((
             PbrBundle {
-                mesh: meshes.add(Mesh::from(shape::Box::new(8.0, 0.2, 1600.0))),
+                mesh: meshes.add(Cuboid::new(ROAD_WIDTH, 0.2, 1600.0)),
                 material: road_mat.clone(),
                 transform: Transform::from_translation(Vec3::new(x, 0.05, 0.0)),
                 ..default()
@@ -150,6 +159,48 @@ fn spawn_road_grid(
         spawn_info.car_spawn_points.push(Vec3::new(x, 0.5, -780.0));
         spawn_info.car_spawn_points.push(Vec3::new(x, 0.5, 780.0));
     }
+
+    // lamp posts, lit by the clock at night
+    let pole_mat = mats.add(StandardMaterial::from(Color::srgb(0.2, 0.2, 0.22)));
+    let pole_mesh = meshes.add(Cuboid::new(0.15, 5.0, 0.15));
+    for base in lamp_post_positions() {
+        commands.spawn((
+            PbrBundle {
+                mesh: pole_mesh.clone(),
+                material: pole_mat.clone(),
+                transform: Transform::from_translation(base + Vec3::Y * 2.5),
+                ..default()
+            },
+            MissionEntity,
+        )).with_children(|parent| {
+            parent.spawn((
+                PointLightBundle {
+                    // off until the clock says it's dark
+                    point_light: PointLight { intensity: 0.0, range: 14.0, color: Color::srgb(1.0, 0.85, 0.6), ..default() },
+                    transform: Transform::from_translation(Vec3::Y * 2.4),
+                    ..default()
+                },
+                StreetLight,
+            ));
+        });
+    }
+}
+
+/// Lamp posts on a pavement corner of each intersection around the town
+/// centre, across the road where that corner is taken by the school
+fn lamp_post_positions() -> Vec<Vec3> {
+    let mut posts = Vec::new();
+    for i in -3..=3 {
+        for j in -3..=3 {
+            let corner = Vec3::new(i as f32 * ROAD_SPACING + PAVEMENT_OFFSET, 0.0, j as f32 * ROAD_SPACING + PAVEMENT_OFFSET);
+            if school::in_footprint(corner, 1.0) {
+                posts.push(corner - Vec3::Z * PAVEMENT_OFFSET * 2.0);
+            } else {
+                posts.push(corner);
+            }
+        }
+    }
+    posts
 }
 
 fn spawn_food_and_market(
@@ -166,7 +217,7 @@ fn spawn_food_and_market(
         Vec3::new(60.0, 0.0, 40.0),
     ];
 
-    for (i, pos) in market_positions.iter().enumerate() {
+    for pos in &market_positions {
         // spawn a simple stall using kenney assets if available
         if let Some(market_scene) = &handles.kenney_market {
             commands.spawn((
@@ -179,10 +230,10 @@ fn spawn_food_and_market(
             ));
         } else {
             // simple stall fallback
-            let stall_mat = mats.add(StandardMaterial::from(Color::rgb(0.7, 0.45, 0.3)));
+            let stall_mat = mats.add(StandardMaterial::from(Color::srgb(0.7, 0.45, 0.3)));
             commands.spawn((
                 PbrBundle {
-                    mesh: meshes.add(Mesh::from(shape::Box::new(2.0, 1.2, 2.0))),
+                    mesh: meshes.add(Cuboid::new(2.0, 1.2, 2.0)),
                     material: stall_mat,
                     transform: Transform::from_translation(*pos + Vec3::Y * 0.6),
                     ..default()
@@ -193,37 +244,51 @@ fn spawn_food_and_market(
         spawn_info.market_spots.push(*pos);
     }
 
-    // Food pickup clusters (small items) — use kenney food if available
+    // Food pickup clusters (small items); the food system restocks these spots
     let food_positions = vec![Vec3::new(18.0, 0.0, -18.0), Vec3::new(-30.0, 0.0, 22.0), Vec3::new(62.0, 0.0, 42.0)];
     for pos in food_positions {
-        if let Some(food_scene) = &handles.kenney_food {
-            commands.spawn((
-                SceneBundle {
-                    scene: food_scene.clone(),
-                    transform: Transform::from_translation(pos + Vec3::Y * 0.2).with_scale(Vec3::splat(0.6)),
-                    ..default()
-                },
-                crate::systems::inventory::FoodPickup,
-                Collider::ball(0.4),
-                RigidBody::Fixed,
-                MissionEntity,
-            ));
-        } else {
-            // fallback sphere
-            let food_mat = mats.add(StandardMaterial::from(Color::rgb(0.9, 0.6, 0.2)));
-            commands.spawn((
-                PbrBundle {
-                    mesh: meshes.add(Mesh::from(shape::UVSphere { radius: 0.4, sectors: 8, stacks: 6 })),
-                    material: food_mat,
-                    transform: Transform::from_translation(pos + Vec3::Y * 0.45),
-                    ..default()
-                },
-                crate::systems::inventory::FoodPickup,
-                Collider::ball(0.4),
-                RigidBody::Fixed,
-                MissionEntity,
-            ));
-        }
+        spawn_food_pickup(commands, meshes, mats, handles, pos, DEFAULT_FOOD);
+        spawn_info.food_spots.push(pos);
+    }
+}
+
+/// A food item on the ground at `pos`; `item` is its `ItemDef::id`
+pub fn spawn_food_pickup(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    mats: &mut Assets<StandardMaterial>,
+    handles: &Handles,
+    pos: Vec3,
+    item: &str,
+) {
+    // use kenney food if available
+    if let Some(food_scene) = &handles.kenney_food {
+        commands.spawn((
+            SceneBundle {
+                scene: food_scene.clone(),
+                transform: Transform::from_translation(pos + Vec3::Y * 0.2).with_scale(Vec3::splat(0.6)),
+                ..default()
+            },
+            ItemPickup::new(item),
+            Collider::ball(0.4),
+            RigidBody::Fixed,
+            MissionEntity,
+        ));
+    } else {
+        // fallback sphere
+        let food_mat = mats.add(StandardMaterial::from(Color::srgb(0.9, 0.6, 0.2)));
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Sphere::new(0.4).mesh().uv(8, 6)),
+                material: food_mat,
+                transform: Transform::from_translation(pos + Vec3::Y * 0.45),
+                ..default()
+            },
+            ItemPickup::new(item),
+            Collider::ball(0.4),
+            RigidBody::Fixed,
+            MissionEntity,
+        ));
     }
 }
 
@@ -242,18 +307,25 @@ fn generate_npc_spawn_points(spawn_info: &mut WorldSpawnInfo) {
     }
     // some scattered around school perimeter
     spawn_info.npc_spawn_points.push(Vec3::new(36.0, 0.0, -30.0));
-    spawn_info.npc_spawn_points.push(Vec3::new(46.0, 0.0, 12.0));
+    spawn_info.npc_spawn_points.push(Vec3::new(46.0, 0.0, 20.0));
     spawn_info.npc_spawn_points.push(Vec3::new(12.0, 0.0, 52.0));
 }
 
-/// spawn a handful of NPCs immediately to populate the world (random types)
-fn spawn_initial_npcs(commands: &mut Commands, handles: &Handles, spawn_info: &WorldSpawnInfo) {
-    use rand::prelude::*;
-    let mut rng = rand::thread_rng();
+/// One NPC the world generator intends to place
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpcSpawn {
+    pub category: NpcCategory,
+    pub position: Vec3,
+    /// `None` when the category has no archetypes; a stock model is used
+    pub look: Option<AppearanceChoice>,
+}
 
-    // choose up to N random spawn points
-    let picks = spawn_info.npc_spawn_points.iter().cloned().take(24).collect::<Vec<_>>();
-    for pos in picks {
+/// Decide which NPCs to place at startup. Pure so a seed can be replayed.
+pub fn plan_initial_npcs(rng: &mut impl Rng, spawn_points: &[Vec3], archetypes: &Archetypes) -> Vec<NpcSpawn> {
+    let mut plan: Vec<NpcSpawn> = Vec::new();
+    let mut used_names = HashSet::new();
+    // choose up to N spawn points
+    for pos in spawn_points.iter().cloned().take(24) {
         let roll: f32 = rng.gen();
         let category = if roll < 0.12 {
             NpcCategory::Trader
@@ -262,23 +334,93 @@ fn spawn_initial_npcs(commands: &mut Commands, handles: &Handles, spawn_info: &W
         } else {
             NpcCategory::Student
         };
+        // looks already standing nearby, so a crowd isn't all twins
+        let nearby = plan
+            .iter()
+            .filter(|s| s.position.distance(pos) < CLUSTER_RADIUS)
+            .filter_map(|s| s.look.as_ref())
+            .collect::<Vec<_>>();
+        let look = archetypes.choose(rng, &category, &nearby, &used_names);
+        if let Some(look) = &look {
+            used_names.insert(look.name.clone());
+        }
+        plan.push(NpcSpawn { category, position: pos, look });
+    }
+    plan
+}
 
+/// spawn a handful of NPCs immediately to populate the world
+fn spawn_initial_npcs(commands: &mut Commands, handles: &Handles, archetypes: &Archetypes, plan: &[NpcSpawn]) {
+    for (index, NpcSpawn { category, position: pos, look }) in plan.iter().cloned().enumerate() {
         let mut builder = commands.spawn((
             TransformBundle::from_transform(Transform::from_translation(pos + Vec3::Y * 0.0)),
             Npc { category: category.clone(), state: NpcState::Idle, timer: 0.0 },
+            NavAgent::new(NPC_WALK_SPEED),
+            NpcId(index as u32),
+            NpcRoutine::new(pos),
+            SimLod::default(),
             MissionEntity,
         ));
 
-        // spawn model: prefer different glbs to diversify
-        let model_handle = match category {
-            NpcCategory::Trader => handles.worker.clone().into(),
-            NpcCategory::Civilian => handles.man.clone().into(),
-            NpcCategory::Student => handles.animated_woman.clone().unwrap_or_else(|| handles.man.clone()).into(),
-        };
+        let archetype = look.as_ref().and_then(|l| archetypes.get(&category, &l.archetype).map(|def| (l, def)));
+        if let Some((look, def)) = archetype {
+            let model = def.model_path();
+            builder.insert((
+                SceneBundle { scene: handles.character(model), transform: Transform::IDENTITY.with_scale(Vec3::splat(def.scale)), ..default() },
+                Animated::new(model),
+                Appearance { archetype: def.id.clone(), tint: def.tint(look.tint), voice: def.voice.clone() },
+                NpcName(look.name.clone()),
+            ));
+            continue;
+        }
 
-        // if model is loaded spawn scene; if not, fallback to a capsule
+        // no archetype table: prefer different glbs to diversify
+        let model_name = match category {
+            NpcCategory::Trader => "Worker.glb",
+            NpcCategory::Civilian => "Man.glb",
+            NpcCategory::Student if handles.animated_woman.is_some() => "Animated Woman.glb",
+            NpcCategory::Student => "Man.glb",
+        };
         builder.insert((
-            SceneBundle { scene: model_handle, transform: Transform::IDENTITY.with_scale(Vec3::splat(0.9)), ..default() }
+            SceneBundle { scene: handles.character(model_name), transform: Transform::IDENTITY.with_scale(Vec3::splat(0.9)), ..default() },
+            Animated::new(model_name),
         ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::school::PropKind;
+
+    /// Everything the generator places, flattened for comparison
+    fn spawn_manifest(seed: WorldSeed) -> (Vec<(PropKind, Vec3)>, Vec<NpcSpawn>) {
+        let school = generate_school_layout(seed.derive("school"));
+        let props = school.props.iter().map(|p| (p.kind, p.transform.translation)).collect();
+
+        let mut spawn_info = WorldSpawnInfo::default();
+        generate_npc_spawn_points(&mut spawn_info);
+        let archetypes = load_archetypes(ARCHETYPES_PATH, &load_voices(VOICES_PATH).unwrap()).unwrap();
+        let npcs = plan_initial_npcs(&mut seed.stream("world_npcs"), &spawn_info.npc_spawn_points, &archetypes);
+        (props, npcs)
+    }
+
+    #[test]
+    fn same_seed_spawns_identical_world() {
+        let seed = WorldSeed(0xB0A7);
+        assert_eq!(spawn_manifest(seed), spawn_manifest(seed));
+    }
+
+    #[test]
+    fn different_seeds_diverge() {
+        assert_ne!(spawn_manifest(WorldSeed(1)), spawn_manifest(WorldSeed(2)));
+    }
+
+    #[test]
+    fn lamp_posts_stand_clear_of_the_school_and_roads() {
+        for post in lamp_post_positions() {
+            assert!(!school::in_footprint(post, 1.0), "lamp post at {post:?} is in the school");
+            assert!(!on_road(post), "lamp post at {post:?} is on a road");
+        }
+    }
+}
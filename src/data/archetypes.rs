@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use crate::core::animation::CHARACTER_MODELS;
+use crate::core::npc_ai::NpcCategory;
+use crate::data::voices::Voices;
+
+pub const ARCHETYPES_PATH: &str = "assets/data/archetypes.ron";
+
+/// NPCs closer than this to each other count as one crowd when avoiding lookalikes
+pub const CLUSTER_RADIUS: f32 = 15.0;
+/// Weight multiplier per lookalike already in the crowd
+const DUPLICATE_PENALTY: f32 = 0.15;
+
+fn one() -> f32 {
+    1.0
+}
+
+/// One kind of character an NPC of a category can look like
+#[derive(Debug, Deserialize, Clone)]
+pub struct ArchetypeDef {
+    pub id: String,
+    /// One of `CHARACTER_MODELS`
+    pub model: String,
+    #[serde(default = "one")]
+    pub scale: f32,
+    #[serde(default = "one")]
+    pub weight: f32,
+    /// Colour multipliers; empty means the model's own colours
+    #[serde(default)]
+    pub tints: Vec<(f32, f32, f32)>,
+    pub names: Vec<String>,
+    /// Key of `Voices`, used for dialogue audio
+    pub voice: String,
+}
+
+impl ArchetypeDef {
+    pub fn model_path(&self) -> &'static str {
+        CHARACTER_MODELS.iter().copied().find(|m| *m == self.model).unwrap_or(CHARACTER_MODELS[0])
+    }
+
+    pub fn tint(&self, index: usize) -> Color {
+        self.tints.get(index).map_or(Color::WHITE, |&(r, g, b)| Color::srgb(r, g, b))
+    }
+}
+
+/// The look picked for one NPC
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppearanceChoice {
+    /// `ArchetypeDef::id`
+    pub archetype: String,
+    /// Index into the archetype's tints
+    pub tint: usize,
+    pub name: String,
+}
+
+/// Archetypes per NPC category
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Archetypes {
+    pub by_category: HashMap<NpcCategory, Vec<ArchetypeDef>>,
+}
+
+impl Archetypes {
+    pub fn get(&self, category: &NpcCategory, id: &str) -> Option<&ArchetypeDef> {
+        self.by_category.get(category)?.iter().find(|d| d.id == id)
+    }
+
+    /// Weighted pick for a new NPC. `nearby` are the looks already placed
+    /// within `CLUSTER_RADIUS`: an archetype gets rarer for each of them
+    /// already wearing it, and a tint none of them wear is always preferred.
+    pub fn choose(
+        &self,
+        rng: &mut impl Rng,
+        category: &NpcCategory,
+        nearby: &[&AppearanceChoice],
+        used_names: &HashSet<String>,
+    ) -> Option<AppearanceChoice> {
+        let defs = self.by_category.get(category)?;
+        let weights = defs
+            .iter()
+            .map(|d| d.weight * DUPLICATE_PENALTY.powi(nearby.iter().filter(|c| c.archetype == d.id).count() as i32))
+            .collect::<Vec<_>>();
+        let def = &defs[WeightedIndex::new(&weights).ok()?.sample(rng)];
+
+        let tints = def.tints.len().max(1);
+        let taken = nearby.iter().filter(|c| c.archetype == def.id).map(|c| c.tint).collect::<Vec<_>>();
+        let free = (0..tints).filter(|t| !taken.contains(t)).collect::<Vec<_>>();
+        let tint = free.choose(rng).copied().unwrap_or_else(|| rng.gen_range(0..tints));
+
+        let unused = def.names.iter().filter(|n| !used_names.contains(*n)).collect::<Vec<_>>();
+        let name = unused.choose(rng).copied().or_else(|| def.names.choose(rng))?.clone();
+
+        Some(AppearanceChoice { archetype: def.id.clone(), tint, name })
+    }
+}
+
+pub fn load_archetypes(path: &str, voices: &Voices) -> Result<Archetypes> {
+    let s = fs::read_to_string(path).with_context(|| format!("Reading archetypes {path}"))?;
+    let by_category: HashMap<NpcCategory, Vec<ArchetypeDef>> = ron::from_str(&s).context("Parsing RON archetypes")?;
+    for (category, defs) in &by_category {
+        for def in defs {
+            if !CHARACTER_MODELS.contains(&def.model.as_str()) {
+                anyhow::bail!("Archetype '{}' ({:?}) uses unknown model '{}'", def.id, category, def.model);
+            }
+            if def.weight <= 0.0 || def.scale <= 0.0 {
+                anyhow::bail!("Archetype '{}' needs a positive weight and scale", def.id);
+            }
+            if def.names.is_empty() {
+                anyhow::bail!("Archetype '{}' has no names", def.id);
+            }
+            if !voices.lines.contains_key(&def.voice) {
+                anyhow::bail!("Archetype '{}' uses unknown voice '{}'", def.id, def.voice);
+            }
+        }
+    }
+    Ok(Archetypes { by_category })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::voices::{load_voices, VOICES_PATH};
+
+    fn bundled() -> Archetypes {
+        let voices = load_voices(VOICES_PATH).expect("voices.ron should load");
+        load_archetypes(ARCHETYPES_PATH, &voices).expect("archetypes.ron should load")
+    }
+
+    #[test]
+    fn bundled_archetypes_cover_every_category() {
+        let archetypes = bundled();
+        for category in [NpcCategory::Student, NpcCategory::Trader, NpcCategory::Civilian] {
+            assert!(archetypes.by_category.get(&category).is_some_and(|d| !d.is_empty()), "{:?}", category);
+        }
+    }
+
+    #[test]
+    fn crowds_avoid_exact_lookalikes() {
+        let archetypes = bundled();
+        // one of every trader archetype already standing there, each in its first tint
+        let crowd = archetypes.by_category[&NpcCategory::Trader]
+            .iter()
+            .map(|d| AppearanceChoice { archetype: d.id.clone(), tint: 0, name: d.names[0].clone() })
+            .collect::<Vec<_>>();
+        let nearby = crowd.iter().collect::<Vec<_>>();
+        let used = crowd.iter().map(|c| c.name.clone()).collect::<HashSet<_>>();
+
+        for seed in 0..200 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let pick = archetypes.choose(&mut rng, &NpcCategory::Trader, &nearby, &used).unwrap();
+            assert!(!crowd.iter().any(|c| c.archetype == pick.archetype && c.tint == pick.tint), "{:?}", pick);
+            assert!(!used.contains(&pick.name));
+        }
+    }
+}
@@ -16,7 +16,28 @@ pub struct Handles {
     pub kenney_furniture: Option<Handle<Scene>>,
     pub kenney_market: Option<Handle<Scene>>,
     pub kenney_skate: Option<Handle<Scene>>,
-    pub dirt_icon: Option<Handle<Image>>,
+    pub furniture_desk: Option<Handle<Scene>>,
+    pub furniture_chair: Option<Handle<Scene>>,
+    pub furniture_bookcase: Option<Handle<Scene>>,
+    pub furniture_bench: Option<Handle<Scene>>,
+    pub building_door: Option<Handle<Scene>>,
+    pub nature_bush: Option<Handle<Scene>>,
+}
+
+impl Handles {
+    /// Scene for one of the rigged character GLBs, falling back to Man.glb
+    pub fn character(&self, model: &str) -> Handle<Scene> {
+        let scene = match model {
+            "Punk.glb" => Some(&self.punk),
+            "Man in Suit.glb" => self.man_in_suit.as_ref(),
+            "Animated Woman.glb" => self.animated_woman.as_ref(),
+            "Farmer.glb" => self.farmer.as_ref(),
+            "Worker Female.glb" => self.worker_female.as_ref(),
+            "Worker.glb" => self.worker.as_ref(),
+            _ => None,
+        };
+        scene.unwrap_or(&self.man).clone()
+    }
 }
 
 pub fn preload_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -29,13 +50,18 @@ pub fn preload_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
         farmer: Some(asset_server.load("Farmer.glb#Scene0")),
         worker_female: Some(asset_server.load("Worker Female.glb#Scene0")),
         worker: Some(asset_server.load("Worker.glb#Scene0")),
-        kenney_car: asset_server.get_handle("kenney_car-kit/car.glb#Scene0").ok().or_else(|| asset_server.get_handle("kenney_car-kit/Car.glb#Scene0").ok()),
-        kenney_roads: asset_server.get_handle("kenney_city-kit-roads/scene.gltf#Scene0").ok(),
-        kenney_food: asset_server.get_handle("kenney_food-kit/scene.gltf#Scene0").ok(),
-        kenney_furniture: asset_server.get_handle("kenney_furniture-kit/scene.gltf#Scene0").ok(),
-        kenney_market: asset_server.get_handle("kenney_mini-market/scene.gltf#Scene0").ok(),
-        kenney_skate: asset_server.get_handle("kenney_mini-skate/scene.gltf#Scene0").ok(),
-        dirt_icon: asset_server.get_handle("dirt.png").ok(),
+        kenney_car: asset_server.get_handle("kenney_car-kit/car.glb#Scene0").or_else(|| asset_server.get_handle("kenney_car-kit/Car.glb#Scene0")),
+        kenney_roads: asset_server.get_handle("kenney_city-kit-roads/scene.gltf#Scene0"),
+        kenney_food: asset_server.get_handle("kenney_food-kit/scene.gltf#Scene0"),
+        kenney_furniture: asset_server.get_handle("kenney_furniture-kit/scene.gltf#Scene0"),
+        kenney_market: asset_server.get_handle("kenney_mini-market/scene.gltf#Scene0"),
+        kenney_skate: asset_server.get_handle("kenney_mini-skate/scene.gltf#Scene0"),
+        furniture_desk: Some(asset_server.load("kenney_furniture-kit/desk.glb#Scene0")),
+        furniture_chair: Some(asset_server.load("kenney_furniture-kit/chairDesk.glb#Scene0")),
+        furniture_bookcase: Some(asset_server.load("kenney_furniture-kit/bookcaseClosedWide.glb#Scene0")),
+        furniture_bench: Some(asset_server.load("kenney_furniture-kit/bench.glb#Scene0")),
+        building_door: Some(asset_server.load("kenney_modular-buildings/Models/GLB format/door-white.glb#Scene0")),
+        nature_bush: Some(asset_server.load("kenney_nature-kit/plant_bushLarge.glb#Scene0")),
     };
 
     commands.insert_resource(handles);
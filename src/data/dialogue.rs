@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use crate::core::npc_ai::NpcCategory;
+use crate::data::items::{ItemCategory, ItemDb};
+use crate::systems::inventory::Inventory;
+
+pub const DIALOGUE_DIR: &str = "assets/dialogue";
+
+/// A conversation for one kind of NPC, loaded from YAML
+#[derive(Debug, Deserialize, Clone)]
+pub struct DialogueTree {
+    pub speaker: String,
+    /// Node opened on the first conversation
+    pub start: String,
+    /// Node opened when the player has talked to this NPC before
+    #[serde(default)]
+    pub start_again: Option<String>,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DialogueNode {
+    /// Overrides the tree's speaker (e.g. for Ethan's own lines)
+    #[serde(default)]
+    pub speaker: Option<String>,
+    pub lines: Vec<String>,
+    /// No choices (or none whose conditions hold) ends the conversation
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// Node to go to; `None` ends the conversation
+    #[serde(default)]
+    pub next: Option<String>,
+    /// All must hold for the choice to be offered
+    #[serde(default)]
+    pub conditions: Vec<DialogueCondition>,
+    /// Something to do once the choice is taken
+    #[serde(default)]
+    pub action: Option<DialogueAction>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogueAction {
+    /// Close the conversation and open the trader's stall
+    Trade,
+}
+
+/// A check on the game state. Every field that is set must hold.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DialogueCondition {
+    /// Item id from `items.ron`
+    pub has_item: Option<String>,
+    pub lacks_item: Option<String>,
+    /// Holds anything of this category
+    pub has_category: Option<ItemCategory>,
+    pub min_caught: Option<u32>,
+    pub max_caught: Option<u32>,
+    pub min_mission_progress: Option<u8>,
+    /// The player has talked to this NPC before
+    pub met_before: Option<bool>,
+    /// A node of this NPC's tree was reached in an earlier (or this) conversation
+    pub visited: Option<String>,
+    pub not_visited: Option<String>,
+}
+
+/// What one NPC remembers of talking to the player. Saved with the game.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NpcMemory {
+    pub times_talked: u32,
+    pub visited: HashSet<String>,
+}
+
+/// Everything a condition can look at
+pub struct DialogueContext<'a> {
+    pub inventory: &'a Inventory,
+    pub items: &'a ItemDb,
+    pub times_caught: u32,
+    pub memory: &'a NpcMemory,
+}
+
+impl DialogueCondition {
+    pub fn holds(&self, ctx: &DialogueContext) -> bool {
+        let has = |item: &str| ctx.inventory.count(item) > 0;
+        let has_category = |category: ItemCategory| {
+            ctx.inventory.stacks().any(|s| ctx.items.get(&s.item).is_some_and(|d| d.category == category))
+        };
+        self.has_item.as_ref().is_none_or(|i| has(i))
+            && self.lacks_item.as_ref().is_none_or(|i| !has(i))
+            && self.has_category.is_none_or(has_category)
+            && self.min_caught.is_none_or(|n| ctx.times_caught >= n)
+            && self.max_caught.is_none_or(|n| ctx.times_caught <= n)
+            && self.min_mission_progress.is_none_or(|n| ctx.inventory.mission_progress >= n)
+            && self.met_before.is_none_or(|met| (ctx.memory.times_talked > 0) == met)
+            && self.visited.as_ref().is_none_or(|n| ctx.memory.visited.contains(n))
+            && self.not_visited.as_ref().is_none_or(|n| !ctx.memory.visited.contains(n))
+    }
+}
+
+impl DialogueChoice {
+    pub fn available(&self, ctx: &DialogueContext) -> bool {
+        self.conditions.iter().all(|c| c.holds(ctx))
+    }
+}
+
+impl DialogueTree {
+    pub fn entry_node(&self, memory: &NpcMemory) -> &str {
+        match &self.start_again {
+            Some(again) if memory.times_talked > 0 => again,
+            _ => &self.start,
+        }
+    }
+}
+
+/// Dialogue trees per NPC category
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DialogueLibrary {
+    pub trees: HashMap<NpcCategory, DialogueTree>,
+}
+
+fn tree_file(category: &NpcCategory) -> &'static str {
+    match category {
+        NpcCategory::Student => "student.yaml",
+        NpcCategory::Trader => "trader.yaml",
+        NpcCategory::Civilian => "civilian.yaml",
+    }
+}
+
+pub fn load_dialogue_tree(path: &str, items: &ItemDb) -> Result<DialogueTree> {
+    let s = fs::read_to_string(path).with_context(|| format!("Reading dialogue file {path}"))?;
+    let tree: DialogueTree = serde_yaml::from_str(&s).with_context(|| format!("Parsing YAML dialogue {path}"))?;
+    validate_tree(&tree, items)?;
+    Ok(tree)
+}
+
+/// Load every category's tree; categories whose file fails are logged and left out
+pub fn load_dialogue_library(dir: &str, items: &ItemDb) -> DialogueLibrary {
+    let mut trees = HashMap::new();
+    for category in [NpcCategory::Student, NpcCategory::Trader, NpcCategory::Civilian] {
+        let path = format!("{}/{}", dir, tree_file(&category));
+        match load_dialogue_tree(&path, items) {
+            Ok(tree) => {
+                trees.insert(category, tree);
+            }
+            Err(err) => error!("Failed to load dialogue for {:?}: {:?}", category, err),
+        }
+    }
+    DialogueLibrary { trees }
+}
+
+fn validate_tree(tree: &DialogueTree, items: &ItemDb) -> Result<()> {
+    for start in std::iter::once(&tree.start).chain(tree.start_again.iter()) {
+        if !tree.nodes.contains_key(start) {
+            anyhow::bail!("Start node '{}' does not exist", start);
+        }
+    }
+    for (id, node) in &tree.nodes {
+        if node.lines.is_empty() {
+            anyhow::bail!("Node '{}' has no lines", id);
+        }
+        for choice in &node.choices {
+            for cond in &choice.conditions {
+                for item in cond.has_item.iter().chain(cond.lacks_item.iter()) {
+                    items.require(item, &format!("Choice '{}' in node '{}'", choice.text, id))?;
+                }
+            }
+            if let Some(next) = &choice.next {
+                if !tree.nodes.contains_key(next) {
+                    anyhow::bail!("Choice '{}' in node '{}' points to missing node '{}'", choice.text, id, next);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::items::{load_items, ITEMS_PATH};
+
+    #[test]
+    fn bundled_dialogue_is_valid() {
+        let items = load_items(ITEMS_PATH).unwrap();
+        let library = load_dialogue_library(DIALOGUE_DIR, &items);
+        assert_eq!(library.trees.len(), 3);
+    }
+
+    #[test]
+    fn conditions_check_inventory_and_memory() {
+        let items = load_items(ITEMS_PATH).unwrap();
+        let mut inventory = Inventory::default();
+        inventory.add(items.get("food_apple").unwrap());
+        let mut memory = NpcMemory::default();
+        let cond = DialogueCondition { has_category: Some(ItemCategory::Food), not_visited: Some("bribe".into()), ..default() };
+
+        let ctx = DialogueContext { inventory: &inventory, items: &items, times_caught: 0, memory: &memory };
+        assert!(cond.holds(&ctx));
+        let burger = DialogueCondition { has_item: Some("food_burger".into()), ..default() };
+        assert!(!burger.holds(&ctx));
+
+        memory.visited.insert("bribe".into());
+        let ctx = DialogueContext { inventory: &inventory, items: &items, times_caught: 0, memory: &memory };
+        assert!(!cond.holds(&ctx));
+
+        let caught = DialogueCondition { min_caught: Some(2), ..default() };
+        assert!(!caught.holds(&ctx));
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::core::hallucinations::HallucinationKind;
+
+pub const HALLUCINATIONS_PATH: &str = "assets/data/hallucinations.ron";
+
+fn one() -> f32 {
+    1.0
+}
+
+fn default_duration() -> f32 {
+    4.0
+}
+
+fn default_radius() -> f32 {
+    15.0
+}
+
+/// One paranormal event the director can stage
+#[derive(Debug, Deserialize, Clone)]
+pub struct HallucinationDef {
+    pub id: String,
+    pub kind: HallucinationKind,
+    /// Only happens while sanity is at or below this
+    pub max_sanity: f32,
+    #[serde(default = "one")]
+    pub weight: f32,
+    /// Intensity budget spent when it happens
+    pub cost: f32,
+    pub cooldown_secs: f32,
+    #[serde(default = "default_duration")]
+    pub duration_secs: f32,
+    /// Reach for effects on things around Ethan
+    #[serde(default = "default_radius")]
+    pub radius: f32,
+    /// Never picked by the director; timelines and code start it by id
+    #[serde(default)]
+    pub scripted: bool,
+    #[serde(default)]
+    pub sound: Option<String>,
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct HallucinationTable {
+    pub defs: Vec<HallucinationDef>,
+}
+
+impl HallucinationTable {
+    pub fn get(&self, id: &str) -> Option<&HallucinationDef> {
+        self.defs.iter().find(|d| d.id == id)
+    }
+}
+
+pub fn load_hallucinations(path: &str) -> Result<HallucinationTable> {
+    let s = fs::read_to_string(path).with_context(|| format!("Reading hallucinations {path}"))?;
+    let defs: Vec<HallucinationDef> = ron::from_str(&s).context("Parsing RON hallucinations")?;
+    let mut ids = HashSet::new();
+    for def in &defs {
+        if !ids.insert(def.id.as_str()) {
+            anyhow::bail!("Hallucination '{}' is defined twice", def.id);
+        }
+        if def.weight <= 0.0 || def.cost < 0.0 || def.cooldown_secs < 0.0 || def.duration_secs <= 0.0 {
+            anyhow::bail!("Hallucination '{}' needs a positive weight and duration and no negative cost or cooldown", def.id);
+        }
+        if !(0.0..=1.0).contains(&def.max_sanity) {
+            anyhow::bail!("Hallucination '{}' has max_sanity {} outside 0..1", def.id, def.max_sanity);
+        }
+        if def.kind == HallucinationKind::FakeFootsteps && def.sound.is_none() {
+            anyhow::bail!("Hallucination '{}' fakes footsteps without a sound", def.id);
+        }
+        if let Some(sound) = &def.sound {
+            if !Path::new("assets").join(sound).exists() {
+                anyhow::bail!("Hallucination '{}' plays missing sound '{}'", def.id, sound);
+            }
+        }
+    }
+    Ok(HallucinationTable { defs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_hallucinations_load() {
+        let table = load_hallucinations(HALLUCINATIONS_PATH).expect("hallucinations.ron should load");
+        assert!(table.defs.iter().any(|d| !d.scripted), "the director needs something to pick");
+        // every effect is used by at least one event
+        for kind in HallucinationKind::ALL {
+            assert!(table.defs.iter().any(|d| d.kind == kind), "{:?}", kind);
+        }
+    }
+}
@@ -1,6 +1,18 @@
+use anyhow::{Context, Result};
+use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+use crate::core::reactions::PrankKind;
+
+pub const ITEMS_PATH: &str = "assets/data/items.ron";
+
+/// What a food pickup gives when it doesn't say
+pub const DEFAULT_FOOD: &str = "food_snack";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum ItemCategory {
     Food,
     Tool,
@@ -8,23 +20,210 @@ pub enum ItemCategory {
     Utility,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// Lingering effect of something Ethan ate
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum FoodEffect {
+    /// Health slowly comes back
+    WellFed { secs: f32 },
+    /// Spoiled food: no sprinting, no appetite, and hunger drains faster
+    Nausea { secs: f32 },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FoodDef {
+    /// Hunger restored
+    pub nutrition: f32,
+    /// How long eating it takes; moving or hiding meanwhile cancels it
+    pub eat_secs: f32,
+    pub effect: Option<FoodEffect>,
+}
+
+/// Something an item does
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ItemUse {
+    Eat(FoodDef),
+    /// Lights the way while equipped and switched on
+    Light,
+    Dig,
+    Till,
+    /// Thrown with the prank key
+    Prank(PrankKind),
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct ItemDef {
-    pub id: &'static str,
-    pub name: &'static str,
+    pub id: String,
+    pub name: String,
     pub category: ItemCategory,
+    #[serde(default = "one")]
     pub stack_size: u32,
-    pub description: &'static str,
-}
-
-// Example item registry (static list)
-pub static ITEM_REGISTRY: &[ItemDef] = &[
-    ItemDef { id: "food_burger", name: "Burger", category: ItemCategory::Food, stack_size: 8, description: "Hearty burger. Restores hunger." },
-    ItemDef { id: "food_apple", name: "Apple", category: ItemCategory::Food, stack_size: 16, description: "Simple fruit. Small hunger restore." },
-    ItemDef { id: "tool_shovel", name: "Shovel", category: ItemCategory::Tool, stack_size: 1, description: "Dig ground to collect dirt." },
-    ItemDef { id: "tool_hoe", name: "Hoe", category: ItemCategory::Tool, stack_size: 1, description: "Till grass into dirt." },
-    ItemDef { id: "tool_torch", name: "Torch", category: ItemCategory::Tool, stack_size: 1, description: "Lights up dark places." },
-    ItemDef { id: "prank_stinkbomb", name: "Stink Bomb", category: ItemCategory::Prank, stack_size: 4, description: "Create chaos briefly." },
-    ItemDef { id: "prank_paperplane", name: "Paper Plane", category: ItemCategory::Prank, stack_size: 16, description: "Silly diversion." },
-    ItemDef { id: "utility_dirt", name: "Dirt", category: ItemCategory::Utility, stack_size: 64, description: "Collected from grass. Used in crafting." },
-];
+    /// Uses before the item breaks; tools only
+    #[serde(default)]
+    pub durability: Option<i32>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub uses: Vec<ItemUse>,
+}
+
+impl ItemDef {
+    pub fn food(&self) -> Option<FoodDef> {
+        self.uses.iter().find_map(|u| match u {
+            ItemUse::Eat(food) => Some(*food),
+            _ => None,
+        })
+    }
+
+    pub fn prank(&self) -> Option<PrankKind> {
+        self.uses.iter().find_map(|u| match u {
+            ItemUse::Prank(kind) => Some(*kind),
+            _ => None,
+        })
+    }
+
+    pub fn has_use(&self, wanted: ItemUse) -> bool {
+        self.uses.contains(&wanted)
+    }
+}
+
+/// Every item, as defined in `items.ron`, in file order
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ItemDb {
+    pub defs: Vec<ItemDef>,
+}
+
+impl ItemDb {
+    pub fn get(&self, id: &str) -> Option<&ItemDef> {
+        self.defs.iter().find(|d| d.id == id)
+    }
+
+    /// Look up an id another data file refers to; `owner` names the referrer in the error
+    pub fn require(&self, id: &str, owner: &str) -> Result<&ItemDef> {
+        self.get(id).with_context(|| format!("{owner} refers to unknown item '{id}'"))
+    }
+}
+
+/// Item ids for the `ItemType` names saves used before items were data-driven
+pub fn legacy_item_id(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "Torch" => "tool_torch",
+        "Shovel" => "tool_shovel",
+        "Hoe" => "tool_hoe",
+        "Dirt" => "utility_dirt",
+        "Food" => DEFAULT_FOOD,
+        "StinkBomb" => "prank_stinkbomb",
+        "PaperPlane" => "prank_paperplane",
+        _ => return None,
+    })
+}
+
+pub fn load_items(path: &str) -> Result<ItemDb> {
+    let s = fs::read_to_string(path).with_context(|| format!("Reading items {path}"))?;
+    let mut defs: Vec<ItemDef> = ron::from_str(&s).context("Parsing RON items")?;
+    let mut ids = HashSet::new();
+    for def in &defs {
+        if !ids.insert(def.id.as_str()) {
+            anyhow::bail!("Item '{}' is defined twice", def.id);
+        }
+        if def.stack_size == 0 {
+            anyhow::bail!("Item '{}' has a stack size of 0", def.id);
+        }
+        if let Some(durability) = def.durability {
+            if durability <= 0 || def.stack_size != 1 {
+                anyhow::bail!("Item '{}' needs positive durability and a stack size of 1", def.id);
+            }
+        }
+        match def.food() {
+            Some(food) if food.nutrition <= 0.0 || food.eat_secs <= 0.0 => {
+                anyhow::bail!("Food '{}' needs positive nutrition and eating time", def.id);
+            }
+            Some(_) if def.category != ItemCategory::Food => anyhow::bail!("Item '{}' is edible but not a Food", def.id),
+            None if def.category == ItemCategory::Food => anyhow::bail!("Food '{}' has no Eat use", def.id),
+            _ => {}
+        }
+    }
+    // a missing picture or model shouldn't cost the whole item table: the
+    // item still works, shown by name and dropped as a plain box
+    for def in &mut defs {
+        for asset in [&mut def.icon, &mut def.model] {
+            let Some(file) = asset.as_deref() else { continue };
+            let file = file.split('#').next().unwrap_or(file);
+            if !Path::new("assets").join(file).exists() {
+                warn!("Item '{}' refers to missing asset '{}', going without", def.id, file);
+                *asset = None;
+            }
+        }
+    }
+    Ok(ItemDb { defs })
+}
+
+/// The item database on `app`, loading it first if no plugin has yet.
+/// Plugins that check their data against item ids call this at build time.
+pub fn init_item_db(app: &mut App) -> ItemDb {
+    if let Some(db) = app.world().get_resource::<ItemDb>() {
+        return db.clone();
+    }
+    let db = load_items(ITEMS_PATH).unwrap_or_else(|err| {
+        error!("Failed to load items: {:?}", err);
+        ItemDb::default()
+    });
+    app.insert_resource(db.clone());
+    db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::food::MARKET_FOOD;
+    use crate::core::tools::{BURIED_LOOT, DIRT_ITEM};
+    use crate::systems::inventory::STARTER_ITEMS;
+
+    #[test]
+    fn bundled_items_load() {
+        let db = load_items(ITEMS_PATH).expect("items.ron should load");
+        for def in &db.defs {
+            assert_eq!(def.food().is_some(), def.category == ItemCategory::Food, "{}", def.id);
+        }
+        for kind in [PrankKind::StinkBomb, PrankKind::PaperPlane] {
+            assert!(db.defs.iter().any(|d| d.prank() == Some(kind)), "{:?}", kind);
+        }
+        assert!(db.defs.iter().any(|d| d.has_use(ItemUse::Light)));
+    }
+
+    #[test]
+    fn missing_item_assets_are_dropped() {
+        let path = std::env::temp_dir().join("missing_item_assets.ron");
+        fs::write(&path, r#"[(id: "rock", name: "Rock", category: Utility, icon: Some("nope.png"), model: Some("nope.glb#Scene0"))]"#).unwrap();
+        let db = load_items(path.to_str().unwrap()).expect("a missing asset isn't fatal");
+        let rock = db.get("rock").unwrap();
+        assert_eq!((&rock.icon, &rock.model), (&None, &None));
+    }
+
+    #[test]
+    fn ids_named_in_code_exist() {
+        let db = load_items(ITEMS_PATH).unwrap();
+        assert!(db.get(DEFAULT_FOOD).and_then(|d| d.food()).is_some());
+        for (id, _) in MARKET_FOOD {
+            assert!(db.get(id).and_then(|d| d.food()).is_some(), "{}", id);
+        }
+        assert!(db.get(DIRT_ITEM).is_some());
+        for (id, _) in BURIED_LOOT {
+            assert!(db.get(id).is_some(), "{}", id);
+        }
+        for id in STARTER_ITEMS {
+            assert!(db.get(id).is_some(), "{}", id);
+        }
+        for name in ["Torch", "Shovel", "Hoe", "Dirt", "Food", "StinkBomb", "PaperPlane"] {
+            assert!(db.get(legacy_item_id(name).unwrap()).is_some(), "{}", name);
+        }
+    }
+}
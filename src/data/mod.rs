@@ -1 +1,9 @@
+pub mod archetypes;
 pub mod assets_loader;
+pub mod dialogue;
+pub mod hallucinations;
+pub mod items;
+pub mod patrol;
+pub mod schedules;
+pub mod traders;
+pub mod voices;
@@ -0,0 +1,40 @@
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+pub const PATROL_ROUTES_PATH: &str = "assets/data/patrol_routes.ron";
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PatrolRouteDef {
+    pub waypoints: Vec<(f32, f32, f32)>,
+    /// How long the Warden lingers at each waypoint
+    #[serde(default)]
+    pub pause_secs: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatrolRoute {
+    pub waypoints: Vec<Vec3>,
+    pub pause_secs: f32,
+}
+
+impl From<PatrolRouteDef> for PatrolRoute {
+    fn from(def: PatrolRouteDef) -> Self {
+        Self {
+            waypoints: def.waypoints.into_iter().map(|(x, y, z)| Vec3::new(x, y, z)).collect(),
+            pause_secs: def.pause_secs,
+        }
+    }
+}
+
+pub fn load_patrol_route(path: &str, level: &str) -> Result<PatrolRoute> {
+    let s = fs::read_to_string(path).with_context(|| format!("Reading patrol routes {path}"))?;
+    let mut routes: HashMap<String, PatrolRouteDef> = ron::from_str(&s).context("Parsing RON patrol routes")?;
+    let def = routes.remove(level).with_context(|| format!("No patrol route for level '{level}'"))?;
+    if def.waypoints.len() < 2 {
+        anyhow::bail!("Patrol route '{}' needs at least 2 waypoints, got {}", level, def.waypoints.len());
+    }
+    Ok(def.into())
+}
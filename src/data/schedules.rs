@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::core::npc_ai::NpcCategory;
+
+pub const NPC_SCHEDULES_PATH: &str = "assets/data/npc_schedules.ron";
+
+/// What an NPC is busy with during a block of the day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Activity {
+    /// At (or walking back to) where they spawned
+    Home,
+    /// Sitting in a classroom
+    Class,
+    /// Eating in the school canteen
+    Canteen,
+    /// Running their market stall
+    Stall,
+    /// Walking along the roads to work, then staying there
+    Commute,
+    /// Strolling around nearby
+    Wander,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ScheduleEntry {
+    /// Hour of day (0..24) this activity starts
+    pub from: f32,
+    pub activity: Activity,
+}
+
+/// Day plans per NPC category. Each plan is sorted by start hour; the last
+/// entry carries on past midnight until the first one starts.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct NpcSchedules {
+    pub plans: HashMap<NpcCategory, Vec<ScheduleEntry>>,
+}
+
+impl NpcSchedules {
+    pub fn activity_at(&self, category: &NpcCategory, hour: f32) -> Activity {
+        let Some(plan) = self.plans.get(category) else { return Activity::Wander };
+        plan.iter()
+            .rev()
+            .find(|e| e.from <= hour)
+            .or_else(|| plan.last())
+            .map_or(Activity::Wander, |e| e.activity)
+    }
+}
+
+pub fn load_npc_schedules(path: &str) -> Result<NpcSchedules> {
+    let s = fs::read_to_string(path).with_context(|| format!("Reading NPC schedules {path}"))?;
+    let mut plans: HashMap<NpcCategory, Vec<ScheduleEntry>> = ron::from_str(&s).context("Parsing RON NPC schedules")?;
+    for (category, plan) in plans.iter_mut() {
+        if plan.is_empty() {
+            anyhow::bail!("Schedule for {:?} is empty", category);
+        }
+        if let Some(bad) = plan.iter().find(|e| !(0.0..24.0).contains(&e.from)) {
+            anyhow::bail!("Schedule for {:?} starts an activity at hour {}, expected 0..24", category, bad.from);
+        }
+        plan.sort_by(|a, b| a.from.total_cmp(&b.from));
+    }
+    Ok(NpcSchedules { plans })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activity_follows_the_clock_and_wraps_past_midnight() {
+        let plan = vec![
+            ScheduleEntry { from: 7.5, activity: Activity::Class },
+            ScheduleEntry { from: 12.0, activity: Activity::Canteen },
+            ScheduleEntry { from: 18.5, activity: Activity::Home },
+        ];
+        let schedules = NpcSchedules { plans: HashMap::from([(NpcCategory::Student, plan)]) };
+
+        assert_eq!(schedules.activity_at(&NpcCategory::Student, 9.0), Activity::Class);
+        assert_eq!(schedules.activity_at(&NpcCategory::Student, 12.0), Activity::Canteen);
+        assert_eq!(schedules.activity_at(&NpcCategory::Student, 23.0), Activity::Home);
+        // before the first entry the previous evening's activity carries on
+        assert_eq!(schedules.activity_at(&NpcCategory::Student, 3.0), Activity::Home);
+        assert_eq!(schedules.activity_at(&NpcCategory::Trader, 9.0), Activity::Wander);
+    }
+
+    #[test]
+    fn bundled_schedules_parse() {
+        let schedules = load_npc_schedules(NPC_SCHEDULES_PATH).expect("npc_schedules.ron should load");
+        assert_eq!(schedules.activity_at(&NpcCategory::Trader, 10.0), Activity::Stall);
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+
+use crate::data::items::ItemDb;
+use crate::progression::CaptureStanding;
+
+pub const TRADERS_PATH: &str = "assets/data/traders.ron";
+
+/// What a trader pays for an item, as a fraction of its selling price
+pub const BUY_BACK_RATIO: f32 = 0.5;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StockDef {
+    /// `ItemDef::id` from `items.ron`
+    pub item: String,
+    /// Base price in coins
+    pub price: u32,
+    /// Most the trader will hold
+    pub max: u32,
+    /// Added back each morning, up to `max`
+    pub restock: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TraderDef {
+    pub id: String,
+    pub name: String,
+    /// Index into `WorldSpawnInfo::market_spots`
+    pub stall: usize,
+    pub stock: Vec<StockDef>,
+}
+
+pub fn load_traders(path: &str, items: &ItemDb) -> Result<Vec<TraderDef>> {
+    let s = fs::read_to_string(path).with_context(|| format!("Reading traders {path}"))?;
+    let traders: Vec<TraderDef> = ron::from_str(&s).context("Parsing RON traders")?;
+    for trader in &traders {
+        for entry in &trader.stock {
+            items.require(&entry.item, &format!("Trader '{}'", trader.id))?;
+        }
+    }
+    Ok(traders)
+}
+
+/// Price multiplier: traders clear stock cheaply before closing and overcharge
+/// kids the Warden has marked.
+pub fn price_multiplier(hour: f32, standing: CaptureStanding) -> f32 {
+    let time = match hour {
+        h if h < 8.0 => 1.1,   // first customers pay for the privilege
+        h if h >= 18.0 => 0.8, // end-of-day clearance
+        _ => 1.0,
+    };
+    let reputation = match standing {
+        CaptureStanding::Unseen => 1.0,
+        CaptureStanding::Noticed => 1.1,
+        CaptureStanding::Marked => 1.25,
+    };
+    time * reputation
+}
+
+/// Final price in coins, never below one
+pub fn adjusted_price(base: u32, multiplier: f32) -> u32 {
+    ((base as f32 * multiplier).round() as u32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::items::{load_items, ITEMS_PATH};
+
+    #[test]
+    fn bundled_traders_load() {
+        let items = load_items(ITEMS_PATH).unwrap();
+        let traders = load_traders(TRADERS_PATH, &items).expect("traders.ron should load with every item known");
+        assert!(!traders.is_empty());
+    }
+
+    #[test]
+    fn prices_follow_clock_and_reputation() {
+        assert_eq!(adjusted_price(10, price_multiplier(12.0, CaptureStanding::Unseen)), 10);
+        assert_eq!(adjusted_price(10, price_multiplier(19.0, CaptureStanding::Unseen)), 8);
+        assert_eq!(adjusted_price(10, price_multiplier(12.0, CaptureStanding::Marked)), 13);
+        assert_eq!(adjusted_price(1, price_multiplier(19.0, CaptureStanding::Unseen)), 1);
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const VOICES_PATH: &str = "assets/data/voices.ron";
+
+/// Babble lines per voice id, as paths under assets/
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Voices {
+    pub lines: HashMap<String, Vec<String>>,
+}
+
+impl Voices {
+    /// The line to play for one thing said; the same `key` always gets the same line
+    pub fn line(&self, voice: &str, key: &str) -> Option<&str> {
+        let lines = self.lines.get(voice).filter(|l| !l.is_empty())?;
+        let hash = key.bytes().fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize));
+        Some(&lines[hash % lines.len()])
+    }
+}
+
+pub fn load_voices(path: &str) -> Result<Voices> {
+    let s = fs::read_to_string(path).with_context(|| format!("Reading voices {path}"))?;
+    let lines: HashMap<String, Vec<String>> = ron::from_str(&s).context("Parsing RON voices")?;
+    for (voice, files) in &lines {
+        if files.is_empty() {
+            anyhow::bail!("Voice '{}' has no lines", voice);
+        }
+        if let Some(missing) = files.iter().find(|f| !Path::new("assets").join(f).exists()) {
+            anyhow::bail!("Voice '{}' uses missing line '{}'", voice, missing);
+        }
+    }
+    Ok(Voices { lines })
+}
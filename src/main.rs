@@ -1,5 +1,17 @@
+// Bevy systems take their resources and queries as parameters
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+// states, fields and helpers kept for missions that aren't built yet
+#![allow(dead_code)]
+
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
 
+mod config;
+mod core;
+mod data;
+mod states;
+mod systems;
+mod utils;
 mod narrative;
 mod endings;
 mod progression;
@@ -11,7 +23,8 @@ use narrative::{ActiveTimeline, load_timeline_from_file};
 use route_events::{StartRoute, EndingCompleted, FinalBellUnlocked};
 use progression::GameProgress;
 use escape_routes::{EscapeRoutePlugin, Player};
-use route_mapping::{route_timeline_path, route_result_ending};
+use route_mapping::{route_timeline_path_for, route_result_ending, route_unlocked};
+use states::GameState;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 enum GamePhase {
@@ -40,19 +53,60 @@ fn main() {
             }),
             ..default()
         }))
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .init_state::<GameState>()
         .insert_state(GamePhase::Menu)
         .insert_resource(GameProgress::default())
         .add_event::<StartRoute>()
         .add_event::<EndingCompleted>()
         .add_event::<FinalBellUnlocked>()
         .add_plugins(EscapeRoutePlugin)
-        .add_systems(Startup, setup_menu_camera)
+        // Game world, actors and mission flow
+        .add_plugins((
+            core::WorldSeedPlugin,
+            core::ClockPlugin,
+            core::WorldPlugin,
+            core::AppearancePlugin,
+            core::AnimationPlugin,
+            core::PlayerPlugin,
+            core::CameraPlugin,
+            core::NavigationPlugin,
+            core::LodPlugin,
+            core::NpcAiPlugin,
+            core::PerceptionPlugin,
+            core::ReactionsPlugin,
+            core::WardenPlugin,
+            core::HidingPlugin,
+        ))
+        .add_plugins((
+            core::SanityPlugin,
+            core::HallucinationPlugin,
+            core::DamagePlugin,
+            core::FoodPlugin,
+            core::ToolsPlugin,
+        ))
+        .add_plugins((
+            systems::UiPlugin,
+            systems::InventoryPlugin,
+            systems::InventoryUiPlugin,
+            systems::SaveGamePlugin,
+            systems::PerformancePlugin,
+            systems::SettingsPlugin,
+            systems::ControlsPlugin,
+            systems::CarsPlugin,
+            systems::CaughtPlugin,
+            systems::GameOverPlugin,
+            systems::DialoguePlugin,
+            systems::TradingPlugin,
+            systems::InteractPlugin,
+        ))
+        .add_systems(Startup, (setup_menu_camera, data::assets_loader::preload_assets))
         .add_systems(Startup, spawn_player)
-        // Menu input / movement
+        // Menu input / movement (only on the title screen, so WASD doesn't also steer the square in-game)
         .add_systems(Update, menu_input.run_if(in_state(GamePhase::Menu)))
-        .add_systems(Update, player_movement.run_if(in_state(GamePhase::Menu)))
+        .add_systems(Update, player_movement.run_if(in_state(GamePhase::Menu).and_then(in_state(GameState::Title))))
         // Start route from trigger
-        .add_systems(Update, on_start_route.run_if(in_state(GamePhase::Menu)))
+        .add_systems(Update, on_start_route.run_if(in_state(GamePhase::Menu).and_then(in_state(GameState::Title))))
         // Timeline playback
         .add_systems(Update, run_timeline.run_if(in_state(GamePhase::InTimeline)))
         .add_systems(Update, check_timeline_finished.run_if(in_state(GamePhase::InTimeline)))
@@ -124,11 +178,16 @@ fn on_start_route(
     mut ev: EventReader<StartRoute>,
     mut next: ResMut<NextState<GamePhase>>,
     mut commands: Commands,
+    gp: Res<GameProgress>,
 ) {
     for start in ev.read() {
         let route_id = start.route_id;
-        if let Some(path) = route_timeline_path(route_id) {
-            match load_timeline_from_file(path) {
+        if !route_unlocked(route_id, &gp) {
+            info!("Route {} is locked: Ethan's mind is still too whole for it", route_id);
+            continue;
+        }
+        if let Some(path) = route_timeline_path_for(route_id, &gp) {
+            match load_timeline_from_file(&path) {
                 Ok(timeline) => {
                     info!("Starting timeline for route {} -> {}", route_id, path);
                     info!("Timeline: {}", timeline.title);
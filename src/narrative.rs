@@ -18,6 +18,36 @@ pub struct Frame {
     pub lighting: String,
     #[serde(default)]
     pub notes: String,
+    /// Character clips to play while this frame is up
+    #[serde(default)]
+    pub animate: Vec<FrameClip>,
+    /// Scripted camera placement; without one the player keeps the camera
+    #[serde(default)]
+    pub shot: Option<FrameShot>,
+    /// Change to Ethan's sanity as this frame comes up (negative hurts)
+    #[serde(default)]
+    pub sanity: f32,
+    /// `HallucinationDef` ids to stage as this frame comes up
+    #[serde(default)]
+    pub hallucinate: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FrameClip {
+    /// `Animated::actor` name, e.g. "ethan" or "warden"
+    pub actor: String,
+    pub clip: String,
+}
+
+/// Offsets in metres from where Ethan last stood, turned to the camera's
+/// heading: +Z is behind him, +X to his right
+#[derive(Debug, Deserialize, Clone)]
+pub struct FrameShot {
+    pub eye: (f32, f32, f32),
+    pub look_at: (f32, f32, f32),
+    /// Hard cut instead of blending from the previous view
+    #[serde(default)]
+    pub cut: bool,
 }
 
 impl Timeline {
@@ -2,10 +2,24 @@ use std::collections::HashSet;
 use bevy::prelude::*;
 use crate::endings::GameEnding;
 
+/// How the school regards Ethan after repeated captures. Routes can branch on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureStanding {
+    Unseen,
+    Noticed,
+    Marked,
+}
+
+/// Sanity Ethan must have sunk below (at some point) for his mind to count as fractured
+pub const FRACTURED_SANITY: f32 = 0.2;
+
 #[derive(Debug, Resource)]
 pub struct GameProgress {
     pub completed: HashSet<GameEnding>,
     pub final_bell_unlocked: bool,
+    pub times_caught: u32,
+    /// Lowest `Sanity` reached in any playthrough so far
+    pub lowest_sanity: f32,
 }
 
 impl Default for GameProgress {
@@ -13,6 +27,8 @@ impl Default for GameProgress {
         Self {
             completed: HashSet::new(),
             final_bell_unlocked: false,
+            times_caught: 0,
+            lowest_sanity: 1.0,
         }
     }
 }
@@ -36,6 +52,25 @@ impl GameProgress {
         required.iter().all(|e| self.completed.contains(e))
     }
 
+    /// Count a capture by the Warden; returns the new total
+    pub fn record_capture(&mut self) -> u32 {
+        self.times_caught += 1;
+        self.times_caught
+    }
+
+    pub fn capture_standing(&self) -> CaptureStanding {
+        match self.times_caught {
+            0 => CaptureStanding::Unseen,
+            1..=2 => CaptureStanding::Noticed,
+            _ => CaptureStanding::Marked,
+        }
+    }
+
+    /// Ethan has come close enough to breaking to reach The Fragmented Mind
+    pub fn mind_fractured(&self) -> bool {
+        self.lowest_sanity <= FRACTURED_SANITY
+    }
+
     fn update_unlock(&mut self) {
         if self.all_primary_completed() {
             self.final_bell_unlocked = true;
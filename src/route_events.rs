@@ -12,4 +12,10 @@ pub struct EndingCompleted {
 }
 
 #[derive(Event)]
-pub struct FinalBellUnlocked;
\ No newline at end of file
+pub struct FinalBellUnlocked;
+
+/// Sent each time the Warden catches Ethan, with the running total
+#[derive(Event)]
+pub struct PlayerCaught {
+    pub times: u32,
+}
@@ -1,4 +1,6 @@
+use std::path::Path;
 use crate::endings::GameEnding;
+use crate::progression::{CaptureStanding, GameProgress};
 
 pub fn route_timeline_path(id: usize) -> Option<&'static str> {
     match id {
@@ -12,6 +14,37 @@ pub fn route_timeline_path(id: usize) -> Option<&'static str> {
     }
 }
 
+/// Whether the player's history lets them take route `id`. The Fragmented
+/// Mind (route 4) stays closed until Ethan's sanity has broken.
+pub fn route_unlocked(id: usize, progress: &GameProgress) -> bool {
+    match route_result_ending(id) {
+        Some(GameEnding::FragmentedMind) => progress.mind_fractured(),
+        _ => true,
+    }
+}
+
+/// Timeline for a route given the player's history. An Ethan whose mind has
+/// fractured (see `GameProgress::mind_fractured`) gets `<route>_fractured.yaml`,
+/// and a Marked one (caught three or more times) `<route>_marked.yaml`, when
+/// those variants exist; fractured wins if both apply.
+pub fn route_timeline_path_for(id: usize, progress: &GameProgress) -> Option<String> {
+    let base = route_timeline_path(id)?;
+    let mut variants = Vec::new();
+    if progress.mind_fractured() {
+        variants.push("_fractured.yaml");
+    }
+    if progress.capture_standing() == CaptureStanding::Marked {
+        variants.push("_marked.yaml");
+    }
+    for suffix in variants {
+        let variant = base.replace(".yaml", suffix);
+        if Path::new(&variant).exists() {
+            return Some(variant);
+        }
+    }
+    Some(base.to_string())
+}
+
 pub fn route_result_ending(id: usize) -> Option<GameEnding> {
     use GameEnding::*;
     match id {
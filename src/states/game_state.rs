@@ -7,6 +7,7 @@ pub enum GameState {
     IntroCutscene,
     Mission1,
     Caught,
+    GameOver,
     MissionComplete,
     OpenWorld,
     Paused,
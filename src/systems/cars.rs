@@ -1,9 +1,20 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 use crate::data::assets_loader::Handles;
-use crate::config::{CAR_SPAWN_RADIUS, SIMULATION_RADIUS};
+use crate::config::CAR_SPAWN_RADIUS;
+use crate::core::lod::{CoarseSim, FullSim, SimLod, SimTier, SimTierChanged};
+use crate::core::damage::{DamageEvent, DamageSource};
 use crate::core::player::Ethan;
 use crate::core::world::MissionEntity;
+use crate::core::seed::TrafficRng;
+use rand::Rng;
+
+/// Cars in the middle distance move in steps this long
+const COARSE_STEP_SECS: f32 = 0.2;
+/// How close a car's centre has to come to Ethan to hit him
+const CAR_HIT_RADIUS: f32 = 1.5;
+/// Health lost per m/s of the car's speed
+const CAR_DAMAGE_PER_SPEED: f32 = 3.0;
 
 #[derive(Component)]
 pub struct Car {
@@ -25,11 +36,13 @@ impl Plugin for CarsPlugin {
         app.init_resource::<CarSpawner>()
            .add_systems(OnEnter(crate::states::GameState::Mission1), init_spawner)
            .add_systems(Update, car_spawn_system.run_if(in_state(crate::states::GameState::Mission1)))
-           .add_systems(Update, car_ai_system.run_if(in_state(crate::states::GameState::Mission1)));
+           .add_systems(Update, (car_ai_system, car_hit_system, retire_dormant_cars).chain().run_if(in_state(crate::states::GameState::Mission1)));
     }
 }
 
 fn init_spawner(mut spawner: ResMut<CarSpawner>) {
+    // Mission1 is re-entered on retry
+    spawner.spawn_points.clear();
     for i in -3..=3 {
         let offset = i as f32 * 40.0;
         spawner.spawn_points.push(Vec3::new(-380.0, 0.5, offset));
@@ -44,9 +57,11 @@ fn car_spawn_system(
     mut commands: Commands,
     time: Res<Time>,
     mut spawner: ResMut<CarSpawner>,
+    mut meshes: ResMut<Assets<Mesh>>,
     player_q: Query<&Transform, With<Ethan>>,
     handles: Res<Handles>,
     existing: Query<&Car>,
+    mut rng: ResMut<TrafficRng>,
 ) {
     let Ok(player_tf) = player_q.get_single() else { return };
     let player_pos = player_tf.translation;
@@ -62,24 +77,26 @@ fn car_spawn_system(
         if dist < CAR_SPAWN_RADIUS && dist > 10.0 {
             // avoid spawning in front of player roughly
             let forward = player_tf.forward();
-            let to_spawn = (spawn - player_pos).normalize();
+            let to_spawn = (*spawn - player_pos).normalize();
             if forward.dot(to_spawn) < -0.15 {
                 let dir = if spawn.x.abs() > spawn.z.abs() { Vec3::new(-spawn.x.signum(), 0.0, 0.0) } else { Vec3::new(0.0, 0.0, -spawn.z.signum()) };
                 // spawn Kenney car model if available else box
                 if let Some(car_scene) = &handles.kenney_car {
                     commands.spawn((
                         SceneBundle { scene: car_scene.clone(), transform: Transform::from_translation(*spawn).with_scale(Vec3::splat(0.7)), ..default() },
-                        Car { speed: 6.0 + rand::random::<f32>() * 3.0, direction: dir },
+                        Car { speed: 6.0 + rng.0.gen::<f32>() * 3.0, direction: dir },
                         RigidBody::KinematicPositionBased,
                         Collider::cuboid(0.8, 0.4, 1.6),
+                        SimLod::default(),
                         MissionEntity,
                     ));
                 } else {
                     commands.spawn((
-                        PbrBundle { mesh: Mesh::from(shape::Box::new(1.6, 0.8, 3.2)).into(), material: Default::default(), transform: Transform::from_translation(*spawn), ..default() },
-                        Car { speed: 6.0 + rand::random::<f32>() * 3.0, direction: dir },
+                        PbrBundle { mesh: meshes.add(Cuboid::new(1.6, 0.8, 3.2)), material: Default::default(), transform: Transform::from_translation(*spawn), ..default() },
+                        Car { speed: 6.0 + rng.0.gen::<f32>() * 3.0, direction: dir },
                         RigidBody::KinematicPositionBased,
                         Collider::cuboid(0.8, 0.4, 1.6),
+                        SimLod::default(),
                         MissionEntity,
                     ));
                 }
@@ -91,24 +108,40 @@ fn car_spawn_system(
 
 fn car_ai_system(
     time: Res<Time>,
-    mut cars: Query<(&mut Transform, &Car)>,
+    mut cars: Query<(&mut Transform, &Car, &mut SimLod), Or<(With<FullSim>, With<CoarseSim>)>>,
+) {
+    for (mut tf, car, mut lod) in &mut cars {
+        lod.pending += time.delta_seconds();
+        if lod.tier == SimTier::Coarse && lod.pending < COARSE_STEP_SECS {
+            continue;
+        }
+        let dt = std::mem::take(&mut lod.pending);
+        let mv = car.direction.normalize() * car.speed * dt;
+        tf.translation += mv;
+    }
+}
+
+/// Only fully simulated cars can be near enough to hit Ethan
+fn car_hit_system(
     player_q: Query<&Transform, With<Ethan>>,
+    cars: Query<(&Transform, &Car), (With<FullSim>, Without<Ethan>)>,
+    mut damage: EventWriter<DamageEvent>,
 ) {
-    let Ok(player) = player_q.get_single() else { return };
-    let player_pos = player.translation;
+    let Ok(player_tf) = player_q.get_single() else { return };
+    for (tf, car) in &cars {
+        let offset = (tf.translation - player_tf.translation) * Vec3::new(1.0, 0.0, 1.0);
+        if car.speed > 0.5 && offset.length() < CAR_HIT_RADIUS {
+            damage.send(DamageEvent { source: DamageSource::Car, amount: car.speed * CAR_DAMAGE_PER_SPEED });
+        }
+    }
+}
 
-    for (mut tf, car) in &mut cars {
-        let d = tf.translation.distance(player_pos);
-        if d > SIMULATION_RADIUS { continue; } // skip far-away cars
-        let mv = car.direction.normalize() * car.speed * time.delta_seconds();
-        tf.translation += mv;
-        // small randomness
-        if rand::random::<f32>() < 0.0015 {
-            let angle = (rand::random::<f32>() - 0.5) * 0.6;
-            let rot = Quat::from_axis_angle(Vec3::Y, angle);
-            let mut dir = car.direction;
-            dir = rot.mul_vec3(dir);
-            tf.rotation = Quat::from_rotation_y(0.0);
+/// A car that has driven out of simulation range is gone for good; the
+/// spawner keeps traffic topped up around the player
+fn retire_dormant_cars(mut commands: Commands, mut events: EventReader<SimTierChanged>, cars: Query<(), With<Car>>) {
+    for ev in events.read() {
+        if ev.to == SimTier::Dormant && cars.contains(ev.entity) {
+            commands.entity(ev.entity).despawn_recursive();
         }
     }
 }
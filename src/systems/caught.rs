@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use crate::states::GameState;
+use crate::config::CHECKPOINT_FILE;
+use crate::core::player::Ethan;
+use crate::core::warden::TheWarden;
+use crate::narrative::{load_timeline_from_file, ActiveTimeline};
+use crate::progression::GameProgress;
+use crate::route_events::PlayerCaught;
+use crate::systems::savegame::{save_exists, LoadGameRequest, SaveGameRequest};
+
+const CAUGHT_TIMELINE: &str = "assets/narrative/caught.yaml";
+/// Fade length used when the caught timeline can't be loaded
+const FALLBACK_FADE_SECS: f32 = 3.0;
+
+/// Tracks the mission checkpoint: written once when the mission starts,
+/// restored when the player retries after being caught.
+#[derive(Resource, Default)]
+pub struct CheckpointState {
+    pub made: bool,
+    pub retry_pending: bool,
+}
+
+#[derive(Resource)]
+struct CaughtSequence {
+    elapsed: f32,
+    duration: f32,
+    prompt_shown: bool,
+}
+
+#[derive(Component)]
+struct CaughtOverlay;
+
+#[derive(Component)]
+struct CaughtCaption;
+
+pub struct CaughtPlugin;
+
+impl Plugin for CaughtPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameProgress>()
+           .init_resource::<CheckpointState>()
+           .add_event::<PlayerCaught>()
+           .add_systems(Update, checkpoint_system.run_if(in_state(GameState::Mission1)))
+           .add_systems(OnEnter(GameState::Caught), start_caught_sequence)
+           .add_systems(Update, (caught_sequence_system, caught_input_system).chain().run_if(in_state(GameState::Caught)))
+           .add_systems(OnExit(GameState::Caught), cleanup_caught);
+    }
+}
+
+/// First frame with a player in the mission: save a checkpoint, or restore it after a retry
+fn checkpoint_system(
+    mut state: ResMut<CheckpointState>,
+    player_q: Query<(), With<Ethan>>,
+    mut save_ev: EventWriter<SaveGameRequest>,
+    mut load_ev: EventWriter<LoadGameRequest>,
+) {
+    if player_q.is_empty() {
+        return;
+    }
+    if state.retry_pending {
+        state.retry_pending = false;
+        load_ev.send(LoadGameRequest { slot: CHECKPOINT_FILE.to_string() });
+        info!("Retrying from checkpoint.");
+    } else if !state.made {
+        state.made = true;
+        save_ev.send(SaveGameRequest { slot: CHECKPOINT_FILE.to_string() });
+    }
+}
+
+fn start_caught_sequence(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut progress: ResMut<GameProgress>,
+    mut caught_ev: EventWriter<PlayerCaught>,
+) {
+    let times = progress.record_capture();
+    caught_ev.send(PlayerCaught { times });
+    info!("Caught by the Warden ({} time(s), standing {:?})", times, progress.capture_standing());
+
+    let duration = match load_timeline_from_file(CAUGHT_TIMELINE) {
+        Ok(timeline) => {
+            let total = (0..timeline.frames.len()).map(|i| timeline.frame_duration_secs(i)).sum();
+            commands.insert_resource(ActiveTimeline::from_timeline(&timeline));
+            total
+        }
+        Err(err) => {
+            error!("Failed to load caught timeline: {:?}", err);
+            FALLBACK_FADE_SECS
+        }
+    };
+    commands.insert_resource(CaughtSequence { elapsed: 0.0, duration, prompt_shown: false });
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0), height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::FlexEnd,
+                padding: UiRect::bottom(Val::Px(48.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.0)),
+            ..default()
+        },
+        CaughtOverlay,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            TextBundle {
+                text: Text::from_section("", TextStyle { font: asset_server.load("fonts/FiraSans-Bold.ttf"), font_size: 28.0, color: Color::WHITE }),
+                ..default()
+            },
+            CaughtCaption,
+        ));
+    });
+}
+
+fn caught_sequence_system(
+    time: Res<Time>,
+    progress: Res<GameProgress>,
+    active: Option<ResMut<ActiveTimeline>>,
+    mut seq: ResMut<CaughtSequence>,
+    mut overlay_q: Query<&mut BackgroundColor, With<CaughtOverlay>>,
+    mut caption_q: Query<&mut Text, With<CaughtCaption>>,
+) {
+    seq.elapsed += time.delta_seconds();
+    let fade = (seq.elapsed / seq.duration.max(0.01)).clamp(0.0, 1.0);
+    for mut bg in &mut overlay_q {
+        bg.0 = Color::srgba(0.0, 0.0, 0.0, fade);
+    }
+
+    let finished = match active {
+        Some(mut active) => {
+            let just_advanced = active.tick_and_maybe_advance(time.delta());
+            if let Some(frame) = active.current_frame() {
+                if just_advanced || seq.elapsed <= time.delta_seconds() {
+                    info!("Caught {:02}: {} | Light {} | Notes {}", frame.index, frame.camera, frame.lighting, frame.notes);
+                    for mut text in &mut caption_q {
+                        text.sections[0].value = frame.camera.clone();
+                    }
+                }
+            }
+            active.finished
+        }
+        None => seq.elapsed >= seq.duration,
+    };
+
+    if finished && !seq.prompt_shown {
+        seq.prompt_shown = true;
+        for mut text in &mut caption_q {
+            text.sections[0].value = format!(
+                "CAUGHT ({}x)\n[R] Retry from checkpoint    [Esc] Title",
+                progress.times_caught
+            );
+        }
+    }
+}
+
+fn caught_input_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    seq: Res<CaughtSequence>,
+    mut checkpoint: ResMut<CheckpointState>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !seq.prompt_shown {
+        return;
+    }
+    if keys.just_pressed(KeyCode::KeyR) {
+        if save_exists(CHECKPOINT_FILE) {
+            checkpoint.retry_pending = true;
+        } else {
+            warn!("No checkpoint found; restarting the mission fresh.");
+        }
+        next_state.set(GameState::Mission1);
+    } else if keys.just_pressed(KeyCode::Escape) {
+        // a new run from the title writes a fresh checkpoint
+        checkpoint.made = false;
+        next_state.set(GameState::Title);
+    }
+}
+
+/// Ethan and the Warden stay on stage for the caught timeline's cues and
+/// shots; Mission1 respawns them when it is re-entered
+fn cleanup_caught(
+    mut commands: Commands,
+    overlay_q: Query<Entity, With<CaughtOverlay>>,
+    actors: Query<Entity, Or<(With<Ethan>, With<TheWarden>)>>,
+) {
+    for e in overlay_q.iter().chain(&actors) {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.remove_resource::<ActiveTimeline>();
+    commands.remove_resource::<CaughtSequence>();
+}
@@ -4,6 +4,7 @@ use std::fs;
 use crate::utils::ensure_dir;
 
 #[derive(Resource, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Controls {
     pub move_forward: KeyCode,
     pub move_back: KeyCode,
@@ -12,18 +13,31 @@ pub struct Controls {
     pub interact: KeyCode,
     pub use_tool: KeyCode,
     pub open_inventory: KeyCode,
+    pub hold_breath: KeyCode,
+    pub prank: KeyCode,
+    pub sprint: KeyCode,
+    pub crouch: KeyCode,
+    pub camera_toggle: KeyCode,
+    pub eat: KeyCode,
 }
 
 impl Default for Controls {
     fn default() -> Self {
         Self {
-            move_forward: KeyCode::W,
-            move_back: KeyCode::S,
-            move_left: KeyCode::A,
-            move_right: KeyCode::D,
-            interact: KeyCode::E,
+            move_forward: KeyCode::KeyW,
+            move_back: KeyCode::KeyS,
+            move_left: KeyCode::KeyA,
+            move_right: KeyCode::KeyD,
+            interact: KeyCode::KeyE,
             use_tool: KeyCode::Space,
-            open_inventory: KeyCode::I,
+            open_inventory: KeyCode::KeyI,
+            hold_breath: KeyCode::ShiftLeft,
+            prank: KeyCode::KeyG,
+            // shares the key with hold_breath, which only applies while hidden
+            sprint: KeyCode::ShiftLeft,
+            crouch: KeyCode::KeyC,
+            camera_toggle: KeyCode::KeyV,
+            eat: KeyCode::KeyF,
         }
     }
 }
@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::core::appearance::{Appearance, NpcName};
+use crate::core::hiding::Hidden;
+use crate::core::navigation::NavAgent;
+use crate::core::npc_ai::{InConversation, Npc, NpcCategory, NpcId, NpcState};
+use crate::core::player::Ethan;
+use crate::data::dialogue::{load_dialogue_library, DialogueAction, DialogueContext, DialogueLibrary, DialogueTree, NpcMemory, DIALOGUE_DIR};
+use crate::progression::GameProgress;
+use crate::states::GameState;
+use crate::systems::controls::Controls;
+use crate::systems::interact::{Interact, InteractSet, InteractTarget};
+use crate::data::items::{init_item_db, ItemDb};
+use crate::data::voices::Voices;
+use crate::systems::inventory::Inventory;
+use crate::systems::trading::OpenTrade;
+
+/// How close the player must stand to start talking
+pub const TALK_RANGE: f32 = 2.0;
+
+/// Per-NPC memory of past conversations, keyed by `NpcId`. Saved with the game.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DialogueMemory {
+    pub npcs: HashMap<u32, NpcMemory>,
+}
+
+/// The conversation in progress, if any
+#[derive(Resource, Debug)]
+pub struct ActiveDialogue {
+    pub npc: Entity,
+    pub npc_id: u32,
+    pub category: NpcCategory,
+    pub node: String,
+    /// Indices of the current node's choices whose conditions hold
+    pub offered: Vec<usize>,
+    pub selected: usize,
+}
+
+#[derive(Component)]
+struct DialoguePanel;
+
+#[derive(Component)]
+struct DialogueText;
+
+/// The line the speaker is babbling right now
+#[derive(Component)]
+struct DialogueVoice;
+
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        let items = init_item_db(app);
+        app.insert_resource(load_dialogue_library(DIALOGUE_DIR, &items))
+           .init_resource::<DialogueMemory>()
+           .add_systems(Update, (
+               start_dialogue_system,
+               dialogue_input_system,
+               dialogue_voice_system,
+               dialogue_ui_system,
+           ).chain().after(InteractSet).run_if(in_state(GameState::Mission1).or_else(in_state(GameState::OpenWorld))))
+           .add_systems(OnExit(GameState::OpenWorld), abandon_dialogue)
+           .add_systems(OnEnter(GameState::Caught), abandon_dialogue)
+           .add_systems(OnEnter(GameState::GameOver), abandon_dialogue)
+           .add_systems(OnEnter(GameState::MissionComplete), abandon_dialogue)
+           .add_systems(OnEnter(GameState::Title), abandon_dialogue);
+    }
+}
+
+fn context<'a>(inventory: &'a Inventory, items: &'a ItemDb, progress: Option<&GameProgress>, memory: &'a NpcMemory) -> DialogueContext<'a> {
+    DialogueContext { inventory, items, times_caught: progress.map_or(0, |p| p.times_caught), memory }
+}
+
+/// Move the conversation to `node`, remember it, and work out which choices to offer
+fn enter_node(active: &mut ActiveDialogue, node: &str, tree: &DialogueTree, memory: &mut NpcMemory, inventory: &Inventory, items: &ItemDb, progress: Option<&GameProgress>) {
+    active.node = node.to_string();
+    active.selected = 0;
+    memory.visited.insert(node.to_string());
+    let ctx = context(inventory, items, progress, memory);
+    active.offered = tree.nodes[node]
+        .choices
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.available(&ctx))
+        .map(|(i, _)| i)
+        .collect();
+}
+
+fn start_dialogue_system(
+    mut commands: Commands,
+    mut interacts: EventReader<Interact>,
+    library: Res<DialogueLibrary>,
+    inventory: Res<Inventory>,
+    items: Res<ItemDb>,
+    progress: Option<Res<GameProgress>>,
+    mut memory: ResMut<DialogueMemory>,
+    player_q: Query<&Transform, (With<Ethan>, Without<Hidden>)>,
+    mut npc_q: Query<(&NpcId, &mut Npc, &mut Transform, &mut NavAgent), Without<Ethan>>,
+) {
+    let Some(entity) = interacts.read().filter_map(|ev| match ev.target {
+        InteractTarget::Npc(npc) => Some(npc),
+        _ => None,
+    }).last() else { return };
+    let Ok(player_tf) = player_q.get_single() else { return };
+    let player_pos = player_tf.translation;
+    let Ok((id, mut npc, mut tf, mut agent)) = npc_q.get_mut(entity) else { return };
+    let Some(tree) = library.trees.get(&npc.category) else {
+        warn!("No dialogue for {:?}", npc.category);
+        return;
+    };
+
+    // stop and turn to face the player
+    agent.stop();
+    npc.state = NpcState::Interacting;
+    let to_player = Vec3::new(player_pos.x - tf.translation.x, 0.0, player_pos.z - tf.translation.z);
+    if to_player.length_squared() > 0.01 {
+        tf.look_to(-to_player, Vec3::Y);
+    }
+    commands.entity(entity).insert(InConversation);
+
+    let npc_memory = memory.npcs.entry(id.0).or_default();
+    let entry = tree.entry_node(npc_memory).to_string();
+    let mut dialogue = ActiveDialogue { npc: entity, npc_id: id.0, category: npc.category.clone(), node: String::new(), offered: Vec::new(), selected: 0 };
+    enter_node(&mut dialogue, &entry, tree, npc_memory, &inventory, &items, progress.as_deref());
+    commands.insert_resource(dialogue);
+}
+
+fn dialogue_input_system(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    library: Res<DialogueLibrary>,
+    inventory: Res<Inventory>,
+    items: Res<ItemDb>,
+    progress: Option<Res<GameProgress>>,
+    active: Option<ResMut<ActiveDialogue>>,
+    mut memory: ResMut<DialogueMemory>,
+    mut npc_q: Query<&mut Npc>,
+    mut trade_ev: EventWriter<OpenTrade>,
+) {
+    let Some(mut active) = active else { return };
+    if !active.is_added() && keyboard.just_pressed(KeyCode::Escape) {
+        end_dialogue(&mut commands, &active, &mut memory, &mut npc_q);
+        return;
+    }
+    let Some(tree) = library.trees.get(&active.category) else { return };
+
+    let count = active.offered.len();
+    if count > 0 {
+        if keyboard.just_pressed(controls.move_back) || keyboard.just_pressed(KeyCode::ArrowDown) {
+            active.selected = (active.selected + 1) % count;
+        }
+        if keyboard.just_pressed(controls.move_forward) || keyboard.just_pressed(KeyCode::ArrowUp) {
+            active.selected = (active.selected + count - 1) % count;
+        }
+    }
+
+    // number keys pick a choice directly; interact / enter confirms the highlighted one
+    const NUMBER_KEYS: [KeyCode; 9] = [
+        KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5,
+        KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    ];
+    let picked = NUMBER_KEYS.iter().position(|k| keyboard.just_pressed(*k)).filter(|i| *i < count);
+    // the key press that opened the conversation must not also confirm it
+    let confirmed = !active.is_added() && (keyboard.just_pressed(controls.interact) || keyboard.just_pressed(KeyCode::Enter));
+    if picked.is_none() && !confirmed {
+        return;
+    }
+
+    let (next, action) = match picked.or(confirmed.then_some(active.selected)) {
+        Some(i) if i < count => {
+            let choice = &tree.nodes[&active.node].choices[active.offered[i]];
+            (choice.next.clone(), choice.action)
+        }
+        // nothing to choose: any confirm closes the conversation
+        _ => (None, None),
+    };
+    if action == Some(DialogueAction::Trade) {
+        end_dialogue(&mut commands, &active, &mut memory, &mut npc_q);
+        trade_ev.send(OpenTrade { npc: active.npc });
+        return;
+    }
+    match next {
+        Some(node) => {
+            let npc_memory = memory.npcs.entry(active.npc_id).or_default();
+            enter_node(&mut active, &node, tree, npc_memory, &inventory, &items, progress.as_deref());
+        }
+        None => end_dialogue(&mut commands, &active, &mut memory, &mut npc_q),
+    }
+}
+
+fn end_dialogue(commands: &mut Commands, active: &ActiveDialogue, memory: &mut DialogueMemory, npc_q: &mut Query<&mut Npc>) {
+    memory.npcs.entry(active.npc_id).or_default().times_talked += 1;
+    if let Some(mut entity) = commands.get_entity(active.npc) {
+        entity.remove::<InConversation>();
+    }
+    if let Ok(mut npc) = npc_q.get_mut(active.npc) {
+        npc.state = NpcState::Idle;
+        npc.timer = 1.0;
+    }
+    commands.remove_resource::<ActiveDialogue>();
+}
+
+/// Each node the NPC speaks gets a line in their archetype's voice, cutting
+/// off the last one. Nodes spoken for someone else stay silent.
+fn dialogue_voice_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    library: Res<DialogueLibrary>,
+    voices: Option<Res<Voices>>,
+    active: Option<Res<ActiveDialogue>>,
+    appearances: Query<&Appearance>,
+    playing: Query<Entity, With<DialogueVoice>>,
+    mut spoken: Local<Option<(Entity, String)>>,
+) {
+    let Some(active) = active else {
+        *spoken = None;
+        return;
+    };
+    let said = (active.npc, active.node.clone());
+    if spoken.as_ref() == Some(&said) {
+        return;
+    }
+    *spoken = Some(said);
+
+    let Some(node) = library.trees.get(&active.category).and_then(|t| t.nodes.get(&active.node)) else { return };
+    if node.speaker.is_some() {
+        return;
+    }
+    let Some(voices) = voices else { return };
+    let Ok(appearance) = appearances.get(active.npc) else { return };
+    let Some(line) = voices.line(&appearance.voice, &format!("{}/{}", active.npc_id, active.node)) else { return };
+    for e in &playing {
+        commands.entity(e).despawn();
+    }
+    commands.spawn((AudioBundle { source: asset_server.load(line.to_string()), settings: PlaybackSettings::DESPAWN }, DialogueVoice));
+}
+
+/// Bottom-of-screen panel: speaker, lines, then the numbered choices
+fn dialogue_ui_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    library: Res<DialogueLibrary>,
+    active: Option<Res<ActiveDialogue>>,
+    names: Query<&NpcName>,
+    panel_q: Query<Entity, With<DialoguePanel>>,
+    mut text_q: Query<&mut Text, With<DialogueText>>,
+) {
+    let Some(active) = active else {
+        for e in &panel_q {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    };
+    if !active.is_changed() {
+        return;
+    }
+    let Some(node) = library.trees.get(&active.category).and_then(|t| t.nodes.get(&active.node).map(|n| (t, n))) else { return };
+    let (tree, node) = node;
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    // a node can speak for someone else (Ethan); otherwise the NPC's own name if it has one
+    let speaker = node.speaker.clone()
+        .or_else(|| names.get(active.npc).ok().map(|n| format!("{} ({})", n.0, tree.speaker)))
+        .unwrap_or_else(|| tree.speaker.clone());
+    let mut body = node.lines.join("\n");
+    if active.offered.is_empty() {
+        body.push_str("\n\n[E] ...");
+    }
+    for (n, &i) in active.offered.iter().enumerate() {
+        let marker = if n == active.selected { ">" } else { " " };
+        body.push_str(&format!("\n{} {}. {}", marker, n + 1, node.choices[i].text));
+    }
+    let sections = vec![
+        TextSection::new(format!("{}\n", speaker), TextStyle { font: font.clone(), font_size: 22.0, color: Color::srgb(1.0, 0.85, 0.4) }),
+        TextSection::new(body, TextStyle { font, font_size: 20.0, color: Color::WHITE }),
+    ];
+
+    if let Ok(mut text) = text_q.get_single_mut() {
+        text.sections = sections;
+        return;
+    }
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(15.0),
+                bottom: Val::Px(24.0),
+                width: Val::Percent(70.0), height: Val::Auto,
+                padding: UiRect::all(Val::Px(14.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.05, 0.05, 0.07, 0.85)),
+            ..default()
+        },
+        DialoguePanel,
+    ))
+    .with_children(|parent| {
+        parent.spawn((TextBundle { text: Text::from_sections(sections), ..default() }, DialogueText));
+    });
+}
+
+/// Leaving the open world, or the mission ending, mid-conversation drops it
+/// without counting it as a talk
+fn abandon_dialogue(
+    mut commands: Commands,
+    active: Option<Res<ActiveDialogue>>,
+    leftovers: Query<Entity, Or<(With<DialoguePanel>, With<DialogueVoice>)>>,
+) {
+    if let Some(active) = active {
+        if let Some(mut entity) = commands.get_entity(active.npc) {
+            entity.remove::<InConversation>();
+        }
+        commands.remove_resource::<ActiveDialogue>();
+    }
+    for e in &leftovers {
+        commands.entity(e).despawn_recursive();
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use crate::states::GameState;
+use crate::config::CHECKPOINT_FILE;
+use crate::core::damage::{DamageSource, PlayerDied};
+use crate::core::player::Ethan;
+use crate::core::warden::TheWarden;
+use crate::systems::caught::CheckpointState;
+use crate::systems::savegame::save_exists;
+
+/// Fade to black before the retry prompt comes up
+const FADE_SECS: f32 = 2.0;
+
+#[derive(Resource)]
+struct GameOverScreen {
+    elapsed: f32,
+    cause: Option<DamageSource>,
+    prompt_shown: bool,
+}
+
+#[derive(Component)]
+struct GameOverOverlay;
+
+#[derive(Component)]
+struct GameOverCaption;
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::GameOver), start_game_over)
+           .add_systems(Update, (game_over_fade_system, game_over_input_system).chain().run_if(in_state(GameState::GameOver)))
+           .add_systems(OnExit(GameState::GameOver), cleanup_game_over);
+    }
+}
+
+fn start_game_over(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut died: EventReader<PlayerDied>,
+    actors: Query<Entity, Or<(With<Ethan>, With<TheWarden>)>>,
+) {
+    let cause = died.read().last().map(|d| d.source);
+    info!("Game over ({})", cause.map_or("unknown", |c| c.label()));
+
+    // Mission1 respawns the player and Warden when it is re-entered
+    for e in &actors {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.insert_resource(GameOverScreen { elapsed: 0.0, cause, prompt_shown: false });
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0), height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.0)),
+            ..default()
+        },
+        GameOverOverlay,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            TextBundle {
+                text: Text::from_section("", TextStyle { font: asset_server.load("fonts/FiraSans-Bold.ttf"), font_size: 32.0, color: Color::WHITE }),
+                ..default()
+            },
+            GameOverCaption,
+        ));
+    });
+}
+
+fn game_over_fade_system(
+    time: Res<Time>,
+    mut screen: ResMut<GameOverScreen>,
+    mut overlay_q: Query<&mut BackgroundColor, With<GameOverOverlay>>,
+    mut caption_q: Query<&mut Text, With<GameOverCaption>>,
+) {
+    screen.elapsed += time.delta_seconds();
+    let fade = (screen.elapsed / FADE_SECS).clamp(0.0, 1.0);
+    for mut bg in &mut overlay_q {
+        bg.0 = Color::srgba(0.2 * (1.0 - fade), 0.0, 0.0, fade);
+    }
+
+    if fade >= 1.0 && !screen.prompt_shown {
+        screen.prompt_shown = true;
+        let cause = screen.cause.map_or("Unknown", |c| c.label());
+        for mut text in &mut caption_q {
+            text.sections[0].value = format!("YOU DIED\n{}\n\n[R] Retry from checkpoint    [Esc] Title", cause);
+        }
+    }
+}
+
+fn game_over_input_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    screen: Res<GameOverScreen>,
+    mut checkpoint: ResMut<CheckpointState>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !screen.prompt_shown {
+        return;
+    }
+    if keys.just_pressed(KeyCode::KeyR) {
+        if save_exists(CHECKPOINT_FILE) {
+            checkpoint.retry_pending = true;
+        } else {
+            warn!("No checkpoint found; restarting the mission fresh.");
+        }
+        next_state.set(GameState::Mission1);
+    } else if keys.just_pressed(KeyCode::Escape) {
+        // a new run from the title writes a fresh checkpoint
+        checkpoint.made = false;
+        next_state.set(GameState::Title);
+    }
+}
+
+fn cleanup_game_over(mut commands: Commands, overlay_q: Query<Entity, With<GameOverOverlay>>) {
+    for e in &overlay_q {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.remove_resource::<GameOverScreen>();
+}
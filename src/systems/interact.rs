@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+
+use crate::core::hiding::{Hidden, HidingSpot, USE_RANGE};
+use crate::core::npc_ai::Npc;
+use crate::core::player::Ethan;
+use crate::states::GameState;
+use crate::systems::controls::Controls;
+use crate::systems::dialogue::{ActiveDialogue, TALK_RANGE};
+use crate::systems::inventory_ui::InventoryScreen;
+use crate::systems::trading::ActiveTrade;
+
+/// What a press of the interact key was aimed at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractTarget {
+    /// Climb out of the hiding spot the player is in
+    LeaveSpot,
+    HidingSpot(Entity),
+    Npc(Entity),
+}
+
+/// Sent once per interact press, for the single nearest thing in reach
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Interact {
+    pub target: InteractTarget,
+}
+
+/// Systems reading `Interact` run after this set, in the same frame
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InteractSet;
+
+pub struct InteractPlugin;
+
+impl Plugin for InteractPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Interact>()
+           .add_systems(Update, interact_dispatch_system
+               .in_set(InteractSet)
+               .run_if(in_state(GameState::Mission1).or_else(in_state(GameState::OpenWorld))));
+    }
+}
+
+/// Route the interact key to the nearest hiding spot or NPC. An open
+/// conversation, stall or bag keeps the key for itself.
+fn interact_dispatch_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    dialogue: Option<Res<ActiveDialogue>>,
+    trade: Option<Res<ActiveTrade>>,
+    screen: Option<Res<InventoryScreen>>,
+    player_q: Query<(&Transform, Option<&Hidden>), With<Ethan>>,
+    spots: Query<(Entity, &GlobalTransform, &HidingSpot)>,
+    npcs: Query<(Entity, &Transform), (With<Npc>, Without<Ethan>)>,
+    mut events: EventWriter<Interact>,
+) {
+    if !keyboard.just_pressed(controls.interact) || dialogue.is_some() || trade.is_some() || screen.is_some_and(|s| s.open) {
+        return;
+    }
+    let Ok((tf, hidden)) = player_q.get_single() else { return };
+    if hidden.is_some() {
+        events.send(Interact { target: InteractTarget::LeaveSpot });
+        return;
+    }
+
+    let pos = tf.translation;
+    let spot = spots
+        .iter()
+        .filter(|(_, _, spot)| spot.occupant.is_none())
+        .map(|(e, gt, _)| (InteractTarget::HidingSpot(e), gt.translation().distance(pos)))
+        .filter(|(_, d)| *d < USE_RANGE);
+    let npc = npcs
+        .iter()
+        .map(|(e, npc_tf)| (InteractTarget::Npc(e), npc_tf.translation.distance(pos)))
+        .filter(|(_, d)| *d < TALK_RANGE);
+    if let Some((target, _)) = spot.chain(npc).min_by(|a, b| a.1.total_cmp(&b.1)) {
+        events.send(Interact { target });
+    }
+}
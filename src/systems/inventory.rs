@@ -1,66 +1,431 @@
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
-
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
-pub enum ItemType {
-    Torch,
-    Shovel,
-    Hoe,
-    Dirt,
-    Food,
-}
+use crate::data::items::{init_item_db, ItemDb, ItemDef};
+use crate::systems::dialogue::ActiveDialogue;
+use crate::systems::trading::ActiveTrade;
+
+/// What Ethan starts a new game with, by item id
+pub const STARTER_ITEMS: [&str; 3] = ["tool_torch", "tool_shovel", "tool_hoe"];
+/// Slots in the bag, hotbar included
+pub const INVENTORY_SLOTS: usize = 24;
+/// The first slots double as the hotbar, on the number keys
+pub const HOTBAR_SLOTS: usize = 6;
+/// Ethan has to step this far from something he dropped before he picks it up again
+const PICKUP_REARM_DISTANCE: f32 = 2.0;
+const PICKUP_RANGE: f32 = 1.2;
+/// A dropped item looks for the floor from this far above Ethan's feet down to
+/// this far below them (a step or a kerb edge in front of him)
+const DROP_RAY_UP: f32 = 0.5;
+const DROP_RAY_DOWN: f32 = 1.5;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ItemStack {
-    pub item: ItemType,
+    /// `ItemDef::id`
+    pub item: String,
     pub count: u32,
     pub durability: Option<i32>,
 }
 
-#[derive(Resource, Serialize, Deserialize, Default)]
+/// What Ethan carries: a fixed grid of slots, the first `HOTBAR_SLOTS` of
+/// which are the hotbar. The item in the selected hotbar slot is equipped.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
 pub struct Inventory {
-    pub slots: HashMap<ItemType, ItemStack>,
-    pub equipped: Option<ItemType>,
+    pub slots: Vec<Option<ItemStack>>,
+    /// Selected hotbar slot
+    pub hotbar: usize,
     pub mission_progress: u8,
 }
 
+impl Default for Inventory {
+    fn default() -> Self {
+        Self { slots: vec![None; INVENTORY_SLOTS], hotbar: 0, mission_progress: 0 }
+    }
+}
+
+impl Inventory {
+    pub fn stacks(&self) -> impl Iterator<Item = &ItemStack> {
+        self.slots.iter().flatten()
+    }
+
+    pub fn count(&self, id: &str) -> u32 {
+        self.stacks().filter(|s| s.item == id).map(|s| s.count).sum()
+    }
+
+    pub fn equipped(&self) -> Option<&ItemStack> {
+        self.slots.get(self.hotbar).and_then(|s| s.as_ref())
+    }
+
+    pub fn equipped_mut(&mut self) -> Option<&mut ItemStack> {
+        self.slots.get_mut(self.hotbar).and_then(|s| s.as_mut())
+    }
+
+    pub fn equipped_id(&self) -> Option<&str> {
+        self.equipped().map(|s| s.item.as_str())
+    }
+
+    /// Whether one more of `def` would fit
+    pub fn can_fit(&self, def: &ItemDef) -> bool {
+        self.slots.iter().any(|slot| match slot {
+            None => true,
+            Some(s) => s.item == def.id && s.count < def.stack_size,
+        })
+    }
+
+    /// Add one of `def`, false if there is no room
+    pub fn add(&mut self, def: &ItemDef) -> bool {
+        self.add_stack(def, 1, def.durability) == 0
+    }
+
+    /// Add `count` of `def`, topping up existing stacks before opening new
+    /// slots. Returns how many didn't fit.
+    pub fn add_stack(&mut self, def: &ItemDef, count: u32, durability: Option<i32>) -> u32 {
+        let mut left = count;
+        // worn tools don't merge; each keeps its own durability
+        if def.stack_size > 1 {
+            for stack in self.slots.iter_mut().flatten().filter(|s| s.item == def.id) {
+                let moved = left.min(def.stack_size.saturating_sub(stack.count));
+                stack.count += moved;
+                left -= moved;
+            }
+        }
+        for slot in self.slots.iter_mut().filter(|s| s.is_none()) {
+            if left == 0 {
+                break;
+            }
+            let moved = left.min(def.stack_size);
+            *slot = Some(ItemStack { item: def.id.clone(), count: moved, durability });
+            left -= moved;
+        }
+        left
+    }
+
+    /// Remove one of `id`, from the equipped slot if it holds some, else the
+    /// last stack of it. False if there is none.
+    pub fn take(&mut self, id: &str) -> bool {
+        let index = if self.equipped_id() == Some(id) {
+            Some(self.hotbar)
+        } else {
+            self.slots.iter().rposition(|s| s.as_ref().is_some_and(|s| s.item == id))
+        };
+        let Some(index) = index else { return false };
+        let slot = &mut self.slots[index];
+        if let Some(stack) = slot {
+            stack.count -= 1;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+        true
+    }
+
+    /// Drag the stack in `from` onto `to`: moves into an empty slot, merges
+    /// into the same item up to `stack_size` (the rest stays behind), and
+    /// swaps with anything else
+    pub fn move_stack(&mut self, from: usize, to: usize, items: &ItemDb) {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return;
+        }
+        let Some(moving) = self.slots[from].take() else { return };
+        match self.slots[to].take() {
+            None => self.slots[to] = Some(moving),
+            Some(mut target) if target.item == moving.item && target.durability.is_none() => {
+                let limit = items.get(&moving.item).map_or(1, |d| d.stack_size);
+                let moved = moving.count.min(limit.saturating_sub(target.count));
+                target.count += moved;
+                self.slots[to] = Some(target);
+                if moving.count > moved {
+                    self.slots[from] = Some(ItemStack { count: moving.count - moved, ..moving });
+                }
+            }
+            Some(other) => {
+                self.slots[to] = Some(moving);
+                self.slots[from] = Some(other);
+            }
+        }
+    }
+
+    /// Move half of the stack in `slot` (rounded down) to the first empty
+    /// slot. False for a single item or a full bag.
+    pub fn split(&mut self, slot: usize) -> bool {
+        let Some(free) = self.slots.iter().position(|s| s.is_none()) else { return false };
+        let Some(Some(stack)) = self.slots.get_mut(slot) else { return false };
+        if stack.count < 2 {
+            return false;
+        }
+        let half = stack.count / 2;
+        stack.count -= half;
+        let split = ItemStack { count: half, ..stack.clone() };
+        self.slots[free] = Some(split);
+        true
+    }
+
+    /// Empty `slot`, handing back what was in it
+    pub fn remove_slot(&mut self, slot: usize) -> Option<ItemStack> {
+        self.slots.get_mut(slot).and_then(|s| s.take())
+    }
+}
+
+/// Something lying in the world to be picked up
+#[derive(Component, Debug, Clone)]
+pub struct ItemPickup {
+    pub stack: ItemStack,
+    /// Dropped items wait until Ethan has stepped away
+    pub armed: bool,
+}
+
+impl ItemPickup {
+    pub fn new(item: &str) -> Self {
+        Self { stack: ItemStack { item: item.to_string(), count: 1, durability: None }, armed: true }
+    }
+}
+
+/// Throw the stack in `slot` on the ground in front of Ethan
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DropItem {
+    pub slot: usize,
+}
+
 pub struct InventoryPlugin;
 
 impl Plugin for InventoryPlugin {
     fn build(&self, app: &mut App) {
+        init_item_db(app);
         app.insert_resource(Inventory::default())
+           .add_event::<DropItem>()
            .add_systems(OnEnter(crate::states::GameState::Mission1), give_starter_items)
-           .add_systems(Update, pickup_food_system.run_if(in_state(crate::states::GameState::Mission1)));
+           .add_systems(Update, (hotbar_input_system, drop_item_system, pickup_system)
+               .run_if(in_state(crate::states::GameState::Mission1).or_else(in_state(crate::states::GameState::OpenWorld))));
     }
 }
 
-fn give_starter_items(mut inv: ResMut<Inventory>) {
-    if inv.slots.is_empty() {
-        inv.slots.insert(ItemType::Torch, ItemStack { item: ItemType::Torch, count: 1, durability: Some(100) });
-        inv.slots.insert(ItemType::Shovel, ItemStack { item: ItemType::Shovel, count: 1, durability: Some(40) });
-        inv.slots.insert(ItemType::Hoe, ItemStack { item: ItemType::Hoe, count: 1, durability: Some(40) });
+/// Starter items go on the hotbar after the first slot, which stays empty so
+/// Ethan starts with nothing in his hands
+fn give_starter_items(mut inv: ResMut<Inventory>, items: Res<ItemDb>) {
+    if inv.stacks().next().is_none() {
+        for (i, id) in STARTER_ITEMS.iter().enumerate() {
+            match items.get(id) {
+                Some(def) => inv.slots[i + 1] = Some(ItemStack { item: def.id.clone(), count: 1, durability: def.durability }),
+                None => error!("Starter item '{}' is not in the item database", id),
+            }
+        }
     }
 }
 
-#[derive(Component)]
-pub struct FoodPickup;
+/// Number keys pick the hotbar slot, except while they pick replies or wares
+fn hotbar_input_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    dialogue: Option<Res<ActiveDialogue>>,
+    trade: Option<Res<ActiveTrade>>,
+    mut inv: ResMut<Inventory>,
+) {
+    const KEYS: [KeyCode; HOTBAR_SLOTS] = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5, KeyCode::Digit6];
+    if dialogue.is_some() || trade.is_some() {
+        return;
+    }
+    if let Some(slot) = KEYS.iter().position(|k| keyboard.just_pressed(*k)) {
+        if inv.hotbar != slot {
+            inv.hotbar = slot;
+        }
+    }
+}
 
-fn pickup_food_system(
+fn drop_item_system(
     mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut mats: ResMut<Assets<StandardMaterial>>,
+    mut drops: EventReader<DropItem>,
+    items: Res<ItemDb>,
+    mut inv: ResMut<Inventory>,
+    rapier: Res<RapierContext>,
     player_q: Query<&Transform, With<crate::core::player::Ethan>>,
-    pickup_q: Query<(Entity, &Transform), With<FoodPickup>>,
+) {
+    let Ok(player_tf) = player_q.get_single() else {
+        drops.clear();
+        return;
+    };
+    for drop in drops.read() {
+        let Some(stack) = inv.remove_slot(drop.slot) else { continue };
+        // model front is +Z, i.e. the transform's back
+        let facing = Vec3::new(player_tf.back().x, 0.0, player_tf.back().z).normalize_or_zero();
+        let pos = floor_below(&rapier, player_tf.translation + facing * 0.8);
+        let model = items.get(&stack.item).and_then(|d| d.model.clone());
+        info!("Dropped {} x{}", stack.item, stack.count);
+        let pickup = ItemPickup { stack, armed: false };
+        match model {
+            Some(model) => commands.spawn((
+                SceneBundle {
+                    scene: asset_server.load(model),
+                    transform: Transform::from_translation(pos + Vec3::Y * 0.2).with_scale(Vec3::splat(0.6)),
+                    ..default()
+                },
+                pickup,
+                crate::core::world::MissionEntity,
+            )),
+            None => commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cuboid::from_length(0.3)),
+                    material: mats.add(StandardMaterial::from(Color::srgb(0.6, 0.55, 0.5))),
+                    transform: Transform::from_translation(pos + Vec3::Y * 0.15),
+                    ..default()
+                },
+                pickup,
+                crate::core::world::MissionEntity,
+            )),
+        };
+    }
+}
+
+/// The floor under `at`, which is about Ethan's feet: a ray from a little above
+/// finds the slab, stair or kerb there, or the height he is at if there's nothing
+fn floor_below(rapier: &RapierContext, at: Vec3) -> Vec3 {
+    let from = at + Vec3::Y * DROP_RAY_UP;
+    match rapier.cast_ray(from, Vec3::NEG_Y, DROP_RAY_UP + DROP_RAY_DOWN, true, QueryFilter::only_fixed()) {
+        Some((_, toi)) => from - Vec3::Y * toi,
+        None => at,
+    }
+}
+
+fn pickup_system(
+    mut commands: Commands,
+    player_q: Query<&Transform, With<crate::core::player::Ethan>>,
+    mut pickup_q: Query<(Entity, &Transform, &mut ItemPickup)>,
+    items: Res<ItemDb>,
     mut inv: ResMut<Inventory>,
 ) {
     let Ok(player_tf) = player_q.get_single() else { return };
 
-    for (e, tf) in &pickup_q {
-        if tf.translation.distance(player_tf.translation) < 1.2 {
-            inv.slots.entry(ItemType::Food)
-                .and_modify(|s| s.count += 1)
-                .or_insert(ItemStack { item: ItemType::Food, count: 1, durability: None });
+    for (e, tf, mut pickup) in &mut pickup_q {
+        let dist = tf.translation.distance(player_tf.translation);
+        if !pickup.armed {
+            pickup.armed = dist > PICKUP_REARM_DISTANCE;
+            continue;
+        }
+        if dist >= PICKUP_RANGE {
+            continue;
+        }
+        let Some(def) = items.get(&pickup.stack.item) else {
+            warn!("Pickup of unknown item '{}'", pickup.stack.item);
             commands.entity(e).despawn_recursive();
-            info!("Picked up food.");
+            continue;
+        };
+        let durability = pickup.stack.durability.or(def.durability);
+        let left = inv.add_stack(def, pickup.stack.count, durability);
+        if left == pickup.stack.count {
+            // no room; it stays on the ground
+            continue;
         }
+        info!("Picked up {} x{}.", def.name, pickup.stack.count - left);
+        if left == 0 {
+            commands.entity(e).despawn_recursive();
+        } else {
+            pickup.stack.count = left;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::items::{load_items, ITEMS_PATH};
+
+    fn db() -> ItemDb {
+        load_items(ITEMS_PATH).unwrap()
+    }
+
+    #[test]
+    fn stacks_fill_before_new_slots_open() {
+        let items = db();
+        let bomb = items.get("prank_stinkbomb").unwrap();
+        let mut inv = Inventory::default();
+        assert_eq!(inv.add_stack(bomb, bomb.stack_size + 1, None), 0);
+        assert_eq!(inv.stacks().count(), 2);
+        assert!(inv.add(bomb));
+        assert_eq!(inv.count("prank_stinkbomb"), bomb.stack_size + 2);
+        assert_eq!(inv.stacks().count(), 2, "the second stack is topped up first");
+    }
+
+    #[test]
+    fn tools_never_stack() {
+        let items = db();
+        let torch = items.get("tool_torch").unwrap();
+        let mut inv = Inventory::default();
+        assert!(inv.add(torch));
+        assert!(inv.add(torch));
+        assert_eq!(inv.stacks().count(), 2);
+        assert!(inv.stacks().all(|s| s.count == 1 && s.durability == torch.durability));
+    }
+
+    #[test]
+    fn a_full_bag_hands_back_the_overflow() {
+        let items = db();
+        let dirt = items.get("utility_dirt").unwrap();
+        let apple = items.get("food_apple").unwrap();
+        let mut inv = Inventory::default();
+        let capacity = dirt.stack_size * INVENTORY_SLOTS as u32;
+        assert_eq!(inv.add_stack(dirt, capacity + 5, None), 5);
+        assert!(!inv.can_fit(dirt));
+        assert!(!inv.can_fit(apple));
+        assert!(!inv.add(apple));
+        assert!(inv.take("utility_dirt"));
+        assert!(inv.can_fit(dirt));
+        assert!(!inv.can_fit(apple), "a part-used stack has no room for other items");
+    }
+
+    #[test]
+    fn moving_merges_swaps_and_leaves_the_remainder() {
+        let items = db();
+        let apple = items.get("food_apple").unwrap();
+        let mut inv = Inventory::default();
+        inv.slots[0] = Some(ItemStack { item: apple.id.clone(), count: apple.stack_size - 2, durability: None });
+        inv.slots[1] = Some(ItemStack { item: apple.id.clone(), count: 5, durability: None });
+        inv.move_stack(1, 0, &items);
+        assert_eq!(inv.slots[0].as_ref().unwrap().count, apple.stack_size);
+        assert_eq!(inv.slots[1].as_ref().unwrap().count, 3);
+
+        inv.slots[2] = Some(ItemStack { item: "tool_hoe".into(), count: 1, durability: Some(40) });
+        inv.move_stack(2, 1, &items);
+        assert_eq!(inv.slots[1].as_ref().unwrap().item, "tool_hoe");
+        assert_eq!(inv.slots[2].as_ref().unwrap().count, 3);
+
+        inv.move_stack(1, 10, &items);
+        assert!(inv.slots[1].is_none());
+        assert_eq!(inv.equipped_id(), Some("food_apple"));
+        inv.move_stack(10, INVENTORY_SLOTS, &items);
+        assert!(inv.slots[10].is_some(), "moving off the grid does nothing");
+    }
+
+    #[test]
+    fn splitting_needs_two_items_and_a_free_slot() {
+        let items = db();
+        let apple = items.get("food_apple").unwrap();
+        let mut inv = Inventory::default();
+        inv.add_stack(apple, 5, None);
+        assert!(inv.split(0));
+        assert_eq!(inv.slots[0].as_ref().unwrap().count, 3);
+        assert_eq!(inv.slots[1].as_ref().unwrap().count, 2);
+        inv.take("food_apple");
+        inv.take("food_apple");
+        assert!(!inv.split(0), "one apple can't be split");
+
+        let dirt = items.get("utility_dirt").unwrap();
+        inv.add_stack(dirt, dirt.stack_size * INVENTORY_SLOTS as u32, None);
+        assert!(!inv.split(1), "no free slot");
+    }
+
+    #[test]
+    fn taking_prefers_the_equipped_slot() {
+        let items = db();
+        let apple = items.get("food_apple").unwrap();
+        let mut inv = Inventory::default();
+        inv.slots[0] = Some(ItemStack { item: apple.id.clone(), count: 2, durability: None });
+        inv.slots[3] = Some(ItemStack { item: apple.id.clone(), count: 2, durability: None });
+        inv.hotbar = 3;
+        assert!(inv.take("food_apple"));
+        assert_eq!(inv.slots[3].as_ref().unwrap().count, 1);
+        assert!(inv.take("food_apple"));
+        assert!(inv.slots[3].is_none());
+        assert!(!inv.take("food_burger"));
     }
 }
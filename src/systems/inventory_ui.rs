@@ -0,0 +1,301 @@
+use bevy::prelude::*;
+use crate::data::items::ItemDb;
+use crate::states::GameState;
+use crate::systems::controls::Controls;
+use crate::systems::inventory::{DropItem, Inventory, ItemStack, HOTBAR_SLOTS, INVENTORY_SLOTS};
+
+const GRID_COLUMNS: usize = 6;
+const SLOT_PX: f32 = 64.0;
+const SLOT_GAP_PX: f32 = 4.0;
+const SLOT_COLOR: Color = Color::srgba(0.1, 0.1, 0.1, 0.85);
+const HOVER_COLOR: Color = Color::srgba(0.25, 0.25, 0.25, 0.9);
+const SELECTED_COLOR: Color = Color::srgba(0.45, 0.4, 0.15, 0.9);
+const DRAGGED_COLOR: Color = Color::srgba(0.2, 0.35, 0.5, 0.9);
+
+/// Whether the bag is open, and the slot being dragged out of
+#[derive(Resource, Debug, Default)]
+pub struct InventoryScreen {
+    pub open: bool,
+    pub dragging: Option<usize>,
+}
+
+#[derive(Component)]
+struct HotbarRoot;
+
+#[derive(Component)]
+struct InventoryPanel;
+
+/// The visible box of the bag; letting go of a drag over it keeps the stack
+#[derive(Component)]
+struct InventoryBox;
+
+/// A slot square, on the hotbar or in the bag
+#[derive(Component)]
+struct SlotNode(usize);
+
+#[derive(Component)]
+struct SlotLabel(usize);
+
+/// The item's `icon`, behind the label; hidden for items without one
+#[derive(Component)]
+struct SlotIcon(usize);
+
+pub struct InventoryUiPlugin;
+
+impl Plugin for InventoryUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InventoryScreen>()
+           .add_systems(OnEnter(GameState::Mission1), spawn_hotbar)
+           .add_systems(OnEnter(GameState::OpenWorld), spawn_hotbar)
+           .add_systems(OnExit(GameState::Mission1), despawn_inventory_ui)
+           .add_systems(OnExit(GameState::OpenWorld), despawn_inventory_ui)
+           .add_systems(Update, (toggle_inventory_system, slot_mouse_system, slot_refresh_system)
+               .chain()
+               .run_if(in_state(GameState::Mission1).or_else(in_state(GameState::OpenWorld))));
+    }
+}
+
+fn slot_bundle(index: usize) -> (ButtonBundle, SlotNode) {
+    (
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(SLOT_PX), height: Val::Px(SLOT_PX),
+                margin: UiRect::all(Val::Px(SLOT_GAP_PX / 2.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BackgroundColor(SLOT_COLOR),
+            ..default()
+        },
+        SlotNode(index),
+    )
+}
+
+fn spawn_slot(parent: &mut ChildBuilder, index: usize, font: &Handle<Font>) {
+    parent.spawn(slot_bundle(index)).with_children(|slot| {
+        slot.spawn((
+            ImageBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(80.0), height: Val::Percent(80.0),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            SlotIcon(index),
+        ));
+        slot.spawn((
+            TextBundle {
+                text: Text::from_section("", TextStyle { font: font.clone(), font_size: 13.0, color: Color::WHITE }),
+                ..default()
+            },
+            SlotLabel(index),
+        ));
+    });
+}
+
+/// Hotbar along the bottom of the screen
+fn spawn_hotbar(mut commands: Commands, asset_server: Res<AssetServer>, existing: Query<(), With<HotbarRoot>>) {
+    if !existing.is_empty() {
+        return;
+    }
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                bottom: Val::Px(12.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        },
+        HotbarRoot,
+    ))
+    .with_children(|bar| {
+        for i in 0..HOTBAR_SLOTS {
+            spawn_slot(bar, i, &font);
+        }
+    });
+}
+
+fn despawn_inventory_ui(
+    mut commands: Commands,
+    mut screen: ResMut<InventoryScreen>,
+    q: Query<Entity, Or<(With<HotbarRoot>, With<InventoryPanel>)>>,
+) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
+    *screen = InventoryScreen::default();
+}
+
+/// The open-inventory key shows and hides the bag (hotbar slots excluded)
+fn toggle_inventory_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    mut screen: ResMut<InventoryScreen>,
+    panel_q: Query<Entity, With<InventoryPanel>>,
+) {
+    if !keyboard.just_pressed(controls.open_inventory) {
+        return;
+    }
+    screen.open = !screen.open;
+    screen.dragging = None;
+    if !screen.open {
+        for e in &panel_q {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let width = GRID_COLUMNS as f32 * (SLOT_PX + SLOT_GAP_PX) + 24.0;
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0), height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+        InventoryPanel,
+    ))
+    .with_children(|root| {
+        root.spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Px(width),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(12.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+                ..default()
+            },
+            Interaction::default(),
+            InventoryBox,
+        ))
+        .with_children(|panel| {
+            panel.spawn(TextBundle {
+                text: Text::from_section(
+                    "INVENTORY\nDrag to move - right-click to split - drag out or [Q] to drop",
+                    TextStyle { font: font.clone(), font_size: 16.0, color: Color::WHITE },
+                ),
+                ..default()
+            });
+            panel.spawn(NodeBundle {
+                style: Style { flex_direction: FlexDirection::Row, flex_wrap: FlexWrap::Wrap, ..default() },
+                ..default()
+            })
+            .with_children(|grid| {
+                for i in HOTBAR_SLOTS..INVENTORY_SLOTS {
+                    spawn_slot(grid, i, &font);
+                }
+            });
+        });
+    });
+}
+
+/// Left-drag moves a stack (let go outside the bag and it goes on the
+/// ground), right-click splits one, Q drops the hovered stack
+fn slot_mouse_system(
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    items: Res<ItemDb>,
+    mut screen: ResMut<InventoryScreen>,
+    mut inv: ResMut<Inventory>,
+    slots: Query<(&SlotNode, &Interaction)>,
+    bag: Query<&Interaction, With<InventoryBox>>,
+    mut drops: EventWriter<DropItem>,
+) {
+    if !screen.open {
+        return;
+    }
+    let hovered = slots.iter().find(|(_, i)| **i != Interaction::None).map(|(s, _)| s.0);
+
+    if mouse.just_pressed(MouseButton::Left) {
+        screen.dragging = hovered.filter(|i| inv.slots[*i].is_some());
+    }
+    if mouse.just_released(MouseButton::Left) {
+        if let Some(from) = screen.dragging.take() {
+            match hovered {
+                Some(to) => inv.move_stack(from, to, &items),
+                // between slots, over the bag's own box, changes nothing
+                None if bag.iter().any(|i| *i != Interaction::None) => {}
+                None => {
+                    drops.send(DropItem { slot: from });
+                }
+            }
+        }
+    }
+    if let Some(slot) = hovered {
+        if mouse.just_pressed(MouseButton::Right) {
+            inv.split(slot);
+        }
+        if keyboard.just_pressed(KeyCode::KeyQ) && inv.slots[slot].is_some() {
+            drops.send(DropItem { slot });
+        }
+    }
+}
+
+fn slot_label(stack: &ItemStack, items: &ItemDb) -> String {
+    let name = items.get(&stack.item).map_or(stack.item.as_str(), |d| d.name.as_str());
+    match (stack.durability, stack.count) {
+        (Some(durability), _) => format!("{}\n({})", name, durability),
+        (None, 1) => name.to_string(),
+        (None, count) => format!("{}\nx{}", name, count),
+    }
+}
+
+fn slot_refresh_system(
+    asset_server: Res<AssetServer>,
+    inv: Res<Inventory>,
+    items: Res<ItemDb>,
+    screen: Res<InventoryScreen>,
+    mut slots: Query<(&SlotNode, &Interaction, &mut BackgroundColor)>,
+    mut labels: Query<(&SlotLabel, &mut Text)>,
+    mut icons: Query<(&SlotIcon, &mut UiImage, &mut Visibility)>,
+    added: Query<(), Added<SlotLabel>>,
+) {
+    for (slot, interaction, mut bg) in &mut slots {
+        let color = if screen.dragging == Some(slot.0) {
+            DRAGGED_COLOR
+        } else if slot.0 == inv.hotbar {
+            SELECTED_COLOR
+        } else if *interaction != Interaction::None {
+            HOVER_COLOR
+        } else {
+            SLOT_COLOR
+        };
+        if bg.0 != color {
+            bg.0 = color;
+        }
+    }
+    if !inv.is_changed() && added.is_empty() {
+        return;
+    }
+    for (label, mut text) in &mut labels {
+        let value = inv.slots.get(label.0).and_then(|s| s.as_ref()).map_or(String::new(), |s| slot_label(s, &items));
+        let value = if label.0 < HOTBAR_SLOTS { format!("{}\n{}", label.0 + 1, value) } else { value };
+        text.sections[0].value = value;
+    }
+    for (icon, mut image, mut visibility) in &mut icons {
+        let path = inv.slots.get(icon.0).and_then(|s| s.as_ref()).and_then(|s| items.get(&s.item)).and_then(|d| d.icon.as_deref());
+        match path {
+            Some(path) => {
+                image.texture = asset_server.load(path.to_string());
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
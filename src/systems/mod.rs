@@ -1,15 +1,27 @@
 pub mod inventory;
+pub mod inventory_ui;
 pub mod savegame;
 pub mod performance;
 pub mod settings;
 pub mod controls;
 pub mod ui;
 pub mod cars;
+pub mod caught;
+pub mod game_over;
+pub mod dialogue;
+pub mod trading;
+pub mod interact;
 
 pub use inventory::InventoryPlugin;
+pub use inventory_ui::InventoryUiPlugin;
 pub use savegame::SaveGamePlugin;
 pub use performance::PerformancePlugin;
 pub use settings::SettingsPlugin;
 pub use controls::ControlsPlugin;
 pub use ui::UiPlugin;
 pub use cars::CarsPlugin;
+pub use caught::CaughtPlugin;
+pub use game_over::GameOverPlugin;
+pub use dialogue::DialoguePlugin;
+pub use trading::TradingPlugin;
+pub use interact::InteractPlugin;
@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, Diagnostics};
+use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, DiagnosticsStore};
 
 pub struct PerformancePlugin;
 
@@ -25,8 +25,8 @@ fn spawn_fps_display(mut commands: Commands, asset_server: Res<AssetServer>) {
     ));
 }
 
-fn fps_update_system(diagnostics: Res<Diagnostics>, mut query: Query<&mut Text, With<FpsText>>) {
-    if let Some(fps) = diagnostics.get(bevy::diagnostic::FrameTimeDiagnosticsPlugin::FPS) {
+fn fps_update_system(diagnostics: Res<DiagnosticsStore>, mut query: Query<&mut Text, With<FpsText>>) {
+    if let Some(fps) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS) {
         if let Some(avg) = fps.average() {
             for mut text in &mut query {
                 text.sections[0].value = format!("FPS: {:.1}", avg);
@@ -1,25 +1,81 @@
 use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use crate::config::{SAVE_DIR, AUTOSAVE_FILE, DEFAULT_SAVE_EXT};
 use crate::utils::ensure_dir;
-use crate::systems::inventory::{Inventory, ItemType, ItemStack};
+use crate::data::items::{legacy_item_id, ItemDb};
+use crate::systems::inventory::{Inventory, ItemStack, HOTBAR_SLOTS};
+use crate::core::clock::WorldClock;
+use crate::core::reactions::Notoriety;
+use crate::core::sanity::Sanity;
+use crate::core::seed::WorldSeed;
+use crate::data::dialogue::NpcMemory;
+use crate::systems::dialogue::DialogueMemory;
+use crate::systems::trading::{TraderStock, Wallet};
+use crate::progression::GameProgress;
 
 #[derive(Serialize, Deserialize)]
 struct SaveData {
     player_pos: [f32; 3],
-    inventory: Vec<(ItemType, u32, Option<i32>)>,
+    /// Older saves: (item id, count, durability) with no slot order
+    #[serde(default, skip_serializing)]
+    inventory: Vec<(String, u32, Option<i32>)>,
+    /// The bag, slot by slot
+    #[serde(default)]
+    slots: Option<Vec<Option<ItemStack>>>,
+    #[serde(default)]
+    hotbar: usize,
     mission_progress: u8,
     health: f32,
     hunger: f32,
+    #[serde(default)]
+    world_seed: Option<u64>,
+    #[serde(default)]
+    times_caught: u32,
+    /// (day, hour) on the world clock
+    #[serde(default)]
+    clock: Option<(u32, f32)>,
+    /// What each NPC remembers of talking to the player, by `NpcId`
+    #[serde(default)]
+    npc_memory: HashMap<u32, NpcMemory>,
+    #[serde(default)]
+    coins: Option<u32>,
+    /// Trader id -> item id -> count left on the shelf
+    #[serde(default)]
+    trader_stock: Option<HashMap<String, HashMap<String, u32>>>,
+    #[serde(default)]
+    notoriety: f32,
+    #[serde(default)]
+    sanity: Option<f32>,
+    /// Lowest sanity reached so far; routes unlock on it
+    #[serde(default)]
+    lowest_sanity: Option<f32>,
+    /// Older saves: the item ids behind their single `Food` stack
+    #[serde(default, skip_serializing)]
+    meals: Vec<String>,
+}
+
+/// Write the current game to a save slot (file name under `SAVE_DIR`)
+#[derive(Event)]
+pub struct SaveGameRequest {
+    pub slot: String,
+}
+
+/// Restore the game from a save slot
+#[derive(Event)]
+pub struct LoadGameRequest {
+    pub slot: String,
 }
 
 pub struct SaveGamePlugin;
 
 impl Plugin for SaveGamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (save_input_system, load_input_system));
+        app.add_event::<SaveGameRequest>()
+           .add_event::<LoadGameRequest>()
+           .add_systems(Update, (save_input_system, save_game_system, load_game_system).chain());
         ensure_dir(SAVE_DIR);
     }
 }
@@ -32,56 +88,135 @@ fn save_path(name: &str) -> PathBuf {
     p
 }
 
+pub fn save_exists(slot: &str) -> bool {
+    save_path(slot).exists()
+}
+
 fn save_input_system(
-    keyboard: Res<Input<KeyCode>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut save_ev: EventWriter<SaveGameRequest>,
+    mut load_ev: EventWriter<LoadGameRequest>,
+) {
+    if keyboard.just_pressed(KeyCode::F5) {
+        save_ev.send(SaveGameRequest { slot: AUTOSAVE_FILE.to_string() });
+    }
+    if keyboard.just_pressed(KeyCode::F9) {
+        load_ev.send(LoadGameRequest { slot: AUTOSAVE_FILE.to_string() });
+    }
+}
+
+fn save_game_system(
+    mut requests: EventReader<SaveGameRequest>,
     player_q: Query<&Transform, With<crate::core::player::Ethan>>,
     inv: Res<Inventory>,
     stats: Option<Res<crate::core::player::PlayerStats>>,
+    seed: Option<Res<WorldSeed>>,
+    progress: Option<Res<GameProgress>>,
+    clock: Option<Res<WorldClock>>,
+    dialogue: Option<Res<DialogueMemory>>,
+    wallet: Option<Res<Wallet>>,
+    stock: Option<Res<TraderStock>>,
+    notoriety: Option<Res<Notoriety>>,
+    sanity: Option<Res<Sanity>>,
 ) {
-    if keyboard.just_pressed(KeyCode::F5) {
-        if let Ok(t) = player_q.get_single() {
-            let items = inv.slots.iter().map(|(k, s)| (k.clone(), s.count, s.durability)).collect::<Vec<_>>();
-            let data = SaveData {
-                player_pos: [t.translation.x, t.translation.y, t.translation.z],
-                inventory: items,
-                mission_progress: inv.mission_progress,
-                health: stats.as_ref().map(|s| s.health).unwrap_or(100.0),
-                hunger: stats.as_ref().map(|s| s.hunger).unwrap_or(100.0),
-            };
-            let path = save_path(AUTOSAVE_FILE);
-            if let Ok(json) = serde_json::to_string_pretty(&data) {
-                if fs::write(&path, json).is_ok() {
-                    info!("Saved game to {}", path.display());
-                } else {
-                    error!("Failed to write save file");
-                }
+    for req in requests.read() {
+        let Ok(t) = player_q.get_single() else {
+            warn!("Can't save to {}: no player", req.slot);
+            continue;
+        };
+        let data = SaveData {
+            player_pos: [t.translation.x, t.translation.y, t.translation.z],
+            inventory: Vec::new(),
+            slots: Some(inv.slots.clone()),
+            hotbar: inv.hotbar,
+            mission_progress: inv.mission_progress,
+            health: stats.as_ref().map(|s| s.health).unwrap_or(100.0),
+            hunger: stats.as_ref().map(|s| s.hunger).unwrap_or(100.0),
+            world_seed: seed.as_ref().map(|s| s.0),
+            times_caught: progress.as_ref().map(|p| p.times_caught).unwrap_or(0),
+            clock: clock.as_ref().map(|c| (c.day, c.hour)),
+            npc_memory: dialogue.as_ref().map(|d| d.npcs.clone()).unwrap_or_default(),
+            coins: wallet.as_ref().map(|w| w.coins),
+            trader_stock: stock.as_ref().map(|s| s.counts.clone()),
+            notoriety: notoriety.as_ref().map_or(0.0, |n| n.0),
+            sanity: sanity.as_ref().map(|s| s.value),
+            lowest_sanity: progress.as_ref().map(|p| p.lowest_sanity),
+            meals: Vec::new(),
+        };
+        let path = save_path(&req.slot);
+        if let Ok(json) = serde_json::to_string_pretty(&data) {
+            if fs::write(&path, json).is_ok() {
+                info!("Saved game to {}", path.display());
+            } else {
+                error!("Failed to write save file");
             }
         }
     }
 }
 
-fn load_input_system(
-    keyboard: Res<Input<KeyCode>>,
+fn load_game_system(
+    mut requests: EventReader<LoadGameRequest>,
     mut player_q: Query<&mut Transform, With<crate::core::player::Ethan>>,
     mut inv: ResMut<Inventory>,
-    mut commands: Commands,
+    items: Res<ItemDb>,
+    seed: Option<Res<WorldSeed>>,
+    stats: Option<ResMut<crate::core::player::PlayerStats>>,
+    progress: Option<ResMut<GameProgress>>,
+    clock: Option<ResMut<WorldClock>>,
+    dialogue: Option<ResMut<DialogueMemory>>,
+    economy: (Option<ResMut<Wallet>>, Option<ResMut<TraderStock>>),
+    notoriety: Option<ResMut<Notoriety>>,
+    sanity: Option<ResMut<Sanity>>,
 ) {
-    if keyboard.just_pressed(KeyCode::F9) {
-        let path = save_path(AUTOSAVE_FILE);
+    let (mut stats, mut progress, mut clock, mut dialogue) = (stats, progress, clock, dialogue);
+    let (mut wallet, mut stock) = economy;
+    let (mut notoriety, mut sanity) = (notoriety, sanity);
+    for req in requests.read() {
+        let path = save_path(&req.slot);
         if let Ok(json) = fs::read_to_string(&path) {
             if let Ok(data) = serde_json::from_str::<SaveData>(&json) {
+                // the world itself is generated at startup, so a different seed can't be swapped in live
+                if let (Some(saved), Some(current)) = (data.world_seed, seed.as_ref()) {
+                    if saved != current.0 {
+                        warn!("Save was made in world seed {} (current {}); restart with --seed {} to get its world back", saved, current.0, saved);
+                    }
+                }
                 if let Ok(mut t) = player_q.get_single_mut() {
                     t.translation = Vec3::new(data.player_pos[0], data.player_pos[1], data.player_pos[2]);
                 }
-                inv.slots.clear();
-                for (k, count, dur) in data.inventory {
-                    inv.slots.insert(k.clone(), ItemStack { item: k, count, durability: dur });
-                }
+                restore_inventory(&mut inv, &items, data.slots, data.inventory, &data.meals);
+                inv.hotbar = data.hotbar.min(HOTBAR_SLOTS - 1);
                 inv.mission_progress = data.mission_progress;
-                if let Some(mut stats) = commands.get_resource_mut::<crate::core::player::PlayerStats>() {
+                if let Some(stats) = stats.as_mut() {
                     stats.health = data.health;
                     stats.hunger = data.hunger;
                 }
+                // captures are a tally across retries; an older checkpoint must not undo them
+                if let Some(progress) = progress.as_mut() {
+                    progress.times_caught = progress.times_caught.max(data.times_caught);
+                    if let Some(lowest) = data.lowest_sanity {
+                        progress.lowest_sanity = progress.lowest_sanity.min(lowest);
+                    }
+                }
+                if let (Some(clock), Some((day, hour))) = (clock.as_mut(), data.clock) {
+                    clock.day = day;
+                    clock.hour = hour.rem_euclid(24.0);
+                }
+                if let Some(dialogue) = dialogue.as_mut() {
+                    dialogue.npcs = data.npc_memory;
+                }
+                if let (Some(wallet), Some(coins)) = (wallet.as_mut(), data.coins) {
+                    wallet.coins = coins;
+                }
+                if let (Some(stock), Some(counts)) = (stock.as_mut(), data.trader_stock) {
+                    stock.counts = counts;
+                }
+                if let Some(notoriety) = notoriety.as_mut() {
+                    notoriety.0 = data.notoriety.clamp(0.0, 1.0);
+                }
+                if let (Some(sanity), Some(value)) = (sanity.as_mut(), data.sanity) {
+                    sanity.value = value.clamp(0.0, 1.0);
+                }
                 info!("Loaded game from {}", path.display());
             }
         } else {
@@ -89,3 +224,54 @@ fn load_input_system(
         }
     }
 }
+
+/// Put the saved bag back, dropping items the database no longer has.
+/// Saves from before the slot grid only list (id, count, durability).
+fn restore_inventory(
+    inv: &mut Inventory,
+    items: &ItemDb,
+    slots: Option<Vec<Option<ItemStack>>>,
+    legacy: Vec<(String, u32, Option<i32>)>,
+    meals: &[String],
+) {
+    *inv = Inventory { mission_progress: inv.mission_progress, ..Inventory::default() };
+    let known = |id: &str| {
+        let def = items.get(id);
+        if def.is_none() {
+            warn!("Save holds unknown item '{}'; dropping it", id);
+        }
+        def
+    };
+
+    if let Some(slots) = slots {
+        let mut overflow = Vec::new();
+        for (i, stack) in slots.into_iter().enumerate() {
+            let Some(stack) = stack.filter(|s| known(&s.item).is_some()) else { continue };
+            match inv.slots.get_mut(i) {
+                Some(slot) => *slot = Some(stack),
+                None => overflow.push(stack),
+            }
+        }
+        for stack in overflow {
+            if let Some(def) = items.get(&stack.item) {
+                inv.add_stack(def, stack.count, stack.durability);
+            }
+        }
+        return;
+    }
+
+    for (id, count, durability) in legacy {
+        let stacks: Vec<(String, u32, Option<i32>)> = if id == "Food" && !meals.is_empty() {
+            meals.iter().map(|m| (m.clone(), 1, None)).collect()
+        } else {
+            // saves from before items.ron used the old item kind names
+            let id = legacy_item_id(&id).map(str::to_string).unwrap_or(id);
+            vec![(id, count, durability)]
+        };
+        for (id, count, durability) in stacks {
+            if let Some(def) = known(&id) {
+                inv.add_stack(def, count, durability);
+            }
+        }
+    }
+}
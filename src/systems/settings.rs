@@ -1,14 +1,24 @@
 use bevy::prelude::*;
 use serde::{Serialize, Deserialize};
 use std::fs;
-use crate::config::SAVE_DIR;
 use crate::utils::ensure_dir;
+use crate::core::clock::DEFAULT_DAY_LENGTH_SECS;
 
 #[derive(Resource, Serialize, Deserialize, Debug, Clone)]
 pub struct Settings {
     pub low_spec_mode: bool,
     pub shadow_quality: u8,
     pub max_particles: u32,
+    /// Fixed world seed; `None` picks a fresh one each run (see `core::seed`)
+    #[serde(default)]
+    pub world_seed: Option<u64>,
+    /// Real seconds per in-game day
+    #[serde(default = "default_day_length")]
+    pub day_length_secs: f32,
+}
+
+fn default_day_length() -> f32 {
+    DEFAULT_DAY_LENGTH_SECS
 }
 
 impl Default for Settings {
@@ -17,6 +27,8 @@ impl Default for Settings {
             low_spec_mode: false,
             shadow_quality: 2,
             max_particles: 200,
+            world_seed: None,
+            day_length_secs: DEFAULT_DAY_LENGTH_SECS,
         }
     }
 }
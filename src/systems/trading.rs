@@ -0,0 +1,313 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::core::clock::{HourChanged, WorldClock};
+use crate::core::npc_ai::{InConversation, Npc, NpcCategory, NpcRoutine};
+use crate::core::world::WorldSpawnInfo;
+use crate::data::items::{init_item_db, ItemDb};
+use crate::data::schedules::Activity;
+use crate::data::traders::{adjusted_price, load_traders, price_multiplier, TraderDef, BUY_BACK_RATIO, TRADERS_PATH};
+use crate::progression::{CaptureStanding, GameProgress};
+use crate::states::GameState;
+use crate::systems::controls::Controls;
+use crate::systems::inventory::Inventory;
+
+const STARTING_COINS: u32 = 25;
+/// Hour traders top their stock back up
+const RESTOCK_HOUR: u32 = 6;
+
+/// The player's money
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct Wallet {
+    pub coins: u32,
+}
+
+impl Default for Wallet {
+    fn default() -> Self {
+        Self { coins: STARTING_COINS }
+    }
+}
+
+/// Every trader as defined in `traders.ron`
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TraderCatalog(pub Vec<TraderDef>);
+
+/// How many of each item every trader has left: trader id -> item id -> count
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TraderStock {
+    pub counts: HashMap<String, HashMap<String, u32>>,
+}
+
+impl TraderStock {
+    /// Full shelves, as at the start of a new game
+    pub fn full(catalog: &[TraderDef]) -> Self {
+        let counts = catalog
+            .iter()
+            .map(|t| (t.id.clone(), t.stock.iter().map(|s| (s.item.clone(), s.max)).collect()))
+            .collect();
+        Self { counts }
+    }
+}
+
+/// Ask to browse a trader's stall; sent by the dialogue system
+#[derive(Event)]
+pub struct OpenTrade {
+    pub npc: Entity,
+}
+
+/// The stall the player is browsing
+#[derive(Resource, Debug)]
+pub struct ActiveTrade {
+    pub npc: Entity,
+    /// Index into `TraderCatalog`
+    pub trader: usize,
+    /// The trader is away from the stall (outside their schedule's hours)
+    pub closed: bool,
+    pub selected: usize,
+    pub message: String,
+}
+
+#[derive(Component)]
+struct TradePanel;
+
+#[derive(Component)]
+struct TradeText;
+
+pub struct TradingPlugin;
+
+impl Plugin for TradingPlugin {
+    fn build(&self, app: &mut App) {
+        let items = init_item_db(app);
+        let catalog = load_traders(TRADERS_PATH, &items).unwrap_or_else(|err| {
+            error!("Failed to load traders: {:?}", err);
+            Vec::new()
+        });
+        app.insert_resource(TraderStock::full(&catalog))
+           .insert_resource(TraderCatalog(catalog))
+           .init_resource::<Wallet>()
+           .add_event::<OpenTrade>()
+           .add_systems(Update, restock_system)
+           .add_systems(Update, (
+               open_trade_system,
+               trade_input_system,
+               trade_ui_system,
+           ).chain().run_if(in_state(GameState::Mission1).or_else(in_state(GameState::OpenWorld))))
+           // leaving play by any route (caught, game over, title) shuts the stall
+           .add_systems(OnExit(GameState::Mission1), close_trade)
+           .add_systems(OnExit(GameState::OpenWorld), close_trade);
+    }
+}
+
+fn open_trade_system(
+    mut commands: Commands,
+    mut events: EventReader<OpenTrade>,
+    catalog: Res<TraderCatalog>,
+    spawn_info: Res<WorldSpawnInfo>,
+    npc_q: Query<(&Npc, &NpcRoutine, &Transform)>,
+) {
+    let Some(ev) = events.read().last() else { return };
+    let Ok((npc, routine, tf)) = npc_q.get(ev.npc) else { return };
+    if npc.category != NpcCategory::Trader {
+        return;
+    }
+
+    // a trader belongs to the stall they work at (or, off shift, the one nearest them)
+    let anchor = routine.work.unwrap_or(tf.translation);
+    let stall = spawn_info
+        .market_spots
+        .iter()
+        .enumerate()
+        .min_by(|a, b| a.1.distance_squared(anchor).total_cmp(&b.1.distance_squared(anchor)))
+        .map(|(i, _)| i);
+    let Some(trader) = stall.and_then(|s| catalog.0.iter().position(|t| t.stall == s)) else {
+        info!("This trader has nothing to sell.");
+        return;
+    };
+
+    let closed = routine.activity != Some(Activity::Stall);
+    commands.entity(ev.npc).insert(InConversation);
+    commands.insert_resource(ActiveTrade { npc: ev.npc, trader, closed, selected: 0, message: String::new() });
+}
+
+fn trade_input_system(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    catalog: Res<TraderCatalog>,
+    clock: Res<WorldClock>,
+    progress: Option<Res<GameProgress>>,
+    active: Option<ResMut<ActiveTrade>>,
+    mut stock: ResMut<TraderStock>,
+    mut wallet: ResMut<Wallet>,
+    mut inventory: ResMut<Inventory>,
+    items: Res<ItemDb>,
+) {
+    let Some(mut active) = active else { return };
+    if keyboard.just_pressed(KeyCode::Escape) {
+        end_trade(&mut commands, &active);
+        return;
+    }
+    let trader = &catalog.0[active.trader];
+    if active.closed || trader.stock.is_empty() {
+        return;
+    }
+
+    let count = trader.stock.len();
+    if keyboard.just_pressed(controls.move_back) || keyboard.just_pressed(KeyCode::ArrowDown) {
+        active.selected = (active.selected + 1) % count;
+    }
+    if keyboard.just_pressed(controls.move_forward) || keyboard.just_pressed(KeyCode::ArrowUp) {
+        active.selected = (active.selected + count - 1) % count;
+    }
+
+    // the interact press that opened the stall must not also buy
+    let buy = !active.is_added() && (keyboard.just_pressed(controls.interact) || keyboard.just_pressed(KeyCode::Enter));
+    let sell = keyboard.just_pressed(KeyCode::KeyX);
+    if !buy && !sell {
+        return;
+    }
+
+    let entry = &trader.stock[active.selected];
+    let Some(def) = items.get(&entry.item) else { return };
+    let standing = progress.as_ref().map_or(CaptureStanding::Unseen, |p| p.capture_standing());
+    let multiplier = price_multiplier(clock.hour, standing);
+    let on_shelf = stock.counts.entry(trader.id.clone()).or_default().entry(entry.item.clone()).or_insert(0);
+    let held = inventory.count(&def.id);
+
+    active.message = if buy {
+        let price = adjusted_price(entry.price, multiplier);
+        if *on_shelf == 0 {
+            format!("{} is sold out.", def.name)
+        } else if wallet.coins < price {
+            format!("You need {} coins for that.", price)
+        } else if !inventory.can_fit(def) {
+            format!("You can't carry more {}.", def.name)
+        } else {
+            wallet.coins -= price;
+            *on_shelf -= 1;
+            inventory.add(def);
+            format!("Bought {} for {} coins.", def.name, price)
+        }
+    } else {
+        let price = adjusted_price(entry.price, multiplier * BUY_BACK_RATIO);
+        if held == 0 {
+            format!("You don't have any {}.", def.name)
+        } else {
+            wallet.coins += price;
+            *on_shelf += 1;
+            inventory.take(&def.id);
+            format!("Sold {} for {} coins.", def.name, price)
+        }
+    };
+}
+
+fn end_trade(commands: &mut Commands, active: &ActiveTrade) {
+    if let Some(mut entity) = commands.get_entity(active.npc) {
+        entity.remove::<InConversation>();
+    }
+    commands.remove_resource::<ActiveTrade>();
+}
+
+fn close_trade(mut commands: Commands, active: Option<Res<ActiveTrade>>, panel_q: Query<Entity, With<TradePanel>>) {
+    if let Some(active) = active {
+        end_trade(&mut commands, &active);
+    }
+    for e in &panel_q {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+/// Each morning traders top their shelves back up
+fn restock_system(mut hours: EventReader<HourChanged>, catalog: Res<TraderCatalog>, mut stock: ResMut<TraderStock>) {
+    if !hours.read().any(|h| h.hour == RESTOCK_HOUR) {
+        return;
+    }
+    for trader in &catalog.0 {
+        let shelves = stock.counts.entry(trader.id.clone()).or_default();
+        for entry in &trader.stock {
+            let count = shelves.entry(entry.item.clone()).or_insert(0);
+            *count = (*count + entry.restock).min(entry.max).max(*count);
+        }
+    }
+    info!("Traders restocked.");
+}
+
+fn trade_ui_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    catalog: Res<TraderCatalog>,
+    stock: Res<TraderStock>,
+    wallet: Res<Wallet>,
+    inventory: Res<Inventory>,
+    items: Res<ItemDb>,
+    clock: Res<WorldClock>,
+    progress: Option<Res<GameProgress>>,
+    active: Option<Res<ActiveTrade>>,
+    panel_q: Query<Entity, With<TradePanel>>,
+    mut text_q: Query<&mut Text, With<TradeText>>,
+) {
+    let Some(active) = active else {
+        for e in &panel_q {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    };
+    let trader = &catalog.0[active.trader];
+
+    let mut body = String::new();
+    if active.closed {
+        body.push_str("The stall is shut. Come back during market hours.");
+    } else {
+        let standing = progress.as_ref().map_or(CaptureStanding::Unseen, |p| p.capture_standing());
+        let multiplier = price_multiplier(clock.hour, standing);
+        for (i, entry) in trader.stock.iter().enumerate() {
+            let Some(def) = items.get(&entry.item) else { continue };
+            let on_shelf = stock.counts.get(&trader.id).and_then(|s| s.get(&entry.item)).copied().unwrap_or(0);
+            let held = inventory.count(&def.id);
+            let marker = if i == active.selected { ">" } else { " " };
+            body.push_str(&format!(
+                "{} {:<12} buy {:>3}  sell {:>3}  stock {:>2}  have {}\n",
+                marker,
+                def.name,
+                adjusted_price(entry.price, multiplier),
+                adjusted_price(entry.price, multiplier * BUY_BACK_RATIO),
+                on_shelf,
+                held
+            ));
+        }
+        body.push_str("\n[E] Buy   [X] Sell   [Esc] Leave");
+    }
+    if !active.message.is_empty() {
+        body.push_str(&format!("\n{}", active.message));
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let sections = vec![
+        TextSection::new(format!("{}    Coins: {}\n", trader.name, wallet.coins), TextStyle { font: font.clone(), font_size: 22.0, color: Color::srgb(1.0, 0.85, 0.4) }),
+        TextSection::new(body, TextStyle { font, font_size: 20.0, color: Color::WHITE }),
+    ];
+
+    if let Ok(mut text) = text_q.get_single_mut() {
+        text.sections = sections;
+        return;
+    }
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(20.0),
+                top: Val::Percent(20.0),
+                width: Val::Percent(60.0), height: Val::Auto,
+                padding: UiRect::all(Val::Px(14.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.05, 0.05, 0.07, 0.9)),
+            ..default()
+        },
+        TradePanel,
+    ))
+    .with_children(|parent| {
+        parent.spawn((TextBundle { text: Text::from_sections(sections), ..default() }, TradeText));
+    });
+}
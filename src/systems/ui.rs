@@ -1,8 +1,11 @@
+use bevy::color::palettes::css;
 use bevy::prelude::*;
 use crate::states::GameState;
-use crate::core::player::PlayerStats;
-use crate::systems::inventory::Inventory;
-use bevy::ui::Size;
+use crate::core::damage::DamageLog;
+use crate::core::food::{Eating, StatusEffects};
+use crate::core::hallucinations::UiGlitch;
+use crate::core::player::{Ethan, PlayerStats};
+use crate::data::items::ItemDb;
 
 
 /// UI plugin: Title + HUD (health left, food right)
@@ -13,14 +16,15 @@ impl Plugin for UiPlugin {
         app.add_systems(Startup, spawn_title_ui)
            .add_systems(Update, title_input_system.run_if(in_state(GameState::Title)))
            .add_systems(OnEnter(GameState::Mission1), spawn_hud)
-           .add_systems(Update, hud_fill_update_system.run_if(in_state(GameState::Mission1)));
+           .add_systems(OnExit(GameState::Mission1), despawn_hud)
+           .add_systems(Update, (hud_fill_update_system, damage_log_hud_system, food_status_hud_system).run_if(in_state(GameState::Mission1)));
     }
 }
 
 fn spawn_title_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
         NodeBundle {
-            style: Style { size: Size::width(Val::Percent(100.0)), justify_content: JustifyContent::Center, align_items: AlignItems::Center, ..default() },
+            style: Style { width: Val::Percent(100.0), justify_content: JustifyContent::Center, align_items: AlignItems::Center, ..default() },
             ..default()
         },
         Name::new("TitleRoot"),
@@ -33,8 +37,8 @@ fn spawn_title_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
     });
 }
 
-fn title_input_system(mut next_state: ResMut<NextState<GameState>>, keys: Res<Input<KeyCode>>) {
-    if keys.just_pressed(KeyCode::Return) {
+fn title_input_system(mut next_state: ResMut<NextState<GameState>>, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::Enter) {
         next_state.set(GameState::Mission1);
     }
 }
@@ -42,7 +46,11 @@ fn title_input_system(mut next_state: ResMut<NextState<GameState>>, keys: Res<In
 // UI marker components for querying/updating
 #[derive(Component)] struct HealthHudTag;
 #[derive(Component)] struct FoodHudTag;
+#[derive(Component)] struct StaminaHudTag;
+#[derive(Component)] struct DamageLogHudTag;
+#[derive(Component)] struct FoodStatusHudTag;
 #[derive(Component)] struct HealthFill;
+#[derive(Component)] struct StaminaFill;
 #[derive(Component)] struct FoodFill;
 
 /// Spawn HUD nodes (health bar left, food bar right)
@@ -54,7 +62,7 @@ fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
                 position_type: PositionType::Absolute,
                 left: Val::Px(12.0),
                 top: Val::Px(12.0),
-                size: Size::new(Val::Px(220.0), Val::Px(36.0)),
+                width: Val::Px(220.0), height: Val::Px(36.0),
                 ..default()
             },
             background_color: BackgroundColor(Color::NONE),
@@ -65,16 +73,16 @@ fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
     .with_children(|parent| {
         // background
         parent.spawn(NodeBundle {
-            style: Style { size: Size::new(Val::Percent(100.0), Val::Percent(100.0)), ..default() },
-            background_color: BackgroundColor(Color::rgba(0.07, 0.07, 0.07, 0.8)),
+            style: Style { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+            background_color: BackgroundColor(Color::srgba(0.07, 0.07, 0.07, 0.8)),
             ..default()
         })
         .with_children(|b| {
             // fill (green) - we will update its width dynamically
             b.spawn((
                 NodeBundle {
-                    style: Style { size: Size::new(Val::Percent(100.0), Val::Percent(100.0)), ..default() },
-                    background_color: BackgroundColor(Color::GREEN),
+                    style: Style { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+                    background_color: BackgroundColor(Color::from(css::GREEN)),
                     ..default()
                 },
                 HealthFill,
@@ -82,6 +90,42 @@ fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
     });
 
+    // Stamina bar (thin, under health)
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(12.0),
+                top: Val::Px(52.0),
+                width: Val::Px(220.0), height: Val::Px(8.0),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0.07, 0.07, 0.07, 0.8)),
+            ..default()
+        },
+        StaminaHudTag,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            NodeBundle {
+                style: Style { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+                background_color: BackgroundColor(Color::srgb(0.95, 0.9, 0.3)),
+                ..default()
+            },
+            StaminaFill,
+        ));
+    });
+
+    // Damage log (under stamina, newest first)
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section("", TextStyle { font: asset_server.load("fonts/FiraSans-Bold.ttf"), font_size: 16.0, color: Color::srgb(0.95, 0.45, 0.4) }),
+            style: Style { position_type: PositionType::Absolute, left: Val::Px(12.0), top: Val::Px(68.0), ..default() },
+            ..default()
+        },
+        DamageLogHudTag,
+    ));
+
     // Food bar (right)
     commands.spawn((
         NodeBundle {
@@ -89,7 +133,7 @@ fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
                 position_type: PositionType::Absolute,
                 right: Val::Px(12.0),
                 top: Val::Px(12.0),
-                size: Size::new(Val::Px(160.0), Val::Px(24.0)),
+                width: Val::Px(160.0), height: Val::Px(24.0),
                 ..default()
             },
             background_color: BackgroundColor(Color::NONE),
@@ -99,43 +143,128 @@ fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
     ))
     .with_children(|parent| {
         parent.spawn(NodeBundle {
-            style: Style { size: Size::new(Val::Percent(100.0), Val::Percent(100.0)), ..default() },
-            background_color: BackgroundColor(Color::rgba(0.07, 0.07, 0.07, 0.8)),
+            style: Style { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+            background_color: BackgroundColor(Color::srgba(0.07, 0.07, 0.07, 0.8)),
             ..default()
         })
         .with_children(|b| {
             b.spawn((
                 NodeBundle {
-                    style: Style { size: Size::new(Val::Percent(100.0), Val::Percent(100.0)), ..default() },
-                    background_color: BackgroundColor(Color::rgb(0.95, 0.6, 0.2)),
+                    style: Style { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+                    background_color: BackgroundColor(Color::srgb(0.95, 0.6, 0.2)),
                     ..default()
                 },
                 FoodFill,
             ));
         });
     });
+
+    // Eating / food effects (under the food bar)
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section("", TextStyle { font: asset_server.load("fonts/FiraSans-Bold.ttf"), font_size: 16.0, color: Color::srgb(0.95, 0.8, 0.5) }),
+            style: Style { position_type: PositionType::Absolute, right: Val::Px(12.0), top: Val::Px(42.0), ..default() },
+            ..default()
+        },
+        FoodStatusHudTag,
+    ));
+}
+
+fn despawn_hud(mut commands: Commands, q: Query<Entity, Or<(With<HealthHudTag>, With<StaminaHudTag>, With<DamageLogHudTag>, With<FoodHudTag>, With<FoodStatusHudTag>)>>) {
+    for e in &q {
+        commands.entity(e).despawn_recursive();
+    }
 }
 
-/// Update the health/food fill widths using the PlayerStats and Inventory
+/// Update the health/food fill widths using the PlayerStats
 fn hud_fill_update_system(
+    time: Res<Time>,
     stats: Option<Res<PlayerStats>>,
-    inv: Option<Res<Inventory>>,
+    glitch: Option<Res<UiGlitch>>,
     mut health_query: Query<&mut Style, With<HealthFill>>,
-    mut food_query: Query<&mut Style, With<FoodFill>>,
+    mut food_query: Query<&mut Style, (With<FoodFill>, Without<HealthFill>)>,
+    mut stamina_query: Query<&mut Style, (With<StaminaFill>, Without<HealthFill>, Without<FoodFill>)>,
 ) {
-    let (health_pct, food_pct) = if let Some(s) = stats {
+    let (health_pct, food_pct, stamina_pct) = if let Some(s) = stats {
         let s = s.clone();
         let hp = if s.max_health > 0.0 { (s.health / s.max_health).clamp(0.0, 1.0) * 100.0 } else { 0.0 };
         let fp = (s.hunger / s.max_hunger).clamp(0.0, 1.0) * 100.0;
-        (hp, fp)
+        let sp = if s.max_stamina > 0.0 { (s.stamina / s.max_stamina).clamp(0.0, 1.0) * 100.0 } else { 0.0 };
+        (hp, fp, sp)
     } else {
-        (100.0, 100.0)
+        (100.0, 100.0, 100.0)
+    };
+    // a hallucinating Ethan can't trust his own readings
+    let (health_pct, food_pct, stamina_pct) = if glitch.is_some_and(|g| g.remaining > 0.0) {
+        let t = time.elapsed_seconds();
+        let flicker = |phase: f32| (((t * 31.0 + phase).sin() * 43758.5).fract().abs() * 130.0).min(100.0);
+        (flicker(0.0), flicker(1.7), flicker(3.1))
+    } else {
+        (health_pct, food_pct, stamina_pct)
     };
 
     for mut style in &mut health_query {
-        style.size.width = Val::Percent(health_pct);
+        style.width = Val::Percent(health_pct);
     }
     for mut style in &mut food_query {
-        style.size.width = Val::Percent(food_pct);
+        style.width = Val::Percent(food_pct);
+    }
+    for mut style in &mut stamina_query {
+        style.width = Val::Percent(stamina_pct);
+    }
+}
+
+/// Lines of the damage log shown on the HUD
+const DAMAGE_LOG_LINES: usize = 4;
+/// A log line stays on the HUD this long after the hit
+const DAMAGE_LOG_SECS: f32 = 6.0;
+
+/// List the last few hits under the bars; the full log stays in `DamageLog`
+fn damage_log_hud_system(
+    time: Res<Time>,
+    log: Res<DamageLog>,
+    mut text_q: Query<&mut Text, With<DamageLogHudTag>>,
+) {
+    let now = time.elapsed_seconds();
+    let lines: Vec<String> = log
+        .entries
+        .iter()
+        .rev()
+        .take_while(|r| now - r.at < DAMAGE_LOG_SECS)
+        .take(DAMAGE_LOG_LINES)
+        .map(|r| format!("-{:.0}  {}", r.amount.max(1.0), r.source.label()))
+        .collect();
+    let value = lines.join("\n");
+    for mut text in &mut text_q {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+/// What Ethan is eating, and how the last meal is sitting
+fn food_status_hud_system(
+    effects: Res<StatusEffects>,
+    items: Res<ItemDb>,
+    eating_q: Query<&Eating, With<Ethan>>,
+    mut text_q: Query<&mut Text, With<FoodStatusHudTag>>,
+) {
+    let mut lines = Vec::new();
+    if let Ok(eating) = eating_q.get_single() {
+        let name = items.get(&eating.item).map_or(eating.item.as_str(), |d| d.name.as_str());
+        let pct = (1.0 - eating.remaining / eating.total.max(0.01)).clamp(0.0, 1.0) * 100.0;
+        lines.push(format!("Eating {} {:.0}%", name, pct));
+    }
+    if effects.well_fed > 0.0 {
+        lines.push(format!("Well fed {:.0}s", effects.well_fed));
+    }
+    if effects.nauseous() {
+        lines.push(format!("Nauseous {:.0}s", effects.nausea));
+    }
+    let value = lines.join("\n");
+    for mut text in &mut text_q {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
     }
 }